@@ -9,6 +9,10 @@ main() {
 
     cargo fmt --all -- --check
     cargo clippy --all-targets --all-features --examples --tests
+    # each http client alone, without archive or compression support
+    for features in default-tls rustls-tls ureq; do
+        cargo clippy --all-targets --no-default-features --features $features -- -D warnings
+    done
 
     if [ ! -z $DISABLE_TESTS ]; then
         return
@@ -0,0 +1,121 @@
+/*!
+On-disk cache of release api responses
+
+Cached responses are revalidated with conditional requests, which GitHub answers
+with `304 Not Modified` without counting them against the api rate limit.
+*/
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::json;
+
+use crate::errors::*;
+
+/// Default cache location in the platform cache directory, e.g.
+/// `~/.cache/<app_name>/self_update_api.json` on linux
+pub(crate) fn default_path(app_name: &str) -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join(app_name).join("self_update_api.json"))
+}
+
+/// A response body along with the headers needed to revalidate it
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct CachedResponse {
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+    pub(crate) body: serde_json::Value,
+}
+
+/// Responses stored by url in a single json file
+#[derive(Clone, Debug)]
+pub(crate) struct ApiCache {
+    path: PathBuf,
+}
+
+impl ApiCache {
+    pub(crate) fn at_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The stored response for `url`, if any
+    pub(crate) fn load(&self, url: &str) -> Option<CachedResponse> {
+        let entry = &self.entries()?[url];
+        if entry.is_null() {
+            return None;
+        }
+        let header = |name: &str| entry[name].as_str().map(String::from);
+        Some(CachedResponse {
+            etag: header("etag"),
+            last_modified: header("last_modified"),
+            body: entry["body"].clone(),
+        })
+    }
+
+    /// Store `response` for `url`, replacing any previous one
+    ///
+    /// * Errors:
+    ///     * Io - Writing the cache file
+    pub(crate) fn store(&self, url: &str, response: &CachedResponse) -> Result<()> {
+        let mut entries = self
+            .entries()
+            .filter(|e| e.is_object())
+            .unwrap_or_else(|| json!({}));
+        entries[url] = json!({
+            "etag": response.etag,
+            "last_modified": response.last_modified,
+            "body": response.body,
+        });
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec(&entries)?)?;
+        Ok(())
+    }
+
+    /// Remove all stored responses
+    pub(crate) fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => Ok(r?),
+        }
+    }
+
+    fn entries(&self) -> Option<serde_json::Value> {
+        let content = fs::read(&self.path).ok()?;
+        serde_json::from_slice(&content).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn store_and_load() {
+        let tmp = TempDir::new("self_update_api_cache").expect("tempdir fail");
+        let cache = ApiCache::at_path(tmp.path().join("app").join("api.json"));
+        let url = "https://api.github.com/repos/o/r/releases/latest";
+        assert_eq!(None, cache.load(url));
+
+        let response = CachedResponse {
+            etag: Some("W/\"abc\"".into()),
+            last_modified: None,
+            body: json!({"tag_name": "v1.0.0"}),
+        };
+        cache.store(url, &response).unwrap();
+        cache
+            .store(
+                "https://api.github.com/repos/o/r/releases/tags/v0.9.0",
+                &response,
+            )
+            .unwrap();
+        assert_eq!(Some(response), cache.load(url));
+
+        cache.clear().unwrap();
+        assert_eq!(None, cache.load(url));
+        cache.clear().unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+/*!
+Carry over file metadata from a replaced executable to its replacement
+*/
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use crate::errors::*;
+
+/// Extended attributes copied from the replaced executable
+#[cfg(target_os = "linux")]
+const PRESERVED_XATTRS: &[&str] = &[
+    // file capabilities set with `setcap`, e.g. `cap_net_bind_service`
+    "security.capability",
+];
+
+/// Extended attribute holding the SELinux security context
+#[cfg(target_os = "linux")]
+const SELINUX_XATTR: &str = "security.selinux";
+
+/// Give `new` the owner, group, file capabilities and SELinux context of `old`.
+///
+/// Ownership is changed first since that clears capabilities. Changing the owner
+/// requires privileges the updating user may not have, in which case the new file
+/// is simply left owned by the updating user.
+///
+/// Returns `false` if `old` has an SELinux context that couldn't be copied, in which
+/// case the installed file should be relabeled with `restore_context`.
+///
+/// * Errors:
+///     * Io - `old` had capabilities that couldn't be applied to `new`
+pub(crate) fn preserve(old: &Path, new: &Path) -> Result<bool> {
+    let meta = fs::metadata(old)?;
+    preserve_owner(&meta, new);
+    preserve_xattrs(old, new)?;
+    Ok(preserve_context(old, new))
+}
+
+/// Reset the SELinux context of `path` to the policy default, like `restorecon`
+///
+/// Best effort, nothing is done if `restorecon` isn't available.
+pub(crate) fn restore_context(path: &Path) {
+    if cfg!(target_os = "linux") {
+        let _ = process::Command::new("restorecon")
+            .arg(path)
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .status();
+    }
+}
+
+#[cfg(unix)]
+fn preserve_owner(old: &fs::Metadata, new: &Path) {
+    use std::os::unix::fs::MetadataExt;
+    if let Ok(current) = fs::metadata(new) {
+        if (current.uid(), current.gid()) != (old.uid(), old.gid()) {
+            let _ = std::os::unix::fs::chown(new, Some(old.uid()), Some(old.gid()));
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn preserve_owner(_old: &fs::Metadata, _new: &Path) {}
+
+#[cfg(target_os = "linux")]
+fn preserve_xattrs(old: &Path, new: &Path) -> Result<()> {
+    for name in PRESERVED_XATTRS {
+        if let Some(value) = xattr::get(old, name)? {
+            xattr::set(new, name, &value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn preserve_xattrs(_old: &Path, _new: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Copy the SELinux context, `false` if `old` has one that couldn't be copied
+#[cfg(target_os = "linux")]
+fn preserve_context(old: &Path, new: &Path) -> bool {
+    match xattr::get(old, SELINUX_XATTR) {
+        Ok(Some(context)) => xattr::set(new, SELINUX_XATTR, &context).is_ok(),
+        Ok(None) => true,
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn preserve_context(_old: &Path, _new: &Path) -> bool {
+    true
+}
+
+#[cfg(target_os = "linux")]
+mod xattr {
+    use std::ffi::CString;
+    use std::io;
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    extern "C" {
+        fn getxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *mut c_void,
+            size: usize,
+        ) -> isize;
+        fn setxattr(
+            path: *const c_char,
+            name: *const c_char,
+            value: *const c_void,
+            size: usize,
+            flags: c_int,
+        ) -> c_int;
+    }
+
+    fn c_path(path: &Path) -> io::Result<CString> {
+        CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Value of the attribute `name` of `path`, `None` if it isn't set or not supported
+    pub(super) fn get(path: &Path, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let path = c_path(path)?;
+        let name = CString::new(name).expect("attribute names are static");
+        let size = unsafe { getxattr(path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) };
+        if size < 0 {
+            return missing_or_err();
+        }
+        let mut value = vec![0u8; size as usize];
+        let size = unsafe {
+            getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr() as *mut c_void,
+                value.len(),
+            )
+        };
+        if size < 0 {
+            return missing_or_err();
+        }
+        value.truncate(size as usize);
+        Ok(Some(value))
+    }
+
+    pub(super) fn set(path: &Path, name: &str, value: &[u8]) -> io::Result<()> {
+        let path = c_path(path)?;
+        let name = CString::new(name).expect("attribute names are static");
+        let res = unsafe {
+            setxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_ptr() as *const c_void,
+                value.len(),
+                0,
+            )
+        };
+        if res != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn missing_or_err() -> io::Result<Option<Vec<u8>>> {
+        const ENODATA: i32 = 61;
+        const ENOTSUP: i32 = 95;
+        let e = io::Error::last_os_error();
+        match e.raw_os_error() {
+            Some(ENODATA) | Some(ENOTSUP) => Ok(None),
+            _ => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn preserve_plain_files() {
+        let tmp = TempDir::new("self_update_attributes").expect("tempdir fail");
+        let old = tmp.path().join("old");
+        let new = tmp.path().join("new");
+        fs::write(&old, "old").unwrap();
+        fs::write(&new, "new").unwrap();
+        assert!(preserve(&old, &new).expect("preserve fail"));
+        assert!(preserve(&tmp.path().join("missing"), &new).is_err());
+    }
+}
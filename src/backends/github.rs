@@ -3,21 +3,119 @@ GitHub releases
 */
 use std::env;
 use std::fs;
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use hyper_old_types::header::{LinkValue, RelationType};
 use indicatif::ProgressStyle;
-use reqwest;
+use regex::Regex;
 use serde_json;
 use tempdir;
 
-use crate::{confirm, errors::*, get_target, version, Download, Extract, Move, Status};
+use crate::api_cache::{self, ApiCache, CachedResponse};
+use crate::attributes;
+use crate::backup;
+use crate::blocks::BlockIndex;
+use crate::check::CheckCache;
+use crate::checksum;
+use crate::downloader::Downloader;
+use crate::extractor::{self, Extractor};
+use crate::http::{self, Deadline, HttpConfig, Response, DEFAULT_USER_AGENT};
+use crate::lock::{self, UpdateLock};
+use crate::messages::{fill, Messages};
+use crate::overrides::{self, EnvOverrides};
+use crate::package_manager::{self, Homebrew, PackageManager};
+use crate::policy::Policy;
+use crate::progress::{DownloadProgress, Phase, ProgressHook, DEFAULT_REPORT_INTERVAL};
+use crate::service::Service;
+use crate::telemetry::{AttemptHook, Outcome, UpdateRecord};
+use crate::{
+    apply_patch, check_space, check_writable, choose, cleanup_old_exe, confirm, delete_on_reboot,
+    detect_archive, errors::*, estimated_extracted_size, exe_id, get_target, get_target_candidates,
+    is_current_exe, is_executing, is_in_path, is_rosetta_translated, musl_equivalent, old_exe_path,
+    processes_running, resolve_symlink, restart_elevated, set_executable, version, ArchiveKind,
+    Callback, Download, Move, NonInteractive, PromptTimeout, Status, ELEVATED_ENV_VAR,
+};
+
+/// Release api used unless configured otherwise
+const DEFAULT_API_URL: &str = "https://api.github.com";
+
+/// Releases requested per page of a listing, the maximum allowed by the api
+const RELEASES_PER_PAGE: u32 = 100;
+
+/// Send a GET request to the GitHub api, converting unsuccessful responses into errors
+///
+/// * Errors:
+///     * Network - request failure
+///     * RateLimited - 403/429 responses with an exhausted rate limit
+///     * HttpStatus - any other unsuccessful status
+fn api_get(http: &HttpConfig, url: &str) -> Result<Response> {
+    wait_on_rate_limit(http, || check_api_response(http.get(url)?, url))
+}
+
+/// Make an api request with `send`, retrying once after waiting for an exhausted
+/// rate limit to reset if that's within the configured `wait_for_rate_limit`
+fn wait_on_rate_limit<F>(http: &HttpConfig, send: F) -> Result<Response>
+where
+    F: Fn() -> Result<Response>,
+{
+    match send() {
+        Err(Error::RateLimited { reset_at }) => match http.rate_limit_delay(reset_at) {
+            Some(delay) => {
+                thread::sleep(delay);
+                send()
+            }
+            None => Err(Error::RateLimited { reset_at }),
+        },
+        result => result,
+    }
+}
+
+/// Authenticate requests to `api.github.com` with the token of the environment,
+/// unless a token is set. Other hosts, e.g. GitHub Enterprise, aren't sent it.
+fn env_auth_token(http: &mut HttpConfig, api_url: &str) {
+    if http.auth_token.is_none() && api_url == DEFAULT_API_URL {
+        http.auth_token = overrides::github_token();
+    }
+}
+
+/// Convert an unsuccessful api response into an error, see `api_get`
+fn check_api_response(resp: Response, url: &str) -> Result<Response> {
+    if resp.is_success() {
+        return Ok(resp);
+    }
+    let status = resp.status();
+    let header_u64 = |name: &str| resp.header(name).and_then(|v| v.parse::<u64>().ok());
+    let limit_exhausted = header_u64("x-ratelimit-remaining") == Some(0);
+    if status == 429 || (status == 403 && limit_exhausted) {
+        // secondary rate limits only say how many seconds to wait
+        let retry_after = header_u64("retry-after").map(|secs| http::unix_now() + secs);
+        return Err(Error::RateLimited {
+            reset_at: header_u64("x-ratelimit-reset").or(retry_after),
+        });
+    }
+    Err(Error::HttpStatus {
+        url: url.to_owned(),
+        status,
+    })
+}
 
 /// GitHub release-asset information
 #[derive(Clone, Debug)]
 pub struct ReleaseAsset {
     pub download_url: String,
+    /// Url of the asset in the release api, which also serves the asset's content to
+    /// authenticated requests, e.g. for private repos
+    pub api_url: Option<String>,
     pub name: String,
+    /// Size in bytes, if provided by the api
+    pub size: Option<u64>,
+    /// Digest of the content computed by GitHub, e.g. `sha256:<hex>`, if provided by
+    /// the api
+    pub digest: Option<String>,
 }
 impl ReleaseAsset {
     /// Parse a release-asset json object
@@ -33,7 +131,10 @@ impl ReleaseAsset {
             .ok_or_else(|| format_err!(Error::Release, "Asset missing `name`"))?;
         Ok(ReleaseAsset {
             download_url: download_url.to_owned(),
+            api_url: asset["url"].as_str().map(str::to_owned),
             name: name.to_owned(),
+            size: asset["size"].as_u64(),
+            digest: asset["digest"].as_str().map(str::to_owned),
         })
     }
 }
@@ -42,8 +143,38 @@ impl ReleaseAsset {
 pub enum GitHubUpdateStatus {
     /// Crate is up to date
     UpToDate,
-    /// Crate was updated to the contained release
-    Updated(Release),
+    /// Crate was updated to the contained release, installed as detailed
+    Updated(Release, Installed),
+}
+
+/// Details of how an update was installed
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Installed {
+    /// The replaced executable left beside the new one as `<exe>.old`: always with
+    /// `UpdateBuilder::keep_old_exe`, otherwise only on Windows when it couldn't be
+    /// deleted yet
+    pub old_exe: Option<PathBuf>,
+    /// Whether processes still run the replaced executable, and need restarting to
+    /// run the new one
+    pub restart_required: bool,
+    /// Ids of the processes still running the replaced executable, as far as they can
+    /// be found: any process on linux, only this one elsewhere
+    pub pids: Vec<u32>,
+}
+
+/// What to do when the installed executable differs from the one released as its
+/// version, e.g. because it was patched locally, see `UpdateBuilder::local_changes`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocalChanges {
+    /// Replace it without checking
+    #[default]
+    Ignore,
+    /// Print `Messages::locally_modified` and replace it
+    Warn,
+    /// Print `Messages::locally_modified` and ask for confirmation before replacing
+    /// it, even with `no_confirm`
+    Confirm,
 }
 
 impl GitHubUpdateStatus {
@@ -51,24 +182,61 @@ impl GitHubUpdateStatus {
     pub fn into_status(self, current_version: String) -> Status {
         match self {
             GitHubUpdateStatus::UpToDate => Status::UpToDate(current_version),
-            GitHubUpdateStatus::Updated(release) => Status::Updated(release.version().into()),
+            GitHubUpdateStatus::Updated(release, _) => Status::Updated(release.version().into()),
         }
     }
 
-    /// Returns `true` if `Status::UpToDate`
-    pub fn uptodate(&self) -> bool {
+    /// How the update was installed, if it was
+    pub fn installed(&self) -> Option<&Installed> {
         match *self {
-            GitHubUpdateStatus::UpToDate => true,
-            _ => false,
+            GitHubUpdateStatus::UpToDate => None,
+            GitHubUpdateStatus::Updated(_, ref installed) => Some(installed),
         }
     }
 
+    /// Returns `true` if `Status::UpToDate`
+    pub fn uptodate(&self) -> bool {
+        matches!(*self, GitHubUpdateStatus::UpToDate)
+    }
+
     /// Returns `true` if `Status::Updated`
     pub fn updated(&self) -> bool {
         !self.uptodate()
     }
 }
 
+/// An update running on a background thread, see `Update::spawn`
+#[derive(Debug)]
+pub struct UpdateHandle {
+    progress: mpsc::Receiver<DownloadProgress>,
+    worker: thread::JoinHandle<Result<GitHubUpdateStatus>>,
+}
+
+impl UpdateHandle {
+    /// Progress reports of the update, as delivered to `UpdateBuilder::on_progress`.
+    /// The channel is disconnected once the update has finished, so e.g. `iter` ends
+    /// with the update, and a GUI event loop can poll `try_iter` instead.
+    pub fn progress(&self) -> &mpsc::Receiver<DownloadProgress> {
+        &self.progress
+    }
+
+    /// Whether the update has finished, so `join` won't block
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Wait for the update to finish and return its result, as from
+    /// `Update::update_extended`
+    ///
+    /// * Errors:
+    ///     * Update - The update panicked
+    pub fn join(self) -> Result<GitHubUpdateStatus> {
+        self.worker
+            .join()
+            .unwrap_or_else(|_| Err(format_err!(Error::Update, "The update thread panicked")))
+    }
+}
+
 /// GitHub release information
 #[derive(Clone, Debug)]
 pub struct Release {
@@ -104,19 +272,17 @@ impl Release {
         })
     }
 
-    /// Check if release has an asset who's name contains the specified `target`
+    /// Check if release has an asset for the specified `target`, see `asset_for`
     pub fn has_target_asset(&self, target: &str) -> bool {
-        self.assets.iter().any(|asset| asset.name.contains(target))
+        self.asset_for(target).is_some()
     }
 
-    /// Return the first `ReleaseAsset` for the current release who's name
-    /// contains the specified `target`
+    /// Return the `ReleaseAsset` of the current release to install for the specified
+    /// `target`: the best ranked by `default_asset_rank` of those whose name contains it
     pub fn asset_for(&self, target: &str) -> Option<ReleaseAsset> {
-        self.assets
-            .iter()
-            .filter(|asset| asset.name.contains(target))
-            .cloned()
-            .nth(0)
+        best_asset(&self.assets, |name| {
+            default_asset_rank(name, &[target.to_owned()])
+        })
     }
 
     pub fn version(&self) -> &str {
@@ -124,12 +290,193 @@ impl Release {
     }
 }
 
+/// Post-update hook, called with the previous version, the installed version,
+/// and the path of the installed executable
+type UpdateHook = dyn Fn(&str, &str, &Path) + Send + Sync;
+
+/// Custom release selection, see `UpdateBuilder::release_filter`
+type ReleaseFilter = dyn Fn(&Release) -> bool + Send + Sync;
+
+/// Custom asset selection, called with asset names
+type AssetMatcher = dyn Fn(&str) -> bool + Send + Sync;
+
+/// Custom asset ranking, called with asset names. Higher ranks are preferred,
+/// `None` excludes the asset.
+type AssetRanker = dyn Fn(&str) -> Option<i64> + Send + Sync;
+
+/// Suffixes of files accompanying a release, like checksums, signatures, patches
+/// and block indexes, which are never installed
+const SIDECAR_SUFFIXES: &[&str] = &[
+    ".sha256",
+    ".sha256sum",
+    ".sha512",
+    ".sha512sum",
+    ".b3",
+    ".blake3",
+    ".sha1",
+    ".md5",
+    ".sig",
+    ".asc",
+    ".minisig",
+    ".pem",
+    ".crt",
+    ".sbom",
+    ".bsdiff",
+    ".json",
+];
+
+/// Archive formats of release assets, see `UpdateBuilder::archive_format`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// `.zip` archives
+    Zip,
+    /// `.tar` archives, also compressed like `.tar.gz`
+    Tar,
+}
+
+impl ArchiveFormat {
+    /// The platform's usual format: zip on Windows, tar elsewhere
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            ArchiveFormat::Zip
+        } else {
+            ArchiveFormat::Tar
+        }
+    }
+
+    fn matches(self, kind: ArchiveKind) -> bool {
+        matches!(
+            (self, kind),
+            (ArchiveFormat::Zip, ArchiveKind::Zip) | (ArchiveFormat::Tar, ArchiveKind::Tar(_))
+        )
+    }
+}
+
+/// Rank of the asset named `name` as the one to install for `targets`, given in order
+/// of preference. Earlier targets rank higher, then the platform's usual archive
+/// format (zip on Windows, tar elsewhere), then other archives, then plain files,
+/// then files compressed in a format whose feature is disabled.
+///
+/// `None` if the name contains none of the `targets`, or it's a checksum, signature
+/// or other file accompanying the release. This is the default ranking of
+/// `UpdateBuilder::asset_ranker`, custom rankers can build on it:
+///
+/// ```
+/// # use self_update::backends::github::{default_asset_rank, Update};
+/// // prefer statically linked musl builds over gnu ones
+/// let targets = self_update::get_target_candidates();
+/// Update::configure().asset_ranker(move |name| {
+///     let musl = targets.iter().map(|t| t.replace("-gnu", "-musl"));
+///     let targets = musl.chain(targets.iter().cloned()).collect::<Vec<_>>();
+///     default_asset_rank(name, &targets)
+/// });
+/// ```
+pub fn default_asset_rank(name: &str, targets: &[String]) -> Option<i64> {
+    asset_rank(name, targets, ArchiveFormat::native())
+}
+
+/// `default_asset_rank`, preferring the `preferred` archive format
+fn asset_rank(name: &str, targets: &[String], preferred: ArchiveFormat) -> Option<i64> {
+    let lower = name.to_lowercase();
+    if SIDECAR_SUFFIXES
+        .iter()
+        .any(|suffix| lower.ends_with(suffix))
+    {
+        return None;
+    }
+    let target_index = targets
+        .iter()
+        .position(|target| name.contains(target.as_str()))?;
+    let format = match detect_archive(Path::new(&lower)) {
+        // only installable as a last resort, with an error naming the feature
+        ArchiveKind::Tar(Some(compression)) | ArchiveKind::Plain(Some(compression))
+            if !compression.is_supported() =>
+        {
+            0
+        }
+        ArchiveKind::Plain(_) => 1,
+        kind if preferred.matches(kind) => 3,
+        _ => 2,
+    };
+    Some((targets.len() - target_index) as i64 * 10 + format)
+}
+
+/// `path` with `suffix` appended to its file name
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Whether the archive cached at `path` is complete, unchanged since it was stored
+/// and matches the release's `checksum`, if any
+fn cached_archive_intact(path: &Path, checksum: Option<&(checksum::Algorithm, String)>) -> bool {
+    let stored = match fs::read_to_string(suffixed(path, ".sha256")) {
+        Ok(stored) => stored,
+        Err(_) => return false,
+    };
+    checksum::Algorithm::Sha256
+        .verify(path, stored.trim())
+        .is_ok()
+        && checksum.is_none_or(|(algorithm, expected)| algorithm.verify(path, expected).is_ok())
+}
+
+/// Extensions substituted for `{ext}` in asset name templates, in order of preference
+#[cfg(windows)]
+const ASSET_EXTENSIONS: &[&str] = &[
+    "zip", "exe", "tar.gz", "tar", "gz", "tar.zst", "tar.xz", "zst", "xz",
+];
+#[cfg(not(windows))]
+const ASSET_EXTENSIONS: &[&str] = &[
+    "tar.gz", "tar", "zip", "gz", "tar.zst", "tar.xz", "zst", "xz",
+];
+
+/// Placeholders available to asset name templates
+const ASSET_TEMPLATE_PLACEHOLDERS: &[&str] = &["bin", "version", "tag", "target", "ext"];
+
+/// The highest ranked of `assets`, the first of them on ties
+fn best_asset<F>(assets: &[ReleaseAsset], rank: F) -> Option<ReleaseAsset>
+where
+    F: Fn(&str) -> Option<i64>,
+{
+    assets
+        .iter()
+        .rev()
+        .filter_map(|asset| rank(&asset.name).map(|rank| (rank, asset)))
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, asset)| asset.clone())
+}
+
+/// The day a release was created, e.g. `2024-01-31` of `2024-01-31T12:00:00Z`
+fn release_date(release: &Release) -> &str {
+    release.date_created.split('T').next().unwrap_or_default()
+}
+
+/// The first line of release notes, without markdown heading markers, shortened to
+/// fit a line of a listing
+fn notes_summary(body: &str) -> String {
+    const MAX_CHARS: usize = 60;
+    let line = body
+        .lines()
+        .map(|line| line.trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    if line.chars().count() <= MAX_CHARS {
+        return line.to_owned();
+    }
+    let short = line.chars().take(MAX_CHARS - 3).collect::<String>();
+    format!("{}...", short.trim_end())
+}
+
 /// `ReleaseList` Builder
 #[derive(Clone, Debug)]
 pub struct ReleaseListBuilder {
     repo_owner: Option<String>,
     repo_name: Option<String>,
     target: Option<String>,
+    api_url: Option<String>,
+    auth_token_from_env: bool,
+    http: HttpConfig,
 }
 impl ReleaseListBuilder {
     /// Set the repo owner, used to build a github api url
@@ -150,8 +497,106 @@ impl ReleaseListBuilder {
         self
     }
 
+    /// Set the base url of the release api, defaults to `https://api.github.com`.
+    /// Use e.g. `https://github.example.com/api/v3` for GitHub Enterprise.
+    pub fn api_url(&mut self, url: &str) -> &mut Self {
+        self.api_url = Some(url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Set a token to authenticate api requests with
+    pub fn auth_token(&mut self, token: &str) -> &mut Self {
+        self.http.auth_token = Some(token.to_owned());
+        self
+    }
+
+    /// Toggle authenticating requests to `api.github.com` with the `GH_TOKEN` or
+    /// `GITHUB_TOKEN` environment variable when no `auth_token` is set. Defaults to `true`.
+    pub fn auth_token_from_env(&mut self, from_env: bool) -> &mut Self {
+        self.auth_token_from_env = from_env;
+        self
+    }
+
+    /// Set the `User-Agent` header sent with every request.
+    /// Defaults to `self_update/<version>`.
+    pub fn user_agent(&mut self, user_agent: &str) -> &mut Self {
+        self.http.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Set the maximum time allowed to establish a connection. Unlimited by default.
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time to wait on the server for a response or any
+    /// chunk of a response body. Defaults to 30 seconds.
+    pub fn read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time any single request, including reading its entire
+    /// body, may take. Unlimited by default.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the number of times a request is retried after a transient network
+    /// failure or a 5xx response. Defaults to `0`.
+    pub fn max_retries(&mut self, retries: u32) -> &mut Self {
+        self.http.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry, doubled for each following retry
+    /// (up to 30 seconds). Defaults to 500 milliseconds.
+    pub fn retry_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.http.retry_backoff = backoff;
+        self
+    }
+
+    /// When the api rate limit is exhausted, wait for it to reset and retry once if
+    /// that's within `max_wait`, instead of failing with `Error::RateLimited`.
+    /// Disabled by default.
+    pub fn wait_for_rate_limit(&mut self, max_wait: Duration) -> &mut Self {
+        self.http.rate_limit_wait = Some(max_wait);
+        self
+    }
+
+    /// Trust the PEM encoded certificates in `path`, e.g. a corporate root CA,
+    /// in addition to the system's root certificates
+    pub fn root_certificates<A: AsRef<Path>>(&mut self, path: A) -> &mut Self {
+        self.http
+            .root_certificates
+            .push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Toggle loading the system CA bundle on linux, for statically linked TLS
+    /// libraries that can't find it on their own. Defaults to `true`.
+    pub fn system_certificates(&mut self, load: bool) -> &mut Self {
+        self.http.system_certificates = load;
+        self
+    }
+
     /// Verify builder args, returning a `ReleaseList`
+    ///
+    /// * Errors:
+    ///     * Config - Invalid `ReleaseList` configuration
+    ///     * Network - Building the http client
     pub fn build(&self) -> Result<ReleaseList> {
+        let api_url = self
+            .api_url
+            .clone()
+            .unwrap_or_else(|| DEFAULT_API_URL.to_owned());
+        let mut http = self.http.clone();
+        if self.auth_token_from_env {
+            env_auth_token(&mut http, &api_url);
+        }
+        http.init_client()?;
         Ok(ReleaseList {
             repo_owner: if let Some(ref owner) = self.repo_owner {
                 owner.to_owned()
@@ -164,6 +609,8 @@ impl ReleaseListBuilder {
                 bail!(Error::Config, "`repo_name` required")
             },
             target: self.target.clone(),
+            api_url,
+            http,
         })
     }
 }
@@ -175,6 +622,8 @@ pub struct ReleaseList {
     repo_owner: String,
     repo_name: String,
     target: Option<String>,
+    api_url: String,
+    http: HttpConfig,
 }
 impl ReleaseList {
     /// Initialize a ReleaseListBuilder
@@ -183,79 +632,98 @@ impl ReleaseList {
             repo_owner: None,
             repo_name: None,
             target: None,
+            api_url: None,
+            auth_token_from_env: true,
+            http: HttpConfig::default(),
         }
     }
 
     /// Retrieve a list of `Release`s.
     /// If specified, filter for those containing a specified `target`
     pub fn fetch(self) -> Result<Vec<Release>> {
-        set_ssl_vars!();
-        let api_url = format!(
-            "https://api.github.com/repos/{}/{}/releases",
-            self.repo_owner, self.repo_name
-        );
-        let releases = Self::fetch_releases(&api_url)?;
-        let releases = match self.target {
-            None => releases,
-            Some(ref target) => releases
-                .into_iter()
-                .filter(|r| r.has_target_asset(target))
-                .collect::<Vec<_>>(),
-        };
-        Ok(releases)
+        self.iter().collect()
     }
 
-    fn fetch_releases(url: &str) -> Result<Vec<Release>> {
-        let mut resp = reqwest::get(url)?;
-        if !resp.status().is_success() {
-            bail!(
-                Error::Network,
-                "api request failed with status: {:?} - for: {:?}",
-                resp.status(),
-                url
-            )
+    /// Iterate over the `Release`s, newest first, requesting further pages of
+    /// the listing only as they're reached.
+    /// If specified, filter for those containing a specified `target`
+    pub fn iter(self) -> Releases {
+        let url = format!(
+            "{}/repos/{}/{}/releases?per_page={}",
+            self.api_url, self.repo_owner, self.repo_name, RELEASES_PER_PAGE
+        );
+        Releases {
+            list: self,
+            page: vec![].into_iter(),
+            next_url: Some(url),
         }
-        let releases = resp.json::<serde_json::Value>()?;
+    }
+
+    /// Request a page of releases, returning them along with the url of the next page
+    fn fetch_page(&self, url: &str) -> Result<(Vec<Release>, Option<String>)> {
+        let mut resp = api_get(&self.http, url)?;
+        let releases = resp.json()?;
         let releases = releases
             .as_array()
-            .ok_or_else(|| format_err!(Error::Release, "No releases found"))?;
-        let mut releases = releases
+            .ok_or_else(|| format_err!(Error::Release, "No releases found"))?
             .iter()
             .map(Release::from_release)
             .collect::<Result<Vec<Release>>>()?;
+        let next_url = resp.header_all("link").find_map(next_page_link);
+        Ok((releases, next_url))
+    }
+}
+
+/// Iterator over the `Release`s of a `ReleaseList`, see `ReleaseList::iter`.
+///
+/// Iteration ends after the first error.
+#[derive(Debug)]
+pub struct Releases {
+    list: ReleaseList,
+    page: std::vec::IntoIter<Release>,
+    next_url: Option<String>,
+}
 
-        // handle paged responses containing `Link` header:
-        // `Link: <https://api.github.com/resource?page=2>; rel="next"`
-        let headers = resp.headers();
-        let links = headers.get_all(reqwest::header::LINK);
+impl Iterator for Releases {
+    type Item = Result<Release>;
 
-        let next_link = links
-            .iter()
-            .filter_map(|link| {
-                if let Ok(link) = link.to_str() {
-                    let lv = LinkValue::new(link.to_owned());
-                    if let Some(rels) = lv.rel() {
-                        if rels.contains(&RelationType::Next) {
-                            return Some(link);
-                        }
-                    }
-                    None
-                } else {
-                    None
+    fn next(&mut self) -> Option<Result<Release>> {
+        loop {
+            if let Some(release) = self.page.next() {
+                match self.list.target {
+                    Some(ref target) if !release.has_target_asset(target) => continue,
+                    _ => return Some(Ok(release)),
                 }
-            })
-            .nth(0);
-
-        Ok(match next_link {
-            None => releases,
-            Some(link) => {
-                releases.extend(Self::fetch_releases(link)?);
-                releases
             }
-        })
+            let url = self.next_url.take()?;
+            match self.list.fetch_page(&url) {
+                Ok((releases, next_url)) => {
+                    self.page = releases.into_iter();
+                    self.next_url = next_url;
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
     }
 }
 
+/// The `rel="next"` url of a paged response's `Link` header, e.g.
+/// `<https://api.github.com/resource?page=2>; rel="next", <...>; rel="last"`
+fn next_page_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|link| {
+        let mut parts = link.split(';');
+        let url = parts.next()?.trim();
+        let is_next = parts.any(|param| {
+            let param = param.trim().replace(' ', "");
+            param == "rel=\"next\"" || param == "rel=next"
+        });
+        if !is_next || !url.starts_with('<') || !url.ends_with('>') {
+            return None;
+        }
+        Some(url[1..url.len() - 1].to_owned())
+    })
+}
+
 /// `github::Update` builder
 ///
 /// Configure download and installation from
@@ -271,9 +739,57 @@ pub struct UpdateBuilder {
     show_download_progress: bool,
     show_output: bool,
     no_confirm: bool,
+    non_interactive: NonInteractive,
+    prompt_timeout: PromptTimeout,
+    messages: Messages,
     current_version: Option<String>,
     target_version: Option<String>,
+    select_release: Option<usize>,
     progress_style: Option<ProgressStyle>,
+    max_download_speed: Option<u64>,
+    download_connections: usize,
+    backups: usize,
+    keep_old_exe: bool,
+    local_changes: LocalChanges,
+    backup_dir: Option<PathBuf>,
+    temp_dir: Option<PathBuf>,
+    download_cache: Option<PathBuf>,
+    resume_downloads: bool,
+    companion_files: Vec<(PathBuf, PathBuf)>,
+    on_update: Option<Callback<UpdateHook>>,
+    on_attempt: Option<Callback<AttemptHook>>,
+    on_progress: Option<Callback<ProgressHook>>,
+    service: Option<Service>,
+    progress_interval: Duration,
+    total_timeout: Option<Duration>,
+    api_url: Option<String>,
+    load_policy: bool,
+    policy_file: Option<PathBuf>,
+    elevate: bool,
+    defer_to_package_manager: bool,
+    homebrew: Homebrew,
+    route_through_package_manager: bool,
+    follow_symlinks: bool,
+    musl_fallback: bool,
+    target_aliases: Vec<String>,
+    asset_pattern: Option<String>,
+    asset_matcher: Option<Callback<AssetMatcher>>,
+    asset_ranker: Option<Callback<AssetRanker>>,
+    asset_name_template: Option<String>,
+    target_asset_names: Vec<(String, String)>,
+    archive_format: Option<ArchiveFormat>,
+    latest_by_semver: bool,
+    release_filter: Option<Callback<ReleaseFilter>>,
+    extractors: Vec<Callback<dyn Extractor>>,
+    downloader: Option<Callback<dyn Downloader>>,
+    delta_updates: bool,
+    verify_checksum: bool,
+    checksum_algorithm: Option<checksum::Algorithm>,
+    differential_download: bool,
+    cache_api_responses: bool,
+    api_cache_file: Option<PathBuf>,
+    auth_token_from_env: bool,
+    http: HttpConfig,
 }
 
 impl UpdateBuilder {
@@ -311,14 +827,253 @@ impl UpdateBuilder {
         self
     }
 
+    /// List the `count` most recent releases with an asset for the target, with their
+    /// date and the first line of their notes, and let the user choose the one to
+    /// install, older releases included, e.g. for `app self-update --select`.
+    /// Ignored when a `target_version_tag` is set. Without a terminal,
+    /// `non_interactive` decides as for confirmation, `NonInteractive::Proceed`
+    /// installing the most recent release.
+    pub fn select_release(&mut self, count: usize) -> &mut Self {
+        self.select_release = Some(count);
+        self
+    }
+
     /// Set the target triple that will be downloaded, e.g. `x86_64-unknown-linux-gnu`.
     ///
-    /// If unspecified, the build target of the crate will be used
+    /// If unspecified, the first asset matching one of `get_target_candidates()` is used,
+    /// which usually is the build target of the crate.
     pub fn target(&mut self, target: &str) -> &mut Self {
         self.target = Some(target.to_owned());
         self
     }
 
+    /// Toggle falling back to the statically linked `-musl` asset when a release has
+    /// no asset for a glibc linux target (`-gnu`). Defaults to `true`.
+    pub fn musl_fallback(&mut self, fallback: bool) -> &mut Self {
+        self.musl_fallback = fallback;
+        self
+    }
+
+    /// Toggle verifying the downloaded asset against the checksum published with the
+    /// release, see the `checksum` module for where it's looked up. The strongest
+    /// algorithm published and supported is used. The update fails if the release has
+    /// no checksum for the asset. Delta updates and differential downloads, which
+    /// don't download the asset, verify the patched or rebuilt executable instead, see
+    /// `delta_updates` and `differential_download`. Defaults to `false`.
+    ///
+    /// Regardless of this setting, assets are verified against the `digest` GitHub
+    /// computes for them when the api provides one.
+    pub fn verify_checksum(&mut self, verify: bool) -> &mut Self {
+        self.verify_checksum = verify;
+        self
+    }
+
+    /// Only accept checksums of `algorithm`, e.g. the one the release pipeline
+    /// produces, which enables `verify_checksum`
+    ///
+    /// * Errors:
+    ///     * Config - On `build`, if the algorithm isn't supported with the enabled
+    ///       features
+    pub fn checksum_algorithm(&mut self, algorithm: checksum::Algorithm) -> &mut Self {
+        self.checksum_algorithm = Some(algorithm);
+        self
+    }
+
+    /// Toggle updating with a binary patch from the current version when the release
+    /// has one, instead of downloading the full release. Defaults to `false`.
+    ///
+    /// Patches are bsdiff patches of the installed executable, published as release
+    /// assets named `<asset name>.from-<current_version>.bsdiff`, e.g.
+    /// `app-x86_64-unknown-linux-gnu.tar.gz.from-1.2.3.bsdiff`. The full release is
+    /// downloaded if there's no patch for the current version, or it doesn't apply.
+    ///
+    /// When the asset has a checksum, e.g. the digest GitHub reports, the patched
+    /// executable of an uncompressed executable asset is verified against it. For
+    /// archives, the patch must have a checksum of its own, which it's verified against.
+    /// The full release is downloaded if verification fails.
+    pub fn delta_updates(&mut self, delta: bool) -> &mut Self {
+        self.delta_updates = delta;
+        self
+    }
+
+    /// Toggle downloading only the blocks of an uncompressed executable asset that
+    /// differ from the installed executable, when the release has a block index for
+    /// it. Defaults to `false`.
+    ///
+    /// Block indexes are published as release assets named `<asset name>.blocks.json`,
+    /// see the `blocks` module. The rebuilt executable is verified against the index,
+    /// and the asset's checksum, e.g. the digest GitHub reports, if it has one. The full
+    /// release is downloaded if there's no index, the server doesn't support range
+    /// requests, or the rebuilt executable fails verification.
+    pub fn differential_download(&mut self, differential: bool) -> &mut Self {
+        self.differential_download = differential;
+        self
+    }
+
+    /// Also accept assets whose names contain one of `aliases` when no asset names the
+    /// target triple, for release naming schemes like `app-linux64.tar.gz` or
+    /// `app-darwin-amd64.zip`. Aliases are tried in order.
+    pub fn target_aliases(&mut self, aliases: &[&str]) -> &mut Self {
+        self.target_aliases
+            .extend(aliases.iter().map(|alias| (*alias).to_owned()));
+        self
+    }
+
+    /// Select the first asset whose name matches the regular expression `pattern`,
+    /// instead of matching the target. Invalid patterns are reported by `build`.
+    /// Replaces any `asset_matcher`, `asset_ranker` or `asset_name_template`.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// Update::configure().asset_pattern(r"^myapp-v[\d.]+-linux-(x86_64|amd64)\.tar\.gz$");
+    /// ```
+    pub fn asset_pattern(&mut self, pattern: &str) -> &mut Self {
+        self.asset_pattern = Some(pattern.to_owned());
+        self.asset_matcher = None;
+        self.asset_ranker = None;
+        self.asset_name_template = None;
+        self
+    }
+
+    /// Select the first asset whose name `matcher` accepts, instead of matching the target.
+    /// Replaces any `asset_pattern`, `asset_ranker` or `asset_name_template`.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// Update::configure().asset_matcher(|name| name.contains("linux") && name.ends_with(".zip"));
+    /// ```
+    pub fn asset_matcher<F>(&mut self, matcher: F) -> &mut Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.asset_matcher = Some(Callback(Arc::new(matcher)));
+        self.asset_pattern = None;
+        self.asset_ranker = None;
+        self.asset_name_template = None;
+        self
+    }
+
+    /// Select the asset `ranker` ranks highest, the first of them on ties, instead of
+    /// the highest `default_asset_rank` for the target. Assets it returns `None` for
+    /// are never selected. Replaces any `asset_pattern`, `asset_matcher` or
+    /// `asset_name_template`.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// // prefer the installer over the portable zip
+    /// Update::configure().asset_ranker(|name| match name {
+    ///     n if n.ends_with("-setup.exe") => Some(2),
+    ///     n if n.ends_with("-portable.zip") => Some(1),
+    ///     _ => None,
+    /// });
+    /// ```
+    pub fn asset_ranker<F>(&mut self, ranker: F) -> &mut Self
+    where
+        F: Fn(&str) -> Option<i64> + Send + Sync + 'static,
+    {
+        self.asset_ranker = Some(Callback(Arc::new(ranker)));
+        self.asset_pattern = None;
+        self.asset_matcher = None;
+        self.asset_name_template = None;
+        self
+    }
+
+    /// Select the asset named after `template` instead of searching asset names for
+    /// the target. The placeholders are:
+    ///
+    /// * `{bin}` - the `bin_name`
+    /// * `{version}` - the release's version, without a leading `v`
+    /// * `{tag}` - the release's tag
+    /// * `{target}` - each of the target candidates in turn, see `target`
+    /// * `{ext}` - each supported archive extension in turn, those of the
+    ///   `archive_format` first
+    ///
+    /// The first name in that order that's an asset of the release is selected.
+    /// Unknown placeholders are reported by `build`. Replaces any `asset_pattern`,
+    /// `asset_matcher` or `asset_ranker`.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// Update::configure().asset_name_template("{bin}-v{version}-{target}.{ext}");
+    /// ```
+    pub fn asset_name_template(&mut self, template: &str) -> &mut Self {
+        self.asset_name_template = Some(template.to_owned());
+        self.asset_pattern = None;
+        self.asset_matcher = None;
+        self.asset_ranker = None;
+        self
+    }
+
+    /// Select the asset named `name` for `target`, for release naming schemes unrelated
+    /// to target triples, like `tool-win64.zip` or `tool-macos.tar.gz`. `name` may use
+    /// the placeholders of `asset_name_template`, e.g. `tool-{version}-win64.{ext}`.
+    /// Takes precedence over the other asset selection options for the first of the
+    /// target candidates (see `target`) with a name, and no asset is selected if the
+    /// release has none of that name. Replaces any earlier name for `target`.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// Update::configure()
+    ///     .asset_name_for("x86_64-pc-windows-msvc", "tool-win64.zip")
+    ///     .asset_name_for("aarch64-apple-darwin", "tool-macos.tar.gz");
+    /// ```
+    pub fn asset_name_for(&mut self, target: &str, name: &str) -> &mut Self {
+        self.target_asset_names.retain(|(t, _)| t != target);
+        self.target_asset_names
+            .push((target.to_owned(), name.to_owned()));
+        self
+    }
+
+    /// Prefer assets in the `format` archive format when a release publishes the
+    /// executable for a target in several, e.g. both a `.zip` and a `.tar.gz`. Also
+    /// orders the `{ext}` candidates of `asset_name_template`. Defaults to
+    /// `ArchiveFormat::native()`: zip on Windows, tar elsewhere.
+    pub fn archive_format(&mut self, format: ArchiveFormat) -> &mut Self {
+        self.archive_format = Some(format);
+        self
+    }
+
+    /// Toggle selecting the latest release as the one with the greatest semver tag
+    /// among all releases, instead of the most recently published one reported by
+    /// GitHub, e.g. for repos also publishing patch releases of older major versions.
+    /// Pre-releases and tags that aren't semver versions are ignored. Defaults to `false`.
+    pub fn latest_by_semver(&mut self, by_semver: bool) -> &mut Self {
+        self.latest_by_semver = by_semver;
+        self
+    }
+
+    /// Select the latest release among the releases `filter` accepts, by semver as
+    /// with `latest_by_semver`, which this enables
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// // stay on the 1.x line
+    /// Update::configure().release_filter(|release| release.version().starts_with("1."));
+    /// ```
+    pub fn release_filter<F>(&mut self, filter: F) -> &mut Self
+    where
+        F: Fn(&Release) -> bool + Send + Sync + 'static,
+    {
+        self.release_filter = Some(Callback(Arc::new(filter)));
+        self
+    }
+
+    /// Extract release assets `extractor` handles with it, e.g. for a packaging format
+    /// the crate doesn't support. Extractors are tried in the order they're added,
+    /// before the built-in formats. See the `extractor` module.
+    pub fn extractor<E: Extractor + 'static>(&mut self, extractor: E) -> &mut Self {
+        self.extractors.push(Callback(Arc::new(extractor)));
+        self
+    }
+
+    /// Download release assets with `downloader` instead of over HTTP, e.g. from an
+    /// internal artifact store. Differential downloads are disabled as they need
+    /// HTTP range requests. See the `downloader` module.
+    pub fn downloader<D: Downloader + 'static>(&mut self, downloader: D) -> &mut Self {
+        self.downloader = Some(Callback(Arc::new(downloader)));
+        self
+    }
+
     /// Set the exe's name. Also sets `bin_path_in_archive` if it hasn't already been set.
     pub fn bin_name(&mut self, name: &str) -> &mut Self {
         self.bin_name = Some(name.to_owned());
@@ -330,11 +1085,29 @@ impl UpdateBuilder {
 
     /// Set the installation path for the new exe, defaults to the current
     /// executable's path
+    ///
+    /// If nothing is installed at this path yet, `update` installs the release without
+    /// comparing versions, creating any missing parent directories. This allows the same
+    /// configuration to power both a `self-update` command and a bootstrap installer.
+    ///
+    /// The path may also be another executable than the running one, e.g. a tool
+    /// managed by a launcher. The precautions for replacing a running executable on
+    /// Windows are then skipped, and updating fails with `Error::InUse` if another
+    /// process is running it (detected on Linux and Windows).
     pub fn bin_install_path<A: AsRef<Path>>(&mut self, bin_install_path: A) -> &mut Self {
         self.bin_install_path = Some(PathBuf::from(bin_install_path.as_ref()));
         self
     }
 
+    /// Toggle how a `bin_install_path` that is a symlink (e.g. `~/.local/bin/app ->
+    /// ~/apps/app-1.2/app`) is updated: `true` (the default) replaces the file the
+    /// link points to, keeping the link intact, `false` replaces the link itself with
+    /// the new executable.
+    pub fn follow_symlinks(&mut self, follow: bool) -> &mut Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
     /// Set the path of the exe inside the release tarball. This is the location
     /// of the executable relative to the base of the tar'd directory and is the
     /// path that will be copied to the `bin_install_path`. If not specified, this
@@ -356,7 +1129,7 @@ impl UpdateBuilder {
     ///
     /// ```
     /// # use self_update::backends::github::Update;
-    /// # fn run() -> Result<(), Box<::std::error::Error>> {
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
     /// Update::configure()
     ///     .bin_path_in_archive("bin/myapp")
     /// #   .build()?;
@@ -368,6 +1141,65 @@ impl UpdateBuilder {
         self
     }
 
+    /// Also install the file at `path_in_archive` (e.g. shell completions or a man page)
+    /// into the directory `install_dir`, replacing any previous version. May be called
+    /// multiple times. Missing directories are created.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// Update::configure()
+    ///     .companion_file("complete/myapp.bash", "/usr/share/bash-completion/completions")
+    ///     .companion_file("doc/myapp.1", "/usr/local/share/man/man1");
+    /// ```
+    pub fn companion_file<A: AsRef<Path>, B: AsRef<Path>>(
+        &mut self,
+        path_in_archive: A,
+        install_dir: B,
+    ) -> &mut Self {
+        self.companion_files.push((
+            path_in_archive.as_ref().to_path_buf(),
+            install_dir.as_ref().to_path_buf(),
+        ));
+        self
+    }
+
+    /// Toggle restarting the current process with elevated privileges (see
+    /// `restart_elevated`) when the directory of `bin_install_path` isn't writable.
+    /// The elevated process is run with the same arguments, so it's expected to
+    /// perform the update again. Defaults to `false`, failing with `Error::PermissionDenied`
+    /// before anything is downloaded.
+    pub fn elevate(&mut self, elevate: bool) -> &mut Self {
+        self.elevate = elevate;
+        self
+    }
+
+    /// Toggle refusing to update an executable installed by a package manager
+    /// (Homebrew, apt, rpm, Scoop, winget, Chocolatey, `cargo install`), failing with
+    /// `Error::PackageManaged` which names the command to update it with. Detecting
+    /// system packages runs `dpkg-query` / `rpm`. Defaults to `false`.
+    pub fn defer_to_package_manager(&mut self, defer: bool) -> &mut Self {
+        self.defer_to_package_manager = defer;
+        self
+    }
+
+    /// Toggle updating an executable installed by Scoop, winget or Chocolatey by
+    /// running the package manager's upgrade command when GitHub has a newer
+    /// release, instead of refusing as set by `defer_to_package_manager`.
+    /// Defaults to `false`. See `homebrew` for Homebrew installs.
+    pub fn route_through_package_manager(&mut self, route: bool) -> &mut Self {
+        self.route_through_package_manager = route;
+        self
+    }
+
+    /// Set how to update an executable installed by Homebrew: instead of GitHub
+    /// releases, `Homebrew::Check` and `Homebrew::Upgrade` compare the version of its
+    /// formula to the current version, and `Homebrew::Upgrade` runs `brew upgrade`
+    /// rather than writing into Homebrew's Cellar. Defaults to `Homebrew::Defer`.
+    pub fn homebrew(&mut self, homebrew: Homebrew) -> &mut Self {
+        self.homebrew = homebrew;
+        self
+    }
+
     /// Toggle download progress bar, defaults to `off`.
     pub fn show_download_progress(&mut self, show: bool) -> &mut Self {
         self.show_download_progress = show;
@@ -380,28 +1212,473 @@ impl UpdateBuilder {
         self
     }
 
-    /// Toggle update output information, defaults to `true`.
-    pub fn show_output(&mut self, show: bool) -> &mut Self {
-        self.show_output = show;
+    /// Limit the average download rate of the release asset to `bytes_per_sec`.
+    /// Unlimited by default.
+    pub fn max_download_speed(&mut self, bytes_per_sec: u64) -> &mut Self {
+        self.max_download_speed = Some(bytes_per_sec);
         self
     }
 
-    /// Toggle download confirmation. Defaults to `false`.
+    /// Download release assets with up to `connections` concurrent ranged requests,
+    /// for servers that throttle each connection. Defaults to `1`.
+    ///
+    /// With more than one connection the asset is written to disk before it's
+    /// extracted, instead of being extracted while it downloads.
+    pub fn download_connections(&mut self, connections: usize) -> &mut Self {
+        self.download_connections = connections.max(1);
+        self
+    }
+
+    /// Keep the `count` most recent executables replaced by an update as versioned
+    /// backups, so they can be restored with `Update::rollback`. Defaults to `0`.
+    pub fn backups(&mut self, count: usize) -> &mut Self {
+        self.backups = count;
+        self
+    }
+
+    /// Keep the executable replaced by an update beside the new one as `<exe>.old`,
+    /// e.g. to switch back by hand if the new one misbehaves. Its path is reported in
+    /// `Installed::old_exe`. It's replaced by the next update, and removed by the next
+    /// update check finding no newer release or with `Update::remove_old_exe`.
+    /// Defaults to `false`: it's only left on Windows until it can be deleted.
+    pub fn keep_old_exe(&mut self, keep: bool) -> &mut Self {
+        self.keep_old_exe = keep;
+        self
+    }
+
+    /// Set what to do when the installed executable was modified locally, i.e.
+    /// differs from the one released as `current_version`. Checking downloads that
+    /// release's asset, unless GitHub published its digest. An executable whose
+    /// release or asset can't be found is assumed unmodified.
+    /// Defaults to `LocalChanges::Ignore`.
+    pub fn local_changes(&mut self, local_changes: LocalChanges) -> &mut Self {
+        self.local_changes = local_changes;
+        self
+    }
+
+    /// Set the directory backups are kept in, defaults to a `.<bin_name>-backups`
+    /// directory next to `bin_install_path`.
+    pub fn backup_dir<A: AsRef<Path>>(&mut self, dir: A) -> &mut Self {
+        self.backup_dir = Some(PathBuf::from(dir.as_ref()));
+        self
+    }
+
+    /// Create the temporary directories releases are downloaded and extracted in under
+    /// `dir`, e.g. a RAM disk, instead of next to `bin_install_path`. When `dir` is on
+    /// another filesystem, the extracted executable is copied next to `bin_install_path`
+    /// before being renamed into place.
+    pub fn temp_dir<A: AsRef<Path>>(&mut self, dir: A) -> &mut Self {
+        self.temp_dir = Some(PathBuf::from(dir.as_ref()));
+        self
+    }
+
+    /// Keep downloaded release archives in `dir`, under `<version>/<target>/<asset name>`,
+    /// and install from there instead of downloading again, e.g. when rolling back or
+    /// provisioning several machines from a shared directory. An archive is only
+    /// stored once it's verified, and only reused if it's intact and still matches the
+    /// release's checksum. Patches and changed blocks (see `delta_updates` and
+    /// `differential_download`) aren't cached.
+    pub fn download_cache<A: AsRef<Path>>(&mut self, dir: A) -> &mut Self {
+        self.download_cache = Some(PathBuf::from(dir.as_ref()));
+        self
+    }
+
+    /// Toggle keeping interrupted downloads of release archives, e.g. when the process
+    /// is killed or the network drops, and resuming them on the next `update` with a
+    /// range request, if the server supports them and the asset didn't change. They're
+    /// kept under `partial` in the `download_cache` if set, or else in the platform
+    /// cache directory, e.g. `~/.cache/<bin_name>/partial` on linux. Only downloads over
+    /// a single connection (see `download_connections`) are resumed. Defaults to `false`.
+    pub fn resume_downloads(&mut self, resume: bool) -> &mut Self {
+        self.resume_downloads = resume;
+        self
+    }
+
+    /// Set a function to run after a new release has been successfully installed by
+    /// `update`, e.g. to migrate config files or clear caches before the app exits.
+    /// It is called with the previous version, the new version, and the path of the
+    /// installed executable.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// Update::configure().on_update(|old, new, path| {
+    ///     println!("updated {:?} from v{} to v{}", path, old, new);
+    /// });
+    /// ```
+    pub fn on_update<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&str, &str, &Path) + Send + Sync + 'static,
+    {
+        self.on_update = Some(Callback(Arc::new(hook)));
+        self
+    }
+
+    /// Restart `service` after a new release has been installed by `update` or
+    /// `install_from_archive`, when the executable backs a daemon. A running Windows
+    /// service is instead stopped before its executable is replaced and started
+    /// afterwards, putting the previous executable back if it fails to start.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// # use self_update::service::Service;
+    /// Update::configure().restart_service(Service::systemd("app.service"));
+    /// ```
+    pub fn restart_service(&mut self, service: Service) -> &mut Self {
+        self.service = Some(service);
+        self
+    }
+
+    /// Call `hook` with the record of each update attempt by `update`, successful or
+    /// not, e.g. to report update success rates. See the `telemetry` module.
+    pub fn on_attempt<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&UpdateRecord) + Send + Sync + 'static,
+    {
+        self.on_attempt = Some(Callback(Arc::new(hook)));
+        self
+    }
+
+    /// Call `hook` as the update progresses, e.g. to drive a GUI progress widget.
+    /// Download progress is reported at most once per `progress_interval`.
+    /// `DownloadProgress::overall_percent` gives the progress of the whole update.
+    ///
+    /// ```
+    /// # use self_update::backends::github::Update;
+    /// use self_update::progress::Phase;
+    ///
+    /// Update::configure().on_progress(|progress| match (progress.phase, progress.total) {
+    ///     (Phase::Downloading, Some(total)) => println!("{}/{}", progress.bytes, total),
+    ///     (phase, _) => println!("{:?} {:.0}%", phase, progress.overall_percent()),
+    /// });
+    /// ```
+    pub fn on_progress<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(DownloadProgress) + Send + Sync + 'static,
+    {
+        self.on_progress = Some(Callback(Arc::new(hook)));
+        self
+    }
+
+    /// Set the minimum interval between two download `on_progress` reports.
+    /// Defaults to 100 milliseconds.
+    pub fn progress_interval(&mut self, interval: Duration) -> &mut Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Toggle update output information, defaults to `true`.
+    pub fn show_output(&mut self, show: bool) -> &mut Self {
+        self.show_output = show;
+        self
+    }
+
+    /// Toggle download confirmation. Defaults to `false`.
     pub fn no_confirm(&mut self, no_confirm: bool) -> &mut Self {
         self.no_confirm = no_confirm;
         self
     }
 
+    /// Set what to do when confirmation is required but stdin or stdout isn't a
+    /// terminal, e.g. under cron or CI. Defaults to `NonInteractive::Abort`.
+    pub fn non_interactive(&mut self, policy: NonInteractive) -> &mut Self {
+        self.non_interactive = policy;
+        self
+    }
+
+    /// Stop waiting for an answer to the confirmation, or the `select_release` prompt,
+    /// after `timeout` and continue as `default` says, e.g. for updaters of services
+    /// or kiosks which shouldn't wait forever for someone to answer. Defaults to
+    /// waiting indefinitely.
+    pub fn prompt_timeout(&mut self, timeout: Duration, default: NonInteractive) -> &mut Self {
+        self.prompt_timeout = Some((timeout, default));
+        self
+    }
+
+    /// Set the messages printed while updating, e.g. translated ones.
+    /// Defaults to English.
+    pub fn messages(&mut self, messages: Messages) -> &mut Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Set the base url of the release api, defaults to `https://api.github.com`.
+    /// Use e.g. `https://github.example.com/api/v3` for GitHub Enterprise.
+    pub fn api_url(&mut self, url: &str) -> &mut Self {
+        self.api_url = Some(url.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Toggle applying the update policy file at `Policy::default_path(bin_name)`,
+    /// if it exists. Defaults to `false`. See the `policy` module.
+    pub fn load_policy(&mut self, load: bool) -> &mut Self {
+        self.load_policy = load;
+        self
+    }
+
+    /// Apply the update policy file at `path`, if it exists. See the `policy` module.
+    pub fn policy_file<A: AsRef<Path>>(&mut self, path: A) -> &mut Self {
+        self.policy_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Toggle caching release api responses in the platform cache directory, e.g.
+    /// `~/.cache/<bin_name>/self_update_api.json` on linux. Defaults to `false`.
+    ///
+    /// Cached responses are revalidated with conditional requests (`ETag` /
+    /// `If-Modified-Since`), which don't count against GitHub's rate limit when
+    /// nothing changed.
+    pub fn cache_api_responses(&mut self, cache: bool) -> &mut Self {
+        self.cache_api_responses = cache;
+        self
+    }
+
+    /// Cache release api responses in the file at `path`, see `cache_api_responses`
+    pub fn api_cache_file<A: AsRef<Path>>(&mut self, path: A) -> &mut Self {
+        self.api_cache_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set a token to authenticate api requests and downloads with
+    pub fn auth_token(&mut self, token: &str) -> &mut Self {
+        self.http.auth_token = Some(token.to_owned());
+        self
+    }
+
+    /// Toggle authenticating requests to `api.github.com` with the `GH_TOKEN` or
+    /// `GITHUB_TOKEN` environment variable when no `auth_token` is set, e.g. to raise
+    /// the rate limit or reach private repos. Defaults to `true`.
+    pub fn auth_token_from_env(&mut self, from_env: bool) -> &mut Self {
+        self.auth_token_from_env = from_env;
+        self
+    }
+
+    /// Set the `User-Agent` header sent with every request.
+    /// Defaults to `<bin_name>/<current_version> self_update/<version>`.
+    pub fn user_agent(&mut self, user_agent: &str) -> &mut Self {
+        self.http.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Set the maximum time allowed to establish a connection. Unlimited by default.
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time to wait on the server for a response or any
+    /// chunk of a response body. Defaults to 30 seconds.
+    pub fn read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time any single request, including reading its entire
+    /// body, may take. Unlimited by default.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time the whole update may take, from looking up the release
+    /// to replacing the executable, after which it fails with `Error::TimedOut` and
+    /// the executable is left as it was. Requests in progress are aborted at their
+    /// next read, waiting on the server is still only bounded by `read_timeout`.
+    /// Unlimited by default.
+    pub fn total_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.total_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the number of times a request is retried after a transient network
+    /// failure or a 5xx response. Defaults to `0`.
+    pub fn max_retries(&mut self, retries: u32) -> &mut Self {
+        self.http.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry, doubled for each following retry
+    /// (up to 30 seconds). Defaults to 500 milliseconds.
+    pub fn retry_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.http.retry_backoff = backoff;
+        self
+    }
+
+    /// When the api rate limit is exhausted, wait for it to reset and retry once if
+    /// that's within `max_wait`, instead of failing with `Error::RateLimited`.
+    /// Disabled by default.
+    pub fn wait_for_rate_limit(&mut self, max_wait: Duration) -> &mut Self {
+        self.http.rate_limit_wait = Some(max_wait);
+        self
+    }
+
+    /// Trust the PEM encoded certificates in `path`, e.g. a corporate root CA,
+    /// in addition to the system's root certificates
+    pub fn root_certificates<A: AsRef<Path>>(&mut self, path: A) -> &mut Self {
+        self.http
+            .root_certificates
+            .push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Toggle loading the system CA bundle on linux, for statically linked TLS
+    /// libraries that can't find it on their own. Defaults to `true`.
+    pub fn system_certificates(&mut self, load: bool) -> &mut Self {
+        self.http.system_certificates = load;
+        self
+    }
+
     /// Confirm config and create a ready-to-use `Update`
     ///
+    /// For a `bin_name` of e.g. `my-app`, these environment variables take precedence
+    /// over the builder configuration:
+    ///
+    /// * `SELF_UPDATE_DISABLE`, `MY_APP_UPDATE_DISABLE` - updating fails with `Error::Disabled`
+    /// * `MY_APP_UPDATE_NO_CONFIRM` - overrides `no_confirm`
+    /// * `MY_APP_UPDATE_API_URL` - overrides `api_url`
+    /// * `MY_APP_UPDATE_TOKEN` - overrides `auth_token`
+    ///
+    /// Flags are set by `1`, `true`, `yes` or `on`.
+    ///
+    /// An update policy file, if enabled, also takes precedence over the builder
+    /// configuration.
+    ///
     /// * Errors:
-    ///     * Config - Invalid `Update` configuration
+    ///     * Config - Invalid `Update` configuration or policy file
+    ///     * Io - Reading the policy file
+    ///     * Network - Building the http client
     pub fn build(&self) -> Result<Update> {
-        let bin_install_path = if let Some(v) = &self.bin_install_path {
+        let mut bin_install_path = if let Some(v) = &self.bin_install_path {
             v.clone()
         } else {
             env::current_exe()?
         };
+        if self.follow_symlinks {
+            bin_install_path = resolve_symlink(&bin_install_path)?;
+        }
+
+        let overrides = self
+            .bin_name
+            .as_ref()
+            .map(|name| EnvOverrides::from_env(name))
+            .unwrap_or_default();
+
+        let policy_file = match (&self.policy_file, &self.bin_name) {
+            (Some(path), _) => Some(path.clone()),
+            (None, Some(bin_name)) if self.load_policy => Some(Policy::default_path(bin_name)?),
+            _ => None,
+        };
+        let policy = match policy_file {
+            Some(ref path) => Policy::load(path)?.unwrap_or_default(),
+            None => Policy::default(),
+        };
+        let disabled_by = overrides.disabled_by.or_else(|| {
+            policy_file
+                .as_ref()
+                .filter(|_| policy.disable)
+                .map(|path| path.display().to_string())
+        });
+
+        let mut target_candidates = match self.target {
+            Some(ref target) => vec![target.to_owned()],
+            None => get_target_candidates(),
+        };
+        if self.musl_fallback {
+            let musl = target_candidates
+                .iter()
+                .filter_map(|target| musl_equivalent(target))
+                .collect::<Vec<_>>();
+            target_candidates.extend(musl);
+        }
+        target_candidates.extend(self.target_aliases.iter().cloned());
+
+        if let Some(ref service) = self.service {
+            service.check_supported()?;
+            if service.stops_for_replace() && is_current_exe(&bin_install_path) {
+                bail!(
+                    Error::Config,
+                    "{} is stopped to replace its executable, the update must be run by another process",
+                    service
+                );
+            }
+        }
+
+        let templates = self
+            .asset_name_template
+            .iter()
+            .map(|template| ("asset_name_template", template))
+            .chain(
+                self.target_asset_names
+                    .iter()
+                    .map(|(_, name)| ("asset_name_for", name)),
+            );
+        let placeholder = Regex::new(r"\{([^{}]*)\}").expect("invalid placeholder regex");
+        for (option, template) in templates {
+            let unknown = placeholder
+                .captures_iter(template)
+                .map(|c| c[1].to_owned())
+                .find(|name| !ASSET_TEMPLATE_PLACEHOLDERS.contains(&name.as_str()));
+            if let Some(unknown) = unknown {
+                bail!(
+                    Error::Config,
+                    "Unknown placeholder `{{{}}}` in `{}` {:?}",
+                    unknown,
+                    option,
+                    template
+                )
+            }
+        }
+
+        let asset_ranker = match self.asset_pattern {
+            Some(ref pattern) => {
+                let regex = Regex::new(pattern).map_err(|e| {
+                    format_err!(
+                        Error::Config,
+                        "Invalid `asset_pattern` {:?}: {}",
+                        pattern,
+                        e
+                    )
+                })?;
+                Some(Callback(
+                    Arc::new(move |name: &str| regex.is_match(name).then_some(0_i64))
+                        as Arc<AssetRanker>,
+                ))
+            }
+            None => match self.asset_matcher {
+                Some(ref matcher) => {
+                    let matcher = matcher.clone();
+                    Some(Callback(
+                        Arc::new(move |name: &str| (matcher.0)(name).then_some(0_i64))
+                            as Arc<AssetRanker>,
+                    ))
+                }
+                None => self.asset_ranker.clone(),
+            },
+        };
+
+        let api_url = overrides
+            .api_url
+            .or_else(|| self.api_url.clone())
+            .unwrap_or_else(|| DEFAULT_API_URL.to_owned());
+        let mut http = self.http.clone();
+        if overrides.auth_token.is_some() {
+            http.auth_token = overrides.auth_token;
+        }
+        if self.auth_token_from_env {
+            env_auth_token(&mut http, &api_url);
+        }
+        if policy.proxy.is_some() {
+            http.proxy = policy.proxy.clone();
+        }
+        if let (None, Some(bin_name), Some(current_version)) =
+            (&http.user_agent, &self.bin_name, &self.current_version)
+        {
+            http.user_agent = Some(format!(
+                "{}/{} {}",
+                bin_name, current_version, DEFAULT_USER_AGENT
+            ));
+        }
+        // one client, and connection pool, for every request of the update
+        http.init_client()?;
 
         Ok(Update {
             repo_owner: if let Some(ref owner) = self.repo_owner {
@@ -419,11 +1696,21 @@ impl UpdateBuilder {
                 .as_ref()
                 .map(|t| t.to_owned())
                 .unwrap_or_else(|| get_target().to_owned()),
+            target_candidates,
+            asset_ranker,
+            asset_name_template: self.asset_name_template.clone(),
+            target_asset_names: self.target_asset_names.clone(),
+            archive_format: self.archive_format.unwrap_or_else(ArchiveFormat::native),
+            latest_by_semver: self.latest_by_semver || self.release_filter.is_some(),
+            release_filter: self.release_filter.clone(),
+            extractors: self.extractors.clone(),
+            downloader: self.downloader.clone(),
             bin_name: if let Some(ref name) = self.bin_name {
                 name.to_owned()
             } else {
                 bail!(Error::Config, "`bin_name` required")
             },
+            updates_self: is_current_exe(&bin_install_path),
             bin_install_path,
             bin_path_in_archive: if let Some(ref path) = self.bin_path_in_archive {
                 path.to_owned()
@@ -435,30 +1722,134 @@ impl UpdateBuilder {
             } else {
                 bail!(Error::Config, "`current_version` required")
             },
-            target_version: self.target_version.as_ref().map(|v| v.to_owned()),
+            target_version: policy
+                .pin_version
+                .clone()
+                .or_else(|| self.target_version.clone()),
+            select_release: self.select_release,
             show_download_progress: self.show_download_progress,
             progress_style: self.progress_style.clone(),
+            max_download_speed: self.max_download_speed,
+            download_connections: self.download_connections,
+            backups: self.backups,
+            keep_old_exe: self.keep_old_exe,
+            local_changes: self.local_changes,
+            backup_dir: self.backup_dir.clone(),
+            temp_dir: self.temp_dir.clone(),
+            download_cache: self.download_cache.clone(),
+            partial_downloads: match (&self.download_cache, &self.bin_name) {
+                _ if !self.resume_downloads => None,
+                (Some(dir), _) => Some(dir.join("partial")),
+                (None, Some(bin_name)) => {
+                    dirs::cache_dir().map(|dir| dir.join(bin_name).join("partial"))
+                }
+                (None, None) => None,
+            },
+            companion_files: self.companion_files.clone(),
+            on_update: self.on_update.clone(),
+            on_attempt: self.on_attempt.clone(),
+            on_progress: self.on_progress.clone(),
+            service: self.service.clone(),
+            progress_interval: self.progress_interval,
+            total_timeout: self.total_timeout,
             show_output: self.show_output,
-            no_confirm: self.no_confirm,
+            no_confirm: overrides.no_confirm.unwrap_or(self.no_confirm),
+            non_interactive: self.non_interactive,
+            prompt_timeout: self.prompt_timeout,
+            messages: self.messages.clone(),
+            api_url,
+            disabled_by,
+            policy,
+            elevate: self.elevate,
+            defer_to_package_manager: self.defer_to_package_manager,
+            homebrew: self.homebrew,
+            route_through_package_manager: self.route_through_package_manager,
+            delta_updates: self.delta_updates,
+            checksum_algorithms: match self.checksum_algorithm {
+                Some(algorithm) if !algorithm.is_supported() => bail!(
+                    Error::Config,
+                    "{} checksums aren't supported with the enabled features",
+                    algorithm
+                ),
+                Some(algorithm) => vec![algorithm],
+                None if self.verify_checksum => checksum::Algorithm::ALL
+                    .iter()
+                    .copied()
+                    .filter(|algorithm| algorithm.is_supported())
+                    .collect(),
+                None => vec![],
+            },
+            differential_download: self.differential_download,
+            api_cache: match (&self.api_cache_file, &self.bin_name) {
+                (Some(path), _) => Some(ApiCache::at_path(path)),
+                (None, Some(bin_name)) if self.cache_api_responses => {
+                    api_cache::default_path(bin_name).map(ApiCache::at_path)
+                }
+                _ => None,
+            },
+            http,
         })
     }
 }
 
 /// Updates to a specified or latest release distributed via GitHub
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Update {
     repo_owner: String,
     repo_name: String,
     target: String,
+    target_candidates: Vec<String>,
+    asset_ranker: Option<Callback<AssetRanker>>,
+    asset_name_template: Option<String>,
+    target_asset_names: Vec<(String, String)>,
+    archive_format: ArchiveFormat,
+    latest_by_semver: bool,
+    release_filter: Option<Callback<ReleaseFilter>>,
+    extractors: Vec<Callback<dyn Extractor>>,
+    downloader: Option<Callback<dyn Downloader>>,
     current_version: String,
     target_version: Option<String>,
+    select_release: Option<usize>,
     bin_name: String,
     bin_install_path: PathBuf,
+    updates_self: bool,
     bin_path_in_archive: PathBuf,
     show_download_progress: bool,
     show_output: bool,
     no_confirm: bool,
+    non_interactive: NonInteractive,
+    prompt_timeout: PromptTimeout,
+    messages: Messages,
     progress_style: Option<ProgressStyle>,
+    max_download_speed: Option<u64>,
+    download_connections: usize,
+    backups: usize,
+    keep_old_exe: bool,
+    local_changes: LocalChanges,
+    backup_dir: Option<PathBuf>,
+    temp_dir: Option<PathBuf>,
+    download_cache: Option<PathBuf>,
+    /// Directory interrupted downloads are kept in to be resumed, see `resume_downloads`
+    partial_downloads: Option<PathBuf>,
+    companion_files: Vec<(PathBuf, PathBuf)>,
+    on_update: Option<Callback<UpdateHook>>,
+    on_attempt: Option<Callback<AttemptHook>>,
+    on_progress: Option<Callback<ProgressHook>>,
+    service: Option<Service>,
+    progress_interval: Duration,
+    total_timeout: Option<Duration>,
+    api_url: String,
+    disabled_by: Option<String>,
+    policy: Policy,
+    elevate: bool,
+    defer_to_package_manager: bool,
+    homebrew: Homebrew,
+    route_through_package_manager: bool,
+    delta_updates: bool,
+    checksum_algorithms: Vec<checksum::Algorithm>,
+    differential_download: bool,
+    api_cache: Option<ApiCache>,
+    http: HttpConfig,
 }
 impl Update {
     /// Initialize a new `Update` builder
@@ -466,42 +1857,189 @@ impl Update {
         UpdateBuilder::new()
     }
 
-    fn get_latest_release(repo_owner: &str, repo_name: &str) -> Result<Release> {
-        set_ssl_vars!();
+    /// The configured current version
+    pub fn current_version(&self) -> &str {
+        &self.current_version
+    }
+
+    /// Fetch the latest release of the configured repo, by semver if
+    /// `latest_by_semver` or a `release_filter` is set
+    pub fn get_latest_release(&self) -> Result<Release> {
+        if self.latest_by_semver {
+            return self.get_greatest_release();
+        }
         let api_url = format!(
-            "https://api.github.com/repos/{}/{}/releases/latest",
-            repo_owner, repo_name
+            "{}/repos/{}/{}/releases/latest",
+            self.api_url, self.repo_owner, self.repo_name
         );
-        let mut resp = reqwest::get(&api_url)?;
-        if !resp.status().is_success() {
+        Release::from_release(&self.api_get_json(&api_url)?)
+    }
+
+    /// The release with the greatest semver tag of those accepted by the
+    /// `release_filter`, ignoring pre-releases and tags that aren't semver versions
+    fn get_greatest_release(&self) -> Result<Release> {
+        let mut greatest: Option<(semver::Version, Release)> = None;
+        for release in self.release_list().iter() {
+            let release = release?;
+            let version = match semver::Version::parse(release.version()) {
+                Ok(version) if !version.is_prerelease() => version,
+                _ => continue,
+            };
+            if let Some(ref filter) = self.release_filter {
+                if !(filter.0)(&release) {
+                    continue;
+                }
+            }
+            if greatest.as_ref().is_none_or(|(g, _)| version > *g) {
+                greatest = Some((version, release));
+            }
+        }
+        greatest.map(|(_, release)| release).ok_or_else(|| {
+            format_err!(
+                Error::Release,
+                "No release of {}/{} with a semver tag",
+                self.repo_owner,
+                self.repo_name
+            )
+        })
+    }
+
+    /// Let the user choose among the `count` most recent releases accepted by the
+    /// `release_filter` with an asset to install, see `UpdateBuilder::select_release`
+    fn choose_release(&self, count: usize) -> Result<Release> {
+        let mut releases = vec![];
+        for release in self.release_list().iter() {
+            if releases.len() == count {
+                break;
+            }
+            let release = release?;
+            let accepted = self
+                .release_filter
+                .as_ref()
+                .is_none_or(|filter| (filter.0)(&release));
+            if accepted && self.select_asset(&release).is_some() {
+                releases.push(release);
+            }
+        }
+        if releases.is_empty() {
             bail!(
-                Error::Network,
-                "api request failed with status: {:?} - for: {:?}",
-                resp.status(),
-                api_url
+                Error::Release,
+                "No release of {}/{} for {}",
+                self.repo_owner,
+                self.repo_name,
+                self.target
             )
         }
-        let json = resp.json::<serde_json::Value>()?;
-        Ok(Release::from_release(&json)?)
+        let choices = releases
+            .iter()
+            .enumerate()
+            .map(|(i, release)| {
+                fill(
+                    &self.messages.release_choice,
+                    &[
+                        ("index", &(i + 1)),
+                        ("version", &release.version()),
+                        ("date", &release_date(release)),
+                        ("notes", &notes_summary(&release.body)),
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        let prompt = fill(&self.messages.select_release, &[("count", &releases.len())]);
+        let index = choose(&prompt, &choices, self.non_interactive, self.prompt_timeout)?;
+        Ok(releases.swap_remove(index))
+    }
+
+    /// Listing of all the releases of the repo
+    fn release_list(&self) -> ReleaseList {
+        ReleaseList {
+            repo_owner: self.repo_owner.clone(),
+            repo_name: self.repo_name.clone(),
+            target: None,
+            api_url: self.api_url.clone(),
+            http: self.http.clone(),
+        }
     }
 
-    fn get_release_version(repo_owner: &str, repo_name: &str, ver: &str) -> Result<Release> {
-        set_ssl_vars!();
+    /// Fetch the release of the configured repo tagged `ver`
+    pub fn get_release_version(&self, ver: &str) -> Result<Release> {
         let api_url = format!(
-            "https://api.github.com/repos/{}/{}/releases/tags/{}",
-            repo_owner, repo_name, ver
+            "{}/repos/{}/{}/releases/tags/{}",
+            self.api_url, self.repo_owner, self.repo_name, ver
         );
-        let mut resp = reqwest::get(&api_url)?;
-        if !resp.status().is_success() {
-            bail!(
-                Error::Network,
-                "api request failed with status: {:?} - for: {:?}",
-                resp.status(),
-                api_url
-            )
+        Release::from_release(&self.api_get_json(&api_url)?)
+    }
+
+    /// Request an api `url`, revalidating a cached response if `api_cache` is set
+    fn api_get_json(&self, url: &str) -> Result<serde_json::Value> {
+        let cache = match self.api_cache {
+            Some(ref cache) => cache,
+            None => return api_get(&self.http, url)?.json(),
+        };
+        let cached = cache.load(url);
+        let mut resp = wait_on_rate_limit(&self.http, || {
+            let resp = self.http.get_conditional(
+                url,
+                cached.as_ref().and_then(|c| c.etag.as_deref()),
+                cached.as_ref().and_then(|c| c.last_modified.as_deref()),
+            )?;
+            if resp.status() == 304 && cached.is_some() {
+                return Ok(resp);
+            }
+            check_api_response(resp, url)
+        })?;
+        if let Some(cached) = cached.filter(|_| resp.status() == 304) {
+            return Ok(cached.body);
+        }
+        let etag = resp.header("etag").map(String::from);
+        let last_modified = resp.header("last-modified").map(String::from);
+        let body = resp.json()?;
+        if etag.is_some() || last_modified.is_some() {
+            // best effort, the response is still usable without caching it
+            let _ = cache.store(
+                url,
+                &CachedResponse {
+                    etag,
+                    last_modified,
+                    body: body.clone(),
+                },
+            );
+        }
+        Ok(body)
+    }
+
+    /// The url and http settings to download `asset` with: authenticated downloads go
+    /// through the asset's api url, as `browser_download_url` isn't accessible for
+    /// private repos
+    fn asset_download(&self, asset: &ReleaseAsset) -> (String, HttpConfig) {
+        let mut http = self.http.clone();
+        match asset.api_url {
+            Some(ref api_url) if http.auth_token.is_some() => {
+                http.accept = Some("application/octet-stream".to_owned());
+                (api_url.clone(), http)
+            }
+            _ => (asset.download_url.clone(), http),
+        }
+    }
+
+    /// * Errors:
+    ///     * TimedOut - `total_timeout` has elapsed since the update started
+    fn check_deadline(&self) -> Result<()> {
+        match self.http.deadline {
+            Some(deadline) => deadline.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Report reaching `phase` to the `on_progress` hook
+    fn report(&self, phase: Phase) {
+        if let Some(ref hook) = self.on_progress {
+            (hook.0)(DownloadProgress {
+                bytes: 0,
+                total: None,
+                phase,
+            });
         }
-        let json = resp.json::<serde_json::Value>()?;
-        Ok(Release::from_release(&json)?)
     }
 
     fn print_flush(&self, msg: &str) -> Result<()> {
@@ -517,6 +2055,20 @@ impl Update {
         }
     }
 
+    /// Restart the configured `service`, if any and not restarted when replacing
+    /// the executable
+    fn restart_service(&self) -> Result<()> {
+        if let Some(service) = self.service.as_ref().filter(|s| !s.stops_for_replace()) {
+            self.print_flush(&fill(
+                &self.messages.restarting_service,
+                &[("service", service)],
+            ))?;
+            service.restart(self.updates_self)?;
+            self.println(&self.messages.done);
+        }
+        Ok(())
+    }
+
     /// Display release information and update the current binary to the latest release, pending
     /// confirmation from the user
     pub fn update(self) -> Result<Status> {
@@ -526,96 +2078,1345 @@ impl Update {
     }
 
     /// Same as `update`, but returns `GitHubUpdateStatus`.
-    pub fn update_extended(self) -> Result<GitHubUpdateStatus> {
-        self.println(&format!("Checking target-arch... {}", self.target));
-        self.println(&format!(
-            "Checking current version... v{}",
-            self.current_version
-        ));
+    ///
+    /// * Errors:
+    ///     * Disabled - Updates are disabled by an environment variable
+    ///     * PermissionDenied - The install directory isn't writable
+    ///     * InsufficientSpace - Not enough disk space to download and install the release
+    ///     * PackageManaged - The executable should be updated by a package manager
+    ///     * Update - The package manager failed, see `homebrew` and
+    ///       `route_through_package_manager`
+    ///     * Locked - Another update of the executable is in progress
+    ///     * InUse - `bin_install_path` isn't this executable and is being run
+    ///     * TimedOut - The update took longer than `total_timeout`
+    pub fn update_extended(mut self) -> Result<GitHubUpdateStatus> {
+        let started = Instant::now();
+        self.http.deadline = self.total_timeout.map(Deadline::after);
+        let result = self.try_update();
+        if let Ok(GitHubUpdateStatus::UpToDate) = result {
+            // the executable left by a previous update isn't needed anymore, best effort
+            let _ = self.remove_old_exe();
+        }
+        if let Some(ref hook) = self.on_attempt {
+            let (new_version, outcome) = match result {
+                Ok(GitHubUpdateStatus::UpToDate) => (None, Outcome::UpToDate),
+                Ok(GitHubUpdateStatus::Updated(ref release, _)) => {
+                    (Some(release.version().to_owned()), Outcome::Updated)
+                }
+                Err(ref e) => (None, Outcome::Failed(e.category())),
+            };
+            (hook.0)(&UpdateRecord {
+                current_version: self.current_version.clone(),
+                new_version,
+                duration: started.elapsed(),
+                outcome,
+            });
+        }
+        result
+    }
 
-        let release = match self.target_version {
-            None => {
-                self.print_flush("Checking latest released version... ")?;
-                let release = Self::get_latest_release(&self.repo_owner, &self.repo_name)?;
-                {
-                    let release_tag = release.version();
-                    self.println(&format!("v{}", release_tag));
+    /// Run `update_extended` on a new thread and return right away, e.g. to keep a
+    /// desktop application's UI responsive. Progress is reported to the returned
+    /// handle, besides any `on_progress` callback. Confirmation prompts would block
+    /// the update, so GUIs should ask beforehand and set `no_confirm`.
+    ///
+    /// ```no_run
+    /// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+    /// let update = self_update::backends::github::Update::configure()
+    ///     .repo_owner("jaemk")
+    ///     .repo_name("self_update")
+    ///     .bin_name("self_update_example")
+    ///     .current_version(self_update::cargo_crate_version!())
+    ///     .no_confirm(true)
+    ///     .build()?
+    ///     .spawn();
+    /// for progress in update.progress().iter() {
+    ///     println!("{:.0}%", progress.overall_percent());
+    /// }
+    /// let status = update.join()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn(mut self) -> UpdateHandle {
+        let (sender, progress) = mpsc::channel();
+        let hook = self.on_progress.take();
+        self.on_progress = Some(Callback(Arc::new(move |report: DownloadProgress| {
+            if let Some(ref hook) = hook {
+                (hook.0)(report);
+            }
+            // the handle may have been dropped if the app no longer cares
+            let _ = sender.send(report);
+        })));
+        let worker = thread::spawn(move || self.update_extended());
+        UpdateHandle { progress, worker }
+    }
 
-                    if !version::bump_is_greater(&self.current_version, &release_tag)? {
+    fn try_update(&self) -> Result<GitHubUpdateStatus> {
+        if let Some(ref var) = self.disabled_by {
+            return Err(Error::Disabled(var.clone()));
+        }
+        if let Some(formula) = self.homebrew_formula() {
+            return self.update_with_homebrew(&formula);
+        }
+        if self.bin_install_path.exists()
+            && (self.defer_to_package_manager || self.route_through_package_manager)
+        {
+            match PackageManager::detect(&self.bin_install_path) {
+                Some(manager) if self.route_through_package_manager && manager.can_upgrade() => {
+                    return self.update_with_package_manager(manager);
+                }
+                Some(manager) if self.defer_to_package_manager => {
+                    return Err(Error::PackageManaged(manager));
+                }
+                _ => (),
+            }
+        }
+        self.check_install_dir()?;
+        let _lock = self.lock()?;
+        self.check_not_running()?;
+        // best effort, leftovers only waste space
+        let _ = self.remove_stale_tmp_dirs();
+        self.report(Phase::Checking);
+        self.println(&fill(
+            &self.messages.checking_target,
+            &[("target", &self.target)],
+        ));
+        let installed = self.bin_install_path.exists();
+        if installed {
+            self.println(&fill(
+                &self.messages.checking_current_version,
+                &[("version", &self.current_version)],
+            ));
+        } else {
+            self.println(&fill(
+                &self.messages.not_installed,
+                &[("path", &format!("{:?}", self.bin_install_path))],
+            ));
+        }
+
+        let release = match (&self.target_version, self.select_release) {
+            (None, Some(count)) => self.choose_release(count)?,
+            (None, None) => {
+                self.print_flush(&self.messages.checking_latest_version)?;
+                let release = self.get_latest_release()?;
+                let release_tag = release.version();
+                self.println(&fill(
+                    &self.messages.latest_version,
+                    &[("version", &release_tag)],
+                ));
+                if installed {
+                    if !version::bump_is_greater(&self.current_version, release_tag)? {
+                        return Ok(GitHubUpdateStatus::UpToDate);
+                    }
+                    if !self.policy.allows(&self.current_version, release_tag)? {
+                        self.println(&fill(
+                            &self.messages.exceeds_policy,
+                            &[("version", &release_tag)],
+                        ));
                         return Ok(GitHubUpdateStatus::UpToDate);
                     }
 
-                    self.println(&format!(
-                        "New release found! v{} --> v{}",
-                        &self.current_version, release_tag
+                    self.println(&fill(
+                        &self.messages.new_release,
+                        &[("current", &self.current_version), ("latest", &release_tag)],
                     ));
-                    let qualifier =
-                        if version::bump_is_compatible(&self.current_version, &release_tag)? {
-                            ""
-                        } else {
-                            "*NOT* "
-                        };
-                    self.println(&format!("New release is {}compatible", qualifier));
+                    if version::bump_is_compatible(&self.current_version, release_tag)? {
+                        self.println(&self.messages.compatible);
+                    } else {
+                        self.println(&self.messages.incompatible);
+                    }
                 }
                 release
             }
-            Some(ref ver) => {
-                self.println(&format!("Looking for tag: {}", ver));
-                Self::get_release_version(&self.repo_owner, &self.repo_name, ver)?
+            (Some(ver), _) => {
+                self.println(&fill(&self.messages.looking_for_tag, &[("tag", ver)]));
+                self.get_release_version(ver)?
             }
         };
 
-        let target_asset = release.asset_for(&self.target).ok_or_else(|| {
-            format_err!(
-                Error::Release,
-                "No asset found for target: `{}`",
-                self.target
-            )
-        })?;
+        let target_asset = self
+            .select_asset(&release)
+            .ok_or_else(|| Error::NoAssetForTarget(self.target.clone()))?;
+        if is_rosetta_translated() && target_asset.name.contains("aarch64") {
+            self.println(&self.messages.rosetta);
+        }
 
         if self.show_output || !self.no_confirm {
-            println!("\n{} release status:", self.bin_name);
-            println!("  * Current exe: {:?}", self.bin_install_path);
-            println!("  * New exe release: {:?}", target_asset.name);
-            println!("  * New exe download url: {:?}", target_asset.download_url);
-            println!("\nThe new release will be downloaded/extracted and the existing binary will be replaced.");
+            println!(
+                "{}",
+                fill(
+                    &self.messages.release_status,
+                    &[
+                        ("bin_name", &self.bin_name),
+                        ("path", &format!("{:?}", self.bin_install_path)),
+                        ("asset", &format!("{:?}", target_asset.name)),
+                        ("url", &format!("{:?}", target_asset.download_url)),
+                    ],
+                )
+            );
+        }
+        let mut confirmed = false;
+        if installed && self.local_changes != LocalChanges::Ignore && self.is_locally_modified()? {
+            if self.show_output || !self.no_confirm || self.local_changes == LocalChanges::Confirm {
+                println!(
+                    "{}",
+                    fill(
+                        &self.messages.locally_modified,
+                        &[
+                            ("path", &format!("{:?}", self.bin_install_path)),
+                            ("version", &self.current_version),
+                        ],
+                    )
+                );
+            }
+            if self.local_changes == LocalChanges::Confirm {
+                confirm(
+                    &self.messages.confirm,
+                    &self.messages.confirm_answers,
+                    self.non_interactive,
+                    self.prompt_timeout,
+                )?;
+                confirmed = true;
+            }
+        }
+        if !self.no_confirm && !confirmed {
+            confirm(
+                &self.messages.confirm,
+                &self.messages.confirm_answers,
+                self.non_interactive,
+                self.prompt_timeout,
+            )?;
+        }
+
+        let tmp_dir = self.tmp_dir()?;
+        self.check_space(&target_asset, tmp_dir.path())?;
+
+        let checksum = self.published_checksum(&release, &target_asset)?;
+        let replaced = exe_id(&self.bin_install_path);
+        let cached = self.cache_path(&release, &target_asset);
+        let reusable = cached
+            .as_deref()
+            .filter(|path| cached_archive_intact(path, checksum.as_ref()));
+        if let Some(archive) = reusable {
+            self.install(archive, tmp_dir.path())?;
+        } else {
+            self.download_release(
+                &release,
+                &target_asset,
+                checksum.as_ref(),
+                cached.as_deref(),
+                installed,
+                tmp_dir.path(),
+            )?;
+        }
+        if let Some(ref hook) = self.on_update {
+            (hook.0)(
+                &self.current_version,
+                release.version(),
+                &self.bin_install_path,
+            );
+        }
+        self.restart_service()?;
+        self.report(Phase::Done);
+        let old_exe = Some(old_exe_path(&self.bin_install_path)).filter(|p| p.exists());
+        let pids = match replaced {
+            Some(id) => processes_running(id),
+            None if self.updates_self => vec![process::id()],
+            None => vec![],
+        };
+        // Windows refuses writing to a running executable
+        let running = cfg!(windows) && old_exe.as_deref().is_some_and(is_executing);
+        Ok(GitHubUpdateStatus::Updated(
+            release,
+            Installed {
+                old_exe,
+                restart_required: running || !pids.is_empty(),
+                pids,
+            },
+        ))
+    }
+
+    /// The Homebrew formula of the executable, if installed by Homebrew and the
+    /// `homebrew` mode isn't `Homebrew::Defer`
+    fn homebrew_formula(&self) -> Option<String> {
+        if self.homebrew == Homebrew::Defer || !self.bin_install_path.exists() {
+            return None;
         }
+        match PackageManager::detect(&self.bin_install_path) {
+            Some(PackageManager::Homebrew(formula)) => Some(formula),
+            _ => None,
+        }
+    }
+
+    /// Update to the version of the Homebrew `formula` with `brew upgrade`, or fail
+    /// with `Error::PackageManaged` if only checking
+    fn update_with_homebrew(&self, formula: &str) -> Result<GitHubUpdateStatus> {
+        self.report(Phase::Checking);
+        self.print_flush(&self.messages.checking_latest_version)?;
+        let latest = package_manager::homebrew_formula_version(formula)?;
+        self.println(&fill(
+            &self.messages.latest_version,
+            &[("version", &latest)],
+        ));
+        if !version::bump_is_greater(&self.current_version, &latest)? {
+            return Ok(GitHubUpdateStatus::UpToDate);
+        }
+        let manager = PackageManager::Homebrew(formula.to_owned());
+        if self.homebrew == Homebrew::Check {
+            return Err(Error::PackageManaged(manager));
+        }
+        let release = Release {
+            name: formula.to_owned(),
+            body: String::new(),
+            tag: latest,
+            date_created: String::new(),
+            assets: vec![],
+        };
+        self.upgrade_package(&manager, release)
+    }
+
+    /// Update the package of `manager` with it if the latest GitHub release is newer
+    fn update_with_package_manager(&self, manager: PackageManager) -> Result<GitHubUpdateStatus> {
+        if let Some(ref tag) = self.target_version {
+            bail!(
+                Error::Config,
+                "Can't install {} through {}, which installs its latest version",
+                tag,
+                manager
+            );
+        }
+        self.report(Phase::Checking);
+        self.print_flush(&self.messages.checking_latest_version)?;
+        let release = self.get_latest_release()?;
+        self.println(&fill(
+            &self.messages.latest_version,
+            &[("version", &release.version())],
+        ));
+        if !version::bump_is_greater(&self.current_version, release.version())? {
+            return Ok(GitHubUpdateStatus::UpToDate);
+        }
+        self.upgrade_package(&manager, release)
+    }
+
+    /// Update to `release` by running the upgrade command of `manager`, pending
+    /// confirmation from the user
+    fn upgrade_package(
+        &self,
+        manager: &PackageManager,
+        release: Release,
+    ) -> Result<GitHubUpdateStatus> {
+        self.println(&fill(
+            &self.messages.new_release,
+            &[
+                ("current", &self.current_version),
+                ("latest", &release.version()),
+            ],
+        ));
         if !self.no_confirm {
-            confirm("Do you want to continue? [Y/n] ")?;
+            confirm(
+                &self.messages.confirm,
+                &self.messages.confirm_answers,
+                self.non_interactive,
+                self.prompt_timeout,
+            )?;
         }
+        let command = manager.upgrade_command();
+        self.print_flush(&fill(&self.messages.running, &[("command", &command)]))?;
+        self.report(Phase::Installing);
+        manager.upgrade()?;
+        self.println(&self.messages.done);
+        if let Some(ref hook) = self.on_update {
+            (hook.0)(
+                &self.current_version,
+                release.version(),
+                &self.bin_install_path,
+            );
+        }
+        self.report(Phase::Done);
+        Ok(GitHubUpdateStatus::Updated(release, Installed::default()))
+    }
 
-        let tmp_dir_parent = self
-            .bin_install_path
-            .parent()
-            .ok_or_else(|| Error::Update("Failed to determine parent dir".into()))?;
-        let tmp_dir =
-            tempdir::TempDir::new_in(&tmp_dir_parent, &format!("{}_download", self.bin_name))?;
-        let tmp_archive_path = tmp_dir.path().join(&target_asset.name);
-        let mut tmp_archive = fs::File::create(&tmp_archive_path)?;
+    /// Download `target_asset` of `release`, or just the patch or changed blocks of the
+    /// `installed` executable if possible, into `tmp_dir` and install the new executable.
+    /// A downloaded archive is stored at `cache_path` if set.
+    fn download_release(
+        &self,
+        release: &Release,
+        target_asset: &ReleaseAsset,
+        checksum: Option<&(checksum::Algorithm, String)>,
+        cache_path: Option<&Path>,
+        installed: bool,
+        tmp_dir: &Path,
+    ) -> Result<()> {
+        self.println(&self.messages.downloading);
+        let mut new_exe = None;
+        if installed && self.delta_updates {
+            new_exe = self.download_patched_exe(release, target_asset, checksum, tmp_dir);
+        }
+        if installed && self.differential_download && self.downloader.is_none() && new_exe.is_none()
+        {
+            new_exe = self.download_changed_blocks(release, target_asset, checksum, tmp_dir);
+        }
+        if let Some(new_exe) = new_exe {
+            return self.replace_exe(&new_exe, tmp_dir);
+        }
+        match self.download_and_install(target_asset, checksum, cache_path, tmp_dir) {
+            // signed download urls expire, e.g. while retrying, ask for fresh ones
+            Err(Error::HttpStatus { status: 403, .. }) => {
+                let asset = self.refreshed_asset(release, target_asset)?;
+                self.download_and_install(&asset, checksum, cache_path, tmp_dir)
+            }
+            result => result,
+        }
+    }
+
+    /// Path `asset` of `release` is kept at in the `download_cache`, if there's one
+    fn cache_path(&self, release: &Release, asset: &ReleaseAsset) -> Option<PathBuf> {
+        self.download_cache.as_ref().map(|dir| {
+            dir.join(release.version())
+                .join(&self.target)
+                .join(&asset.name)
+        })
+    }
 
-        self.println("Downloading...");
-        let mut download = Download::from_url(&target_asset.download_url);
+    /// Download `asset` into `tmp_dir` and install the executable it contains, storing
+    /// the downloaded archive at `cache_path` if set
+    fn download_and_install(
+        &self,
+        asset: &ReleaseAsset,
+        checksum: Option<&(checksum::Algorithm, String)>,
+        cache_path: Option<&Path>,
+        tmp_dir: &Path,
+    ) -> Result<()> {
+        self.check_deadline()?;
+        let tmp_archive_path = tmp_dir.join(&asset.name);
+        let (url, http) = self.asset_download(asset);
+        let mut download = Download::from_url(&url);
         download.show_progress(self.show_download_progress);
+        download.http = http;
+        download.progress = self.on_progress.clone();
+        download.progress_interval = self.progress_interval;
 
         if let Some(ref progress_style) = self.progress_style {
             download.set_progress_style(progress_style.clone());
         }
+        if let Some(bytes_per_sec) = self.max_download_speed {
+            download.max_download_speed(bytes_per_sec);
+        }
+        download.connections(self.download_connections);
+        if let Some((algorithm, expected)) = checksum {
+            download.checksum(*algorithm, expected);
+        }
+        let partial = self
+            .partial_downloads
+            .as_ref()
+            .map(|dir| dir.join(&asset.name));
+        if let Some(ref partial) = partial {
+            download.resume_from(partial);
+        }
+
+        let archive_kind = detect_archive(&tmp_archive_path);
+        if let Some(ref downloader) = self.downloader {
+            self.report(Phase::Downloading);
+            let mut archive = fs::File::create(&tmp_archive_path)?;
+            downloader.0.download(&asset.download_url, &mut archive)?;
+            archive.sync_all()?;
+            if let Some((algorithm, expected)) = checksum {
+                self.report(Phase::Verifying);
+                algorithm.verify(&tmp_archive_path, expected)?;
+            }
+            self.cache_archive(&tmp_archive_path, cache_path);
+            self.install(&tmp_archive_path, tmp_dir)
+        } else if cache_path.is_none()
+            && partial.is_none()
+            && self.companion_files.is_empty()
+            && archive_kind != ArchiveKind::Zip
+            && self.download_connections == 1
+            && extractor::find(&self.extractors, &tmp_archive_path).is_none()
+        {
+            // extract while downloading, companion files would need a second pass
+            download.download_extract_file(archive_kind, tmp_dir, &self.bin_path_in_archive)?;
+            let new_exe = self.extracted_exe_path(archive_kind, tmp_dir)?;
+            self.replace_exe(&new_exe, tmp_dir)
+        } else {
+            download.download_to_file(&tmp_archive_path)?;
+            self.cache_archive(&tmp_archive_path, cache_path);
+            self.install(&tmp_archive_path, tmp_dir)
+        }
+    }
+
+    /// Store a copy of the verified `archive` at `cache_path` if set, with a `.sha256`
+    /// file to check its integrity when it's reused. Best effort, failing to cache
+    /// doesn't fail the update.
+    fn cache_archive(&self, archive: &Path, cache_path: Option<&Path>) {
+        let cache_path = match cache_path {
+            Some(path) => path,
+            None => return,
+        };
+        let result = (|| -> Result<()> {
+            let dir = cache_path
+                .parent()
+                .ok_or_else(|| Error::Config("`download_cache` has no parent".into()))?;
+            fs::create_dir_all(dir)?;
+            let sum = checksum::Algorithm::Sha256.file_checksum(archive)?;
+            // copied under a temporary name, so a cached archive is always complete
+            let partial = suffixed(cache_path, ".part");
+            fs::copy(archive, &partial)?;
+            fs::write(suffixed(cache_path, ".sha256"), format!("{}\n", sum))?;
+            fs::rename(&partial, cache_path)?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            self.println(&fill(
+                &self.messages.cache_failed,
+                &[("path", &format!("{:?}", cache_path)), ("error", &e)],
+            ));
+        }
+    }
+
+    /// Whether `bin_install_path` differs from the executable released as
+    /// `current_version`, compared with the digest GitHub published for a plain
+    /// asset, or else with the executable extracted from the downloaded asset.
+    /// `false` if there's no such release, tagged with or without a `v` prefix, or it
+    /// has no asset for the target.
+    ///
+    /// * Errors:
+    ///     * Network / HttpStatus - Fetching the release or downloading its asset
+    ///     * Io - Reading the installed executable or extracting the asset
+    fn is_locally_modified(&self) -> Result<bool> {
+        let mut release = None;
+        for tag in &[
+            format!("v{}", self.current_version),
+            self.current_version.clone(),
+        ] {
+            match self.get_release_version(tag) {
+                Ok(found) => {
+                    release = Some(found);
+                    break;
+                }
+                Err(Error::HttpStatus { status: 404, .. }) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        let asset = match release.and_then(|release| self.select_asset(&release)) {
+            Some(asset) => asset,
+            None => return Ok(false),
+        };
+        let digest = asset
+            .digest
+            .as_deref()
+            .and_then(checksum::parse_digest)
+            .filter(|(algorithm, _)| algorithm.is_supported());
+        if let Some((algorithm, digest)) = digest {
+            if detect_archive(Path::new(&asset.name)) == ArchiveKind::Plain(None) {
+                let installed = algorithm.file_checksum(&self.bin_install_path)?;
+                return Ok(!installed.eq_ignore_ascii_case(&digest));
+            }
+        }
+
+        let tmp_dir = self.tmp_dir()?;
+        let archive = tmp_dir.path().join(&asset.name);
+        if let Some(ref downloader) = self.downloader {
+            let mut file = fs::File::create(&archive)?;
+            downloader.0.download(&asset.download_url, &mut file)?;
+        } else {
+            let (url, http) = self.asset_download(&asset);
+            let mut download = Download::from_url(&url);
+            download.http = http;
+            download.download_to_file(&archive)?;
+        }
+        // a separate dir, a plain asset may be named like the executable
+        let released_dir = tmp_dir.path().join("released");
+        fs::create_dir(&released_dir)?;
+        let released = extractor::extract_file(
+            &self.extractors,
+            &archive,
+            &released_dir,
+            &self.bin_path_in_archive,
+        )?;
+        let algorithm = checksum::Algorithm::Sha256;
+        Ok(algorithm.file_checksum(&released)?
+            != algorithm.file_checksum(&self.bin_install_path)?)
+    }
+
+    /// The checksum to verify `asset` of `release` with, and its algorithm: if
+    /// `verify_checksum` is set, the one published with the release, trying the
+    /// strongest algorithms first, else the digest GitHub computed for the asset.
+    /// The digest is also the fallback when no checksum was published.
+    ///
+    /// * Errors:
+    ///     * Release - No checksum of the asset was published
+    ///     * Network / HttpStatus - Downloading a checksum file
+    fn published_checksum(
+        &self,
+        release: &Release,
+        asset: &ReleaseAsset,
+    ) -> Result<Option<(checksum::Algorithm, String)>> {
+        let digest = asset
+            .digest
+            .as_deref()
+            .and_then(checksum::parse_digest)
+            .filter(|(algorithm, _)| {
+                algorithm.is_supported()
+                    && (self.checksum_algorithms.is_empty()
+                        || self.checksum_algorithms.contains(algorithm))
+            });
+        if self.checksum_algorithms.is_empty() {
+            return Ok(digest);
+        }
+        for &algorithm in &self.checksum_algorithms {
+            let sidecars = algorithm
+                .sidecar_suffixes()
+                .iter()
+                .map(|suffix| format!("{}{}", asset.name, suffix));
+            let manifests = algorithm
+                .manifest_names()
+                .iter()
+                .map(|&name| name.to_owned());
+            for name in sidecars.chain(manifests) {
+                let file = match release.assets.iter().find(|a| a.name == name) {
+                    Some(file) => file,
+                    None => continue,
+                };
+                let (url, http) = self.asset_download(file);
+                let mut download = Download::from_url(&url);
+                download.http = http;
+                let mut content = vec![];
+                download.download_to(&mut content)?;
+                let content = String::from_utf8_lossy(&content);
+                if let Some(sum) = checksum::find_checksum(&content, &asset.name, algorithm) {
+                    return Ok(Some((algorithm, sum)));
+                }
+            }
+        }
+        if digest.is_some() {
+            return Ok(digest);
+        }
+        bail!(
+            Error::Release,
+            "No checksum of {} published with release {}",
+            asset.name,
+            release.tag
+        )
+    }
+
+    /// `asset` of `release` as currently listed by the api, with a fresh download url
+    fn refreshed_asset(&self, release: &Release, asset: &ReleaseAsset) -> Result<ReleaseAsset> {
+        self.get_release_version(&release.tag)?
+            .assets
+            .into_iter()
+            .find(|a| a.name == asset.name)
+            .ok_or_else(|| {
+                format_err!(
+                    Error::Release,
+                    "Asset {} is no longer part of release {}",
+                    asset.name,
+                    release.tag
+                )
+            })
+    }
+
+    /// Install the executable contained in an already downloaded release `archive`,
+    /// without contacting GitHub. `archive` may also be a raw (optionally gzipped)
+    /// executable. The archive format is determined from the file extension.
+    ///
+    /// This runs the same verification, extraction and replacement steps as `update`,
+    /// e.g. for machines without network access where the release is copied over by
+    /// hand. `archive` is verified against the hex encoded `checksum`, if given, e.g.
+    /// the one published with the release. With `verify_checksum` or
+    /// `checksum_algorithm` set, a checksum of an accepted algorithm is required.
+    ///
+    /// * Errors:
+    ///     * Config - No `checksum` given while `verify_checksum` is set, or one of an
+    ///       algorithm other than the `checksum_algorithm`
+    ///     * ChecksumMismatch - `archive` doesn't have the `checksum`
+    ///     * ArchiveEntryNotFound - `bin_path_in_archive` isn't in the archive
+    ///     * PermissionDenied - `bin_install_path` isn't writable
+    ///     * Locked - Another update of the executable is in progress
+    ///     * InUse - `bin_install_path` isn't this executable and is being run
+    ///     * Update - Restarting the `service` failed
+    ///     * Io - reading the archive / replacing the binary
+    pub fn install_from_archive<P: AsRef<Path>>(
+        &self,
+        archive: P,
+        checksum: Option<(checksum::Algorithm, &str)>,
+    ) -> Result<()> {
+        let archive = archive.as_ref();
+        match checksum {
+            Some((algorithm, _))
+                if !self.checksum_algorithms.is_empty()
+                    && !self.checksum_algorithms.contains(&algorithm) =>
+            {
+                bail!(
+                    Error::Config,
+                    "{} checksums aren't accepted by this updater",
+                    algorithm
+                )
+            }
+            Some((algorithm, expected)) => algorithm.verify(archive, expected)?,
+            None if !self.checksum_algorithms.is_empty() => bail!(
+                Error::Config,
+                "A checksum of {} is required to verify it",
+                archive.display()
+            ),
+            None => (),
+        }
+        let _lock = self.lock()?;
+        self.check_not_running()?;
+        let tmp_dir = self.tmp_dir()?;
+        self.install(archive, tmp_dir.path())?;
+        self.restart_service()
+    }
+
+    /// The asset of `release` to install: the one named for the first target candidate
+    /// with a name in `target_asset_names`, or the first named after the
+    /// `asset_name_template`, or the highest ranked by the `asset_ranker`, built from
+    /// `asset_pattern` or `asset_matcher` if those are set, otherwise by
+    /// `default_asset_rank` for the `target_candidates`, preferring the `archive_format`
+    fn select_asset(&self, release: &Release) -> Option<ReleaseAsset> {
+        let named = self.target_candidates.iter().find_map(|target| {
+            self.target_asset_names
+                .iter()
+                .find(|(t, _)| t == target)
+                .map(|(_, name)| (target, name))
+        });
+        if let Some((target, name)) = named {
+            return self.template_asset(name, release, std::slice::from_ref(target));
+        }
+        if let Some(ref template) = self.asset_name_template {
+            return self.template_asset(template, release, &self.target_candidates);
+        }
+        match self.asset_ranker {
+            Some(ref ranker) => best_asset(&release.assets, |name| (ranker.0)(name)),
+            None => best_asset(&release.assets, |name| {
+                asset_rank(name, &self.target_candidates, self.archive_format)
+            }),
+        }
+    }
+
+    /// The first asset of `release` named after `template` for one of `targets`
+    fn template_asset(
+        &self,
+        template: &str,
+        release: &Release,
+        targets: &[String],
+    ) -> Option<ReleaseAsset> {
+        self.template_asset_names(template, release, targets)
+            .iter()
+            .find_map(|name| release.assets.iter().find(|asset| asset.name == *name))
+            .cloned()
+    }
+
+    /// The asset names `template` resolves to for `release` and `targets`, in order of
+    /// preference
+    fn template_asset_names(
+        &self,
+        template: &str,
+        release: &Release,
+        targets: &[String],
+    ) -> Vec<String> {
+        let extensions = if template.contains("{ext}") {
+            let mut extensions = ASSET_EXTENSIONS.to_vec();
+            extensions.sort_by_key(|ext| {
+                let kind = detect_archive(Path::new(&format!("asset.{}", ext)));
+                !self.archive_format.matches(kind)
+            });
+            extensions
+        } else {
+            vec![""]
+        };
+        let mut names = vec![];
+        for target in targets {
+            for ext in &extensions {
+                names.push(fill(
+                    template,
+                    &[
+                        ("bin", &self.bin_name),
+                        ("version", &release.version()),
+                        ("tag", &release.tag),
+                        ("target", target),
+                        ("ext", ext),
+                    ],
+                ));
+            }
+        }
+        names
+    }
+
+    /// Download the release's patch from the current version, if any, and apply it to
+    /// the installed executable, returning the path of the patched executable.
+    /// With a `checksum` of `target_asset`, the patched executable is verified against
+    /// it if the asset is an uncompressed executable, else the patch is verified against
+    /// its own published checksum. `None` if there's no patch, or it failed to apply or
+    /// to verify.
+    fn download_patched_exe(
+        &self,
+        release: &Release,
+        target_asset: &ReleaseAsset,
+        checksum: Option<&(checksum::Algorithm, String)>,
+        tmp_dir: &Path,
+    ) -> Option<PathBuf> {
+        let patch_name = format!("{}.from-{}.bsdiff", target_asset.name, self.current_version);
+        let patch_asset = release.assets.iter().find(|a| a.name == patch_name)?;
+        let new_exe = tmp_dir.join(format!("{}.patched", self.bin_name));
+        let result = (|| -> Result<()> {
+            self.println(&fill(
+                &self.messages.downloading_patch,
+                &[("name", &patch_name)],
+            ));
+            let mut patch = vec![];
+            if let Some(ref downloader) = self.downloader {
+                downloader
+                    .0
+                    .download(&patch_asset.download_url, &mut patch)?;
+            } else {
+                let (url, http) = self.asset_download(patch_asset);
+                let mut download = Download::from_url(&url);
+                download.show_progress(self.show_download_progress);
+                download.http = http;
+                download.progress = self.on_progress.clone();
+                download.progress_interval = self.progress_interval;
+                if let Some(bytes_per_sec) = self.max_download_speed {
+                    download.max_download_speed(bytes_per_sec);
+                }
+                download.download_to(&mut patch)?;
+            }
+            let plain = detect_archive(Path::new(&target_asset.name)) == ArchiveKind::Plain(None);
+            if checksum.is_some() && !plain {
+                // the asset's checksum is the archive's, vouch for the patch instead
+                let (algorithm, expected) = self
+                    .published_checksum(release, patch_asset)?
+                    .ok_or_else(|| {
+                        format_err!(Error::Release, "No checksum of {} published", patch_name)
+                    })?;
+                let mut hasher = algorithm.hasher()?;
+                hasher.write_all(&patch)?;
+                checksum::check(hasher.finish(), &expected)?;
+            }
+            apply_patch(&self.bin_install_path, &patch, &new_exe)?;
+            match checksum {
+                Some((algorithm, expected)) if plain => algorithm.verify(&new_exe, expected),
+                _ => Ok(()),
+            }
+        })();
+        match result {
+            Ok(()) => Some(new_exe),
+            Err(e) => {
+                self.println(&fill(&self.messages.patch_failed, &[("error", &e)]));
+                None
+            }
+        }
+    }
+
+    /// Rebuild the new executable from the blocks of the installed executable and the
+    /// changed blocks of `target_asset`, returning the path of the rebuilt executable.
+    /// The rebuilt executable is verified against the index, and the `checksum` of
+    /// `target_asset` if any. `None` if the release has no block index for the asset,
+    /// or rebuilding failed.
+    fn download_changed_blocks(
+        &self,
+        release: &Release,
+        target_asset: &ReleaseAsset,
+        checksum: Option<&(checksum::Algorithm, String)>,
+        tmp_dir: &Path,
+    ) -> Option<PathBuf> {
+        if detect_archive(Path::new(&target_asset.name)) != ArchiveKind::Plain(None) {
+            return None;
+        }
+        let index_name = format!("{}.blocks.json", target_asset.name);
+        let index_asset = release.assets.iter().find(|a| a.name == index_name)?;
+        let new_exe = tmp_dir.join(format!("{}.rebuilt", self.bin_name));
+        let result = (|| -> Result<()> {
+            let (url, http) = self.asset_download(index_asset);
+            let mut download = Download::from_url(&url);
+            download.http = http;
+            let mut index = vec![];
+            download.download_to(&mut index)?;
+            let index = BlockIndex::from_json(&String::from_utf8_lossy(&index))?;
+
+            let old = fs::read(&self.bin_install_path)?;
+            let found = index.find_blocks(&old);
+            self.println(&fill(
+                &self.messages.downloading_changed,
+                &[("size", &index.download_size(&found))],
+            ));
+            let (target_url, target_http) = self.asset_download(target_asset);
+            let new = index.assemble(&old, &found, |start, end| {
+                let mut data = vec![];
+                target_http
+                    .get_range(&target_url, start, end)?
+                    .read_to_end(&mut data)?;
+                Ok(data)
+            })?;
+            if let Some((algorithm, expected)) = checksum {
+                let mut hasher = algorithm.hasher()?;
+                hasher.write_all(&new)?;
+                checksum::check(hasher.finish(), expected)?;
+            }
+            fs::write(&new_exe, new)?;
+            Ok(())
+        })();
+        match result {
+            Ok(()) => Some(new_exe),
+            Err(e) => {
+                self.println(&fill(&self.messages.differential_failed, &[("error", &e)]));
+                None
+            }
+        }
+    }
+
+    /// Prevent concurrent updates of `bin_install_path` for as long as the lock is held
+    fn lock(&self) -> Result<UpdateLock> {
+        UpdateLock::acquire(&lock::path(&self.bin_install_path))
+    }
+
+    /// Create a temporary working directory in the `temp_dir`, or next to
+    /// `bin_install_path` so the extracted executable can be renamed into place
+    fn tmp_dir(&self) -> Result<tempdir::TempDir> {
+        match self.temp_dir {
+            Some(ref dir) => self.tmp_dir_in(dir),
+            None => self.tmp_dir_in(self.install_dir()?),
+        }
+    }
+
+    /// Create a temporary working directory in `parent`, which is created if needed
+    fn tmp_dir_in(&self, parent: &Path) -> Result<tempdir::TempDir> {
+        // first-time installs may target a directory that doesn't exist yet
+        fs::create_dir_all(parent)?;
+        Ok(tempdir::TempDir::new_in(parent, &self.tmp_dir_prefix())?)
+    }
+
+    /// Name prefix of the temporary working directories
+    fn tmp_dir_prefix(&self) -> String {
+        format!("{}_download", self.bin_name)
+    }
+
+    fn install_dir(&self) -> Result<&Path> {
+        self.bin_install_path
+            .parent()
+            .ok_or_else(|| Error::Update("Failed to determine parent dir".into()))
+    }
+
+    /// Remove temporary working directories left in the `temp_dir` and next to
+    /// `bin_install_path` by updates that crashed or were killed, returning how many
+    /// were removed. Updating also does this. Waits for an update in progress to finish.
+    ///
+    /// * Errors:
+    ///     * Locked - Another update of the executable is in progress
+    ///     * Io - removing directories
+    pub fn cleanup_temp_dirs(&self) -> Result<usize> {
+        let _lock = self.lock()?;
+        self.remove_stale_tmp_dirs()
+    }
+
+    /// Remove leftover temporary working directories, the update lock must be held
+    fn remove_stale_tmp_dirs(&self) -> Result<usize> {
+        let prefix = format!("{}.", self.tmp_dir_prefix());
+        let mut dirs = vec![self.install_dir()?];
+        dirs.extend(self.temp_dir.as_deref());
+        let mut removed = 0;
+        for dir in dirs {
+            let entries = match fs::read_dir(dir) {
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => continue,
+                entries => entries?,
+            };
+            for entry in entries {
+                let entry = entry?;
+                let stale = entry.file_name().to_string_lossy().starts_with(&prefix)
+                    && entry.file_type()?.is_dir();
+                if stale {
+                    fs::remove_dir_all(entry.path())?;
+                    removed += 1;
+                }
+            }
+        }
+        Ok(removed)
+    }
 
-        download.download_to(&mut tmp_archive)?;
+    /// Extract the executable from `archive` into `tmp_dir` and replace `bin_install_path`
+    fn install(&self, archive: &Path, tmp_dir: &Path) -> Result<()> {
+        self.check_deadline()?;
+        self.report(Phase::Extracting);
+        self.print_flush(&self.messages.extracting)?;
+        let new_exe = extractor::extract_file(
+            &self.extractors,
+            archive,
+            tmp_dir,
+            &self.bin_path_in_archive,
+        )?;
+        self.println(&self.messages.done);
+        // extracted beforehand, so a missing companion file doesn't leave a half update
+        let companion_files = self.extract_companion_files(archive, tmp_dir)?;
+        self.replace_exe(&new_exe, tmp_dir)?;
+        self.install_companion_files(&companion_files)
+    }
 
-        self.print_flush("Extracting archive... ")?;
-        Extract::from_source(&tmp_archive_path)
-            .extract_file(&tmp_dir.path(), &self.bin_path_in_archive)?;
-        let new_exe = tmp_dir.path().join(&self.bin_path_in_archive);
-        self.println("Done");
+    /// Path of the executable extracted into `tmp_dir` from an archive of kind `archive`
+    fn extracted_exe_path(&self, archive: ArchiveKind, tmp_dir: &Path) -> Result<PathBuf> {
+        Ok(match archive {
+            // single-file sources are extracted under just the file name
+            ArchiveKind::Plain(_) => {
+                tmp_dir.join(self.bin_path_in_archive.file_name().ok_or_else(|| {
+                    Error::Config("`bin_path_in_archive` has no file-name".into())
+                })?)
+            }
+            _ => tmp_dir.join(&self.bin_path_in_archive),
+        })
+    }
 
-        self.print_flush("Replacing binary file... ")?;
-        let tmp_file = tmp_dir.path().join(&format!("__{}_backup", self.bin_name));
-        Move::from_source(&new_exe)
+    /// Replace `bin_install_path` with the extracted executable `new_exe`
+    fn replace_exe(&self, new_exe: &Path, tmp_dir: &Path) -> Result<()> {
+        self.check_deadline()?;
+        self.report(Phase::Installing);
+        // renaming into place requires `new_exe` to be on the install dir's filesystem
+        let staging = match self.temp_dir {
+            Some(_) => Some(self.tmp_dir_in(self.install_dir()?)?),
+            None => None,
+        };
+        let new_exe = match staging {
+            Some(ref staging) => {
+                let staged = staging.path().join(&self.bin_name);
+                fs::copy(new_exe, &staged)?;
+                staged
+            }
+            None => new_exe.to_path_buf(),
+        };
+        let new_exe = new_exe.as_path();
+        set_executable(new_exe)?;
+        let installed = self.bin_install_path.exists();
+        if installed {
+            self.print_flush(&self.messages.replacing)?;
+        } else {
+            self.print_flush(&self.messages.installing)?;
+        }
+        let context_preserved = if installed {
+            attributes::preserve(&self.bin_install_path, new_exe)?
+        } else {
+            true
+        };
+        let tmp_file = self.replaced_exe_path(tmp_dir)?;
+        let stopped = match self.service {
+            Some(ref service) if installed && service.stops_for_replace() => service.stop()?,
+            _ => false,
+        };
+        let replaced = Move::from_source(new_exe)
+            .replace_using_temp(&tmp_file)
+            .to_dest(&self.bin_install_path);
+        let started = if stopped {
+            // the previous executable is still in place if replacing it failed
+            self.start_service(replaced.is_ok().then_some(tmp_file.as_path()))
+        } else {
+            Ok(())
+        };
+        replaced.and(started)?;
+        if !context_preserved {
+            attributes::restore_context(&self.bin_install_path);
+        }
+        if self.backups > 0 && tmp_file.exists() {
+            // the `.old` file stays in place, store a copy of it
+            let replaced = if self.keep_old_exe {
+                let copy = tmp_dir.join(format!("__{}_backup", self.bin_name));
+                fs::copy(&tmp_file, &copy)?;
+                copy
+            } else {
+                tmp_file.clone()
+            };
+            backup::store(
+                &replaced,
+                &self.backup_dir()?,
+                &self.bin_name,
+                &self.current_version,
+                self.backups,
+            )?;
+        }
+        if tmp_file.exists() && !self.keep_old_exe {
+            self.remove_replaced_exe(&tmp_file);
+        }
+        self.println(&self.messages.done);
+
+        let install_dir = self.bin_install_path.parent();
+        let path_var = env::var_os("PATH").unwrap_or_default();
+        if let Some(dir) = install_dir.filter(|dir| !installed && !is_in_path(dir, &path_var)) {
+            self.println(&fill(
+                &self.messages.not_in_path,
+                &[("dir", &format!("{:?}", dir)), ("bin_name", &self.bin_name)],
+            ));
+        }
+        Ok(())
+    }
+
+    /// Start the `service` stopped to replace its executable. If it fails to start,
+    /// the `replaced` executable is put back and started instead.
+    fn start_service(&self, replaced: Option<&Path>) -> Result<()> {
+        let service = self.service.as_ref().expect("stopped service");
+        self.print_flush(&fill(
+            &self.messages.restarting_service,
+            &[("service", service)],
+        ))?;
+        let (e, replaced) = match (service.start(), replaced) {
+            (Err(e), Some(replaced)) => (e, replaced),
+            (result, _) => return result,
+        };
+        Move::from_source(replaced).to_dest(&self.bin_install_path)?;
+        service.start()?;
+        bail!(
+            Error::Update,
+            "{} failed to start with the new executable, restored the previous one: {}",
+            service,
+            e
+        )
+    }
+
+    /// Fail early if the install directory isn't writable, or restart elevated if enabled
+    fn check_install_dir(&self) -> Result<()> {
+        let dir = match self.bin_install_path.parent() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        };
+        match check_writable(dir) {
+            Err(Error::PermissionDenied { .. })
+                if self.elevate && env::var_os(ELEVATED_ENV_VAR).is_none() =>
+            {
+                self.println(&fill(
+                    &self.messages.restarting_elevated,
+                    &[("dir", &format!("{:?}", dir))],
+                ));
+                Err(restart_elevated(env::current_exe()?))
+            }
+            Err(e @ Error::PermissionDenied { .. }) => {
+                self.println(&fill(
+                    &self.messages.not_writable,
+                    &[("dir", &format!("{:?}", dir))],
+                ));
+                Err(e)
+            }
+            r => r,
+        }
+    }
+
+    /// Fail early if `tmp_dir` lacks the space to download and extract `asset`, or the
+    /// install directory lacks the space for the extracted executable
+    fn check_space(&self, asset: &ReleaseAsset, tmp_dir: &Path) -> Result<()> {
+        let size = match asset.size {
+            Some(size) => size,
+            None => return Ok(()),
+        };
+        let extracted = estimated_extracted_size(detect_archive(Path::new(&asset.name)), size);
+        check_space(tmp_dir, size.saturating_add(extracted))?;
+        match self.bin_install_path.parent() {
+            Some(dir) => check_space(dir, extracted),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail if `bin_install_path` is another executable than this one and is running,
+    /// unless it's run by the `service` restarted after the update
+    fn check_not_running(&self) -> Result<()> {
+        if !self.updates_self && self.service.is_none() && is_executing(&self.bin_install_path) {
+            return Err(Error::InUse(self.bin_install_path.clone()));
+        }
+        Ok(())
+    }
+
+    /// Remove the replaced executable moved aside to `replaced` on Windows, best
+    /// effort. This executable can't be deleted while it's running, so it's scheduled
+    /// for deletion on reboot, or `cleanup_old_exe` removes it on the next run.
+    fn remove_replaced_exe(&self, replaced: &Path) {
+        if !cfg!(windows) {
+            return;
+        }
+        if self.updates_self {
+            let _ = delete_on_reboot(replaced);
+        } else {
+            let _ = fs::remove_file(replaced);
+        }
+    }
+
+    /// Where the current `bin_install_path` is moved before being replaced.
+    ///
+    /// Windows doesn't allow replacing or deleting a running executable, but it can be
+    /// renamed: it's moved aside to `<exe>.old`, removed by `cleanup_old_exe` on the next run.
+    /// It's also kept there with `keep_old_exe`.
+    fn replaced_exe_path(&self, tmp_dir: &Path) -> Result<PathBuf> {
+        if cfg!(windows) || self.keep_old_exe {
+            cleanup_old_exe(&self.bin_install_path)?;
+            Ok(old_exe_path(&self.bin_install_path))
+        } else {
+            Ok(tmp_dir.join(format!("__{}_backup", self.bin_name)))
+        }
+    }
+
+    /// Extract the `companion_files` from `archive` into `tmp_dir`, returning the
+    /// extracted files and their install paths
+    fn extract_companion_files(
+        &self,
+        archive: &Path,
+        tmp_dir: &Path,
+    ) -> Result<Vec<(PathBuf, PathBuf)>> {
+        let extract_dir = tmp_dir.join("__companion_files");
+        let mut extracted = vec![];
+        for (path_in_archive, install_dir) in &self.companion_files {
+            let file_name = path_in_archive.file_name().ok_or_else(|| {
+                format_err!(
+                    Error::Config,
+                    "companion file {:?} has no file-name",
+                    path_in_archive
+                )
+            })?;
+            extracted.push((
+                extractor::extract_file(&self.extractors, archive, &extract_dir, path_in_archive)?,
+                install_dir.join(file_name),
+            ));
+        }
+        Ok(extracted)
+    }
+
+    /// Install the `extracted` companion files at their paths, each staged next to
+    /// its destination and renamed into place so it's never left partially written
+    fn install_companion_files(&self, extracted: &[(PathBuf, PathBuf)]) -> Result<()> {
+        if extracted.is_empty() {
+            return Ok(());
+        }
+        self.print_flush(&self.messages.installing_companion_files)?;
+        for (file, dest) in extracted {
+            let install_dir = dest
+                .parent()
+                .ok_or_else(|| format_err!(Error::Config, "{:?} has no parent dir", dest))?;
+            let staging = self.tmp_dir_in(install_dir)?;
+            let staged = staging.path().join("companion");
+            fs::copy(file, &staged).map_err(|e| match e.kind() {
+                io::ErrorKind::PermissionDenied => Error::PermissionDenied {
+                    path: dest.clone(),
+                    source: e,
+                },
+                _ => Error::Io(e),
+            })?;
+            Move::from_source(&staged).to_dest(dest)?;
+        }
+        self.println(&self.messages.done);
+        Ok(())
+    }
+
+    fn backup_dir(&self) -> Result<PathBuf> {
+        if let Some(ref dir) = self.backup_dir {
+            return Ok(dir.clone());
+        }
+        let parent = self
+            .bin_install_path
+            .parent()
+            .ok_or_else(|| Error::Update("Failed to determine parent dir".into()))?;
+        Ok(parent.join(format!(".{}-backups", self.bin_name)))
+    }
+
+    /// Remove the installed executable along with its backups (see `UpdateBuilder::backups`)
+    /// and any state stored by this crate, such as `check::CheckCache::new(bin_name)`
+    /// and cached api responses.
+    /// Asks for confirmation unless `no_confirm` is set.
+    ///
+    /// * Errors:
+    ///     * Aborted - The user declined
+    ///     * NonInteractive - Confirmation is required without a terminal to ask on
+    ///     * PermissionDenied - `bin_install_path` can't be removed
+    ///     * Io - removing files
+    pub fn uninstall(&self) -> Result<()> {
+        if self.show_output || !self.no_confirm {
+            println!(
+                "{}",
+                fill(
+                    &self.messages.uninstall_status,
+                    &[
+                        ("bin_name", &self.bin_name),
+                        ("path", &format!("{:?}", self.bin_install_path)),
+                        ("backup_dir", &format!("{:?}", self.backup_dir()?)),
+                    ],
+                )
+            );
+        }
+        if !self.no_confirm {
+            confirm(
+                &self.messages.confirm,
+                &self.messages.confirm_answers,
+                self.non_interactive,
+                self.prompt_timeout,
+            )?;
+        }
+
+        let lock = self.lock()?;
+        self.print_flush(&self.messages.removing)?;
+        backup::remove_all(&self.backup_dir()?, &self.bin_name)?;
+        if let Some(ref cache) = self.api_cache {
+            cache.clear()?;
+        }
+        if let Ok(cache) = CheckCache::new(&self.bin_name) {
+            cache.clear()?;
+            if let Some(dir) = cache.path().parent() {
+                // only succeeds if the app didn't store anything else there
+                let _ = fs::remove_dir(dir);
+            }
+        }
+        match fs::remove_file(&self.bin_install_path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(Error::PermissionDenied {
+                    path: self.bin_install_path.clone(),
+                    source: e,
+                })
+            }
+            r => r?,
+        }
+        drop(lock);
+        let _ = fs::remove_file(lock::path(&self.bin_install_path));
+        self.println(&self.messages.done);
+        Ok(())
+    }
+
+    /// Remove the `<exe>.old` executable left beside `bin_install_path` by a previous
+    /// update, see `UpdateBuilder::keep_old_exe`. A missing `.old` file is not an error.
+    ///
+    /// * Errors:
+    ///     * Io - removing the file
+    pub fn remove_old_exe(&self) -> Result<()> {
+        cleanup_old_exe(&self.bin_install_path)
+    }
+
+    /// Replace `bin_install_path` with the newest backup kept by a previous update
+    /// (see `UpdateBuilder::backups`), returning the restored version. The restored
+    /// backup is removed from the backup directory.
+    ///
+    /// * Errors:
+    ///     * Update - No backup is available
+    ///     * PermissionDenied - `bin_install_path` isn't writable
+    ///     * Locked - Another update of the executable is in progress
+    ///     * InUse - `bin_install_path` isn't this executable and is being run
+    ///     * Io - copying / replacing the binary
+    pub fn rollback(&self) -> Result<String> {
+        let dir = self.backup_dir()?;
+        let (version, backup) = backup::list(&dir, &self.bin_name)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| {
+                format_err!(
+                    Error::Update,
+                    "No backup of `{}` found in {:?}",
+                    self.bin_name,
+                    dir
+                )
+            })?;
+
+        let _lock = self.lock()?;
+        self.check_not_running()?;
+        self.print_flush(&fill(&self.messages.restoring, &[("version", &version)]))?;
+        let tmp_dir = self.tmp_dir()?;
+        let restored = tmp_dir.path().join(&self.bin_name);
+        fs::copy(&backup, &restored)?;
+        let tmp_file = self.replaced_exe_path(tmp_dir.path())?;
+        Move::from_source(&restored)
             .replace_using_temp(&tmp_file)
             .to_dest(&self.bin_install_path)?;
-        self.println("Done");
-        Ok(GitHubUpdateStatus::Updated(release))
+        self.remove_replaced_exe(&tmp_file);
+        fs::remove_file(&backup)?;
+        self.println(&self.messages.done);
+        Ok(version.to_string())
     }
 }
 
@@ -631,9 +3432,74 @@ impl Default for UpdateBuilder {
             show_download_progress: false,
             show_output: true,
             no_confirm: false,
+            non_interactive: NonInteractive::default(),
+            prompt_timeout: None,
+            messages: Messages::default(),
             current_version: None,
             target_version: None,
+            select_release: None,
             progress_style: None,
+            max_download_speed: None,
+            download_connections: 1,
+            backups: 0,
+            keep_old_exe: false,
+            local_changes: LocalChanges::default(),
+            backup_dir: None,
+            temp_dir: None,
+            download_cache: None,
+            resume_downloads: false,
+            companion_files: vec![],
+            on_update: None,
+            on_attempt: None,
+            on_progress: None,
+            service: None,
+            progress_interval: DEFAULT_REPORT_INTERVAL,
+            total_timeout: None,
+            api_url: None,
+            load_policy: false,
+            policy_file: None,
+            elevate: false,
+            defer_to_package_manager: false,
+            homebrew: Homebrew::default(),
+            route_through_package_manager: false,
+            follow_symlinks: true,
+            musl_fallback: true,
+            target_aliases: vec![],
+            asset_pattern: None,
+            asset_matcher: None,
+            asset_ranker: None,
+            asset_name_template: None,
+            target_asset_names: vec![],
+            archive_format: None,
+            latest_by_semver: false,
+            release_filter: None,
+            extractors: vec![],
+            downloader: None,
+            delta_updates: false,
+            verify_checksum: false,
+            checksum_algorithm: None,
+            differential_download: false,
+            cache_api_responses: false,
+            api_cache_file: None,
+            auth_token_from_env: true,
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+impl super::Updater for Update {
+    fn current_version(&self) -> String {
+        self.current_version.clone()
+    }
+
+    fn latest_version(&self) -> Result<String> {
+        if let Some(formula) = self.homebrew_formula() {
+            return package_manager::homebrew_formula_version(&formula);
         }
+        Ok(self.get_latest_release()?.version().to_owned())
+    }
+
+    fn update(&self) -> Result<Status> {
+        self.clone().update()
     }
 }
@@ -0,0 +1,361 @@
+/*!
+In-memory release backend for testing update flows
+
+Serves canned releases and assets without any network access, installing into an
+explicitly configured `bin_install_path` so tests never touch the running executable.
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::backends::mock;
+
+let dir = tempdir::TempDir::new("app")?;
+let status = mock::Update::configure()
+    .release(mock::Release::new("1.1.0").asset("app-x86_64-unknown-linux-gnu", b"new exe"))
+    .target("x86_64-unknown-linux-gnu")
+    .bin_name("app")
+    .bin_install_path(dir.path().join("app"))
+    .current_version("1.0.0")
+    .build()?
+    .update()?;
+assert!(status.updated());
+# Ok(())
+# }
+# run().unwrap();
+```
+*/
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tempdir::TempDir;
+
+use crate::{
+    detect_archive, errors::*, get_target, set_executable, version, Extract, Move, Status,
+};
+
+/// A canned release and its assets
+#[derive(Clone, Debug, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// A canned release asset with its full content
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+impl Release {
+    /// Create a release without assets, a leading `v` is ignored
+    pub fn new(version: &str) -> Self {
+        Self {
+            version: version.trim_start_matches('v').to_owned(),
+            assets: vec![],
+        }
+    }
+
+    /// Add an asset named `name`, e.g. a plain executable or an archive
+    /// (`.tar.gz`, `.zip`, ...) containing one
+    pub fn asset<C: AsRef<[u8]>>(mut self, name: &str, content: C) -> Self {
+        self.assets.push(ReleaseAsset {
+            name: name.to_owned(),
+            content: content.as_ref().to_vec(),
+        });
+        self
+    }
+
+    /// Return the first asset whose name contains the specified `target`
+    pub fn asset_for(&self, target: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name.contains(target))
+    }
+}
+
+/// `mock::Update` builder
+#[derive(Clone, Debug, Default)]
+pub struct UpdateBuilder {
+    releases: Vec<Release>,
+    target: Option<String>,
+    bin_install_path: Option<PathBuf>,
+    bin_path_in_archive: Option<PathBuf>,
+    current_version: Option<String>,
+    target_version: Option<String>,
+}
+
+impl UpdateBuilder {
+    /// Initialize a new builder
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Add a release to serve
+    pub fn release(&mut self, release: Release) -> &mut Self {
+        self.releases.push(release);
+        self
+    }
+
+    /// Set the target to pick release assets for, defaults to the current target
+    pub fn target(&mut self, target: &str) -> &mut Self {
+        self.target = Some(target.to_owned());
+        self
+    }
+
+    /// Set the exe's name, also used as `bin_path_in_archive` unless that's set
+    pub fn bin_name(&mut self, name: &str) -> &mut Self {
+        if self.bin_path_in_archive.is_none() {
+            self.bin_path_in_archive = Some(PathBuf::from(name));
+        }
+        self
+    }
+
+    /// Set the path to install the new executable to. Required, unlike other
+    /// backends this doesn't default to the current executable.
+    pub fn bin_install_path<A: AsRef<Path>>(&mut self, path: A) -> &mut Self {
+        self.bin_install_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the path of the exe within archive assets, defaults to `bin_name`
+    pub fn bin_path_in_archive(&mut self, bin_path: &str) -> &mut Self {
+        self.bin_path_in_archive = Some(PathBuf::from(bin_path));
+        self
+    }
+
+    /// Set the version of the "installed" executable
+    pub fn current_version(&mut self, ver: &str) -> &mut Self {
+        self.current_version = Some(ver.to_owned());
+        self
+    }
+
+    /// Set the version to update to, instead of the latest release
+    pub fn target_version(&mut self, ver: &str) -> &mut Self {
+        self.target_version = Some(ver.trim_start_matches('v').to_owned());
+        self
+    }
+
+    /// Confirm config and create a ready-to-use `Update`
+    ///
+    /// * Errors:
+    ///     * Config - Invalid `Update` configuration
+    pub fn build(&self) -> Result<Update> {
+        Ok(Update {
+            releases: self.releases.clone(),
+            target: self
+                .target
+                .clone()
+                .unwrap_or_else(|| get_target().to_owned()),
+            bin_install_path: if let Some(ref path) = self.bin_install_path {
+                path.to_owned()
+            } else {
+                bail!(Error::Config, "`bin_install_path` required")
+            },
+            bin_path_in_archive: if let Some(ref path) = self.bin_path_in_archive {
+                path.to_owned()
+            } else {
+                bail!(
+                    Error::Config,
+                    "`bin_name` or `bin_path_in_archive` required"
+                )
+            },
+            current_version: if let Some(ref ver) = self.current_version {
+                ver.to_owned()
+            } else {
+                bail!(Error::Config, "`current_version` required")
+            },
+            target_version: self.target_version.clone(),
+        })
+    }
+}
+
+/// Updates to a version served from memory
+#[derive(Clone, Debug)]
+pub struct Update {
+    releases: Vec<Release>,
+    target: String,
+    bin_install_path: PathBuf,
+    bin_path_in_archive: PathBuf,
+    current_version: String,
+    target_version: Option<String>,
+}
+
+impl Update {
+    /// Initialize a new `Update` builder
+    pub fn configure() -> UpdateBuilder {
+        UpdateBuilder::new()
+    }
+
+    /// The served release with the greatest version
+    ///
+    /// * Errors:
+    ///     * Release - No releases are served
+    ///     * SemVer - A release has an invalid version
+    pub fn get_latest_release(&self) -> Result<Release> {
+        let mut latest: Option<&Release> = None;
+        for release in &self.releases {
+            match latest {
+                Some(l) if !version::bump_is_greater(&l.version, &release.version)? => (),
+                _ => latest = Some(release),
+            }
+        }
+        latest
+            .cloned()
+            .ok_or_else(|| format_err!(Error::Release, "No releases found"))
+    }
+
+    /// The served release with version `ver`
+    ///
+    /// * Errors:
+    ///     * Release - No such release is served
+    pub fn get_release_version(&self, ver: &str) -> Result<Release> {
+        let ver = ver.trim_start_matches('v');
+        self.releases
+            .iter()
+            .find(|r| r.version == ver)
+            .cloned()
+            .ok_or_else(|| format_err!(Error::Release, "No release found for version: {}", ver))
+    }
+
+    /// Install the `target_version` release, or the latest release if it's newer
+    /// than the `current_version`
+    ///
+    /// * Errors:
+    ///     * Release / SemVer - See `get_latest_release`, `get_release_version`
+    ///     * NoAssetForTarget - The release has no asset for the `target`
+    ///     * ArchiveEntryNotFound - The asset doesn't contain `bin_path_in_archive`
+    ///     * Io - Writing the new executable
+    pub fn update(&self) -> Result<Status> {
+        let release = match self.target_version {
+            Some(ref ver) => self.get_release_version(ver)?,
+            None => {
+                let latest = self.get_latest_release()?;
+                if !version::bump_is_greater(&self.current_version, &latest.version)? {
+                    return Ok(Status::UpToDate(self.current_version.clone()));
+                }
+                latest
+            }
+        };
+        let asset = release
+            .asset_for(&self.target)
+            .ok_or_else(|| Error::NoAssetForTarget(self.target.clone()))?;
+        self.install(asset)?;
+        Ok(Status::Updated(release.version))
+    }
+
+    fn install(&self, asset: &ReleaseAsset) -> Result<()> {
+        let install_dir = self
+            .bin_install_path
+            .parent()
+            .ok_or_else(|| Error::Config("`bin_install_path` has no parent".into()))?;
+        fs::create_dir_all(install_dir)?;
+        let tmp_dir = TempDir::new_in(install_dir, "self_update_mock")?;
+        let asset_path = tmp_dir.path().join(&asset.name);
+        fs::write(&asset_path, &asset.content)?;
+
+        let extract_dir = tmp_dir.path().join("extracted");
+        let archive = detect_archive(&asset_path);
+        Extract::from_source(&asset_path)
+            .archive(archive)
+            .extract_file(&extract_dir, &self.bin_path_in_archive)?;
+        let new_exe = match archive {
+            crate::ArchiveKind::Plain(_) => {
+                extract_dir.join(self.bin_path_in_archive.file_name().ok_or_else(|| {
+                    Error::Config("`bin_path_in_archive` has no file-name".into())
+                })?)
+            }
+            _ => extract_dir.join(&self.bin_path_in_archive),
+        };
+        set_executable(&new_exe)?;
+        Move::from_source(&new_exe)
+            .replace_using_temp(&tmp_dir.path().join("replaced"))
+            .to_dest(&self.bin_install_path)
+    }
+}
+
+impl super::Updater for Update {
+    fn current_version(&self) -> String {
+        self.current_version.clone()
+    }
+
+    fn latest_version(&self) -> Result<String> {
+        Ok(self.get_latest_release()?.version)
+    }
+
+    fn update(&self) -> Result<Status> {
+        Update::update(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn updater(dir: &Path) -> UpdateBuilder {
+        let mut builder = Update::configure();
+        builder
+            .release(Release::new("v1.0.0").asset("app-t", "1.0.0"))
+            .release(Release::new("v1.2.0").asset("app-t", "1.2.0"))
+            .release(Release::new("v1.1.0").asset("app-t", "1.1.0"))
+            .target("t")
+            .bin_name("app")
+            .bin_install_path(dir.join("bin").join("app"))
+            .current_version("1.0.0");
+        builder
+    }
+
+    #[test]
+    fn update_to_latest() {
+        let tmp = TempDir::new("self_update_mock").expect("tempdir fail");
+        let installed = tmp.path().join("bin").join("app");
+        let status = updater(tmp.path()).build().unwrap().update().unwrap();
+        assert_eq!("1.2.0", status.version());
+        assert!(status.updated());
+        assert_eq!("1.2.0", fs::read_to_string(&installed).unwrap());
+
+        let status = updater(tmp.path())
+            .current_version("1.2.0")
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert!(status.uptodate());
+
+        let status = updater(tmp.path())
+            .target_version("v1.1.0")
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!("1.1.0", status.version());
+        assert_eq!("1.1.0", fs::read_to_string(&installed).unwrap());
+    }
+
+    #[test]
+    fn as_updater() {
+        use crate::backends::Updater;
+        let tmp = TempDir::new("self_update_mock").expect("tempdir fail");
+        let updater: Box<dyn Updater> = Box::new(updater(tmp.path()).build().unwrap());
+        assert_eq!("1.0.0", updater.current_version());
+        assert_eq!("1.2.0", updater.latest_version().unwrap());
+        assert_eq!(Some("1.2.0".to_owned()), updater.check().unwrap());
+        assert!(updater.update().unwrap().updated());
+    }
+
+    #[test]
+    fn missing_asset() {
+        let tmp = TempDir::new("self_update_mock").expect("tempdir fail");
+        let update = updater(tmp.path()).target("other").build().unwrap();
+        match update.update() {
+            Err(Error::NoAssetForTarget(ref target)) if target == "other" => (),
+            r => panic!(
+                "expected NoAssetForTarget, got {:?}",
+                r.map(|s| s.to_string())
+            ),
+        }
+        assert!(updater(tmp.path())
+            .target_version("3.0.0")
+            .build()
+            .unwrap()
+            .update()
+            .is_err());
+    }
+}
@@ -1,5 +1,53 @@
 /*!
 Collection of modules supporting various release distribution backends
+
+Each backend's `Update` implements `Updater`, so applications can pick a backend at
+runtime or substitute a fake in tests:
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::backends::{github, Updater};
+
+let updater: Box<dyn Updater> = Box::new(
+    github::Update::configure()
+        .repo_owner("jaemk")
+        .repo_name("self_update")
+        .bin_name("self_update_example")
+        .current_version(self_update::cargo_crate_version!())
+        .build()?,
+);
+# Ok(())
+# }
+```
 */
+use crate::errors::*;
+use crate::{version, Status};
 
 pub mod github;
+#[cfg(feature = "mock")]
+pub mod mock;
+
+/// Common interface of the backends' `Update`s, object safe so updaters can be
+/// stored as `Box<dyn Updater>`
+pub trait Updater {
+    /// The version of the installed executable
+    fn current_version(&self) -> String;
+
+    /// The version of the latest release, whether or not it's newer
+    fn latest_version(&self) -> Result<String>;
+
+    /// The version of the latest release if it's newer than the current version
+    fn check(&self) -> Result<Option<String>> {
+        let latest = self.latest_version()?;
+        Ok(
+            if version::bump_is_greater(&self.current_version(), &latest)? {
+                Some(latest)
+            } else {
+                None
+            },
+        )
+    }
+
+    /// Update the executable as configured, see the backend's `Update::update`
+    fn update(&self) -> Result<Status>;
+}
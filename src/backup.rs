@@ -0,0 +1,122 @@
+/*!
+Versioned backups of replaced executables
+
+Backups of `bin_name` are stored as `<dir>/<bin_name>-<version>[.exe]`.
+*/
+use std::env::consts::EXE_SUFFIX;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+
+use crate::errors::*;
+
+/// Path of the backup of `bin_name` at `version` inside `dir`
+pub(crate) fn path(dir: &Path, bin_name: &str, version: &str) -> PathBuf {
+    dir.join(format!("{}-{}{}", bin_name, version, EXE_SUFFIX))
+}
+
+/// Backups of `bin_name` found in `dir`, newest version first
+pub(crate) fn list(dir: &Path, bin_name: &str) -> Result<Vec<(Version, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(Error::Io(e)),
+    };
+    let prefix = format!("{}-", bin_name);
+    let mut backups = vec![];
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let version = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|name| name.strip_suffix(EXE_SUFFIX))
+            .and_then(|version| Version::parse(version).ok());
+        if let Some(version) = version {
+            backups.push((version, entry.path()));
+        }
+    }
+    backups.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(backups)
+}
+
+/// Move `exe` into `dir` as the backup of `bin_name` at `version`, copying instead
+/// when `dir` is on another filesystem. Afterwards only the `keep` newest backups
+/// are retained.
+pub(crate) fn store(
+    exe: &Path,
+    dir: &Path,
+    bin_name: &str,
+    version: &str,
+    keep: usize,
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let backup = path(dir, bin_name, version);
+    if fs::rename(exe, &backup).is_err() {
+        fs::copy(exe, &backup)?;
+    }
+    for (_, old) in list(dir, bin_name)?.into_iter().skip(keep) {
+        fs::remove_file(old)?;
+    }
+    Ok(backup)
+}
+
+/// Delete all backups of `bin_name` in `dir`, and `dir` itself if nothing else is left in it
+pub(crate) fn remove_all(dir: &Path, bin_name: &str) -> Result<()> {
+    for (_, backup) in list(dir, bin_name)? {
+        fs::remove_file(backup)?;
+    }
+    // fails if missing, or still holding other files
+    let _ = fs::remove_dir(dir);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn store_keeps_newest_backups() {
+        let tmp = TempDir::new("self_update_backup_store").expect("tempdir fail");
+        let backups = tmp.path().join("backups");
+        for version in &["0.9.0", "1.0.0", "0.10.1"] {
+            let exe = tmp.path().join("exe");
+            fs::write(&exe, version).unwrap();
+            store(&exe, &backups, "app", version, 2).expect("store fail");
+            assert!(!exe.exists());
+        }
+        fs::write(backups.join("app-cli-2.0.0"), "other app").unwrap();
+
+        let found = list(&backups, "app").expect("list fail");
+        assert_eq!(2, found.len());
+        assert_eq!(Version::parse("1.0.0").unwrap(), found[0].0);
+        assert_eq!(Version::parse("0.10.1").unwrap(), found[1].0);
+        assert_eq!("1.0.0", fs::read_to_string(&found[0].1).unwrap());
+    }
+
+    #[test]
+    fn remove_all_backups() {
+        let tmp = TempDir::new("self_update_backup_remove").expect("tempdir fail");
+        let backups = tmp.path().join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        fs::write(path(&backups, "app", "1.0.0"), "app").unwrap();
+        remove_all(&backups, "app").expect("remove fail");
+        assert!(!backups.exists());
+
+        fs::create_dir_all(&backups).unwrap();
+        fs::write(path(&backups, "app", "1.0.0"), "app").unwrap();
+        fs::write(backups.join("notes.txt"), "keep me").unwrap();
+        remove_all(&backups, "app").expect("remove fail");
+        assert!(backups.join("notes.txt").exists());
+        assert!(list(&backups, "app").unwrap().is_empty());
+    }
+
+    #[test]
+    fn list_missing_dir() {
+        let tmp = TempDir::new("self_update_backup_list").expect("tempdir fail");
+        assert!(list(&tmp.path().join("nope"), "app").unwrap().is_empty());
+    }
+}
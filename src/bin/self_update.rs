@@ -0,0 +1,279 @@
+/*!
+Install and update executables released on GitHub
+
+Requires the `clap` feature: `cargo install self_update --features clap`
+
+```shell
+self_update install BurntSushi/ripgrep --bin rg
+self_update update BurntSushi/ripgrep --bin rg
+self_update list BurntSushi/ripgrep
+```
+*/
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use self_update::backends::github::{ReleaseList, Update, UpdateBuilder};
+use self_update::errors::{Error, Result};
+
+/// Number of recent releases to choose from with `--select`
+const SELECT_RELEASES: usize = 10;
+
+fn repo_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("repo")
+        .value_name("owner/repo")
+        .required(true)
+        .help("GitHub repository releasing the executable")
+}
+
+/// Arguments shared by `install` and `update`
+fn update_args<'a, 'b>(cmd: App<'a, 'b>) -> App<'a, 'b> {
+    cmd.arg(repo_arg())
+        .arg(
+            Arg::with_name("bin")
+                .long("bin")
+                .takes_value(true)
+                .required(true)
+                .help("Name of the executable, in the release archive and once installed"),
+        )
+        .arg(
+            Arg::with_name("dir")
+                .long("dir")
+                .takes_value(true)
+                .help("Directory the executable is installed in [default: ~/.local/bin]"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .help("Target triple to pick release assets for [default: this system's]"),
+        )
+        .arg(
+            Arg::with_name("version")
+                .long("version")
+                .value_name("tag")
+                .takes_value(true)
+                .help("Install the release with this tag instead of the latest"),
+        )
+        .arg(
+            Arg::with_name("select")
+                .long("select")
+                .conflicts_with("version")
+                .help("Choose the release to install among the most recent ones"),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .long("yes")
+                .short("y")
+                .help("Don't ask for confirmation"),
+        )
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("self_update")
+        .version(self_update::cargo_crate_version!())
+        .about("Install and update executables released on GitHub")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("token")
+                .long("token")
+                .takes_value(true)
+                .global(true)
+                .help("GitHub token to authenticate with [default: $GH_TOKEN or $GITHUB_TOKEN]"),
+        )
+        .subcommand(update_args(
+            SubCommand::with_name("install").about("Install the latest or a given release"),
+        ))
+        .subcommand(
+            update_args(SubCommand::with_name("update").about("Update an installed executable"))
+                .arg(
+                    Arg::with_name("current")
+                        .long("current")
+                        .takes_value(true)
+                        .help("Installed version [default: from `<bin> --version`]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List the releases of a repository")
+                .arg(repo_arg()),
+        )
+}
+
+/// Split `owner/repo`
+fn parse_repo(repo: &str) -> Result<(&str, &str)> {
+    match repo.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+            Ok((owner, name))
+        }
+        _ => Err(Error::Config(format!(
+            "expected a repository as `owner/repo`, found {:?}",
+            repo
+        ))),
+    }
+}
+
+/// The version in `--version` output like `rg 13.0.0` or `app v1.2.3 (abc123)`
+fn parse_version_output(output: &str) -> Option<String> {
+    output
+        .lines()
+        .next()?
+        .split_whitespace()
+        .map(|word| word.trim_start_matches('v'))
+        .find(|word| semver::Version::parse(word).is_ok())
+        .map(str::to_owned)
+}
+
+/// Version of the executable at `path`, from its `--version` output
+fn installed_version(path: &Path) -> Result<String> {
+    let output = Command::new(path).arg("--version").output()?;
+    parse_version_output(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+        Error::Config(format!(
+            "couldn't read the version of {:?}, pass it with `--current`",
+            path
+        ))
+    })
+}
+
+fn install_dir(matches: &ArgMatches) -> Result<PathBuf> {
+    match matches.value_of("dir") {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => dirs::home_dir()
+            .map(|home| home.join(".local").join("bin"))
+            .ok_or_else(|| Error::Config("no home directory, pass `--dir`".into())),
+    }
+}
+
+/// An updater configured from the `install` / `update` arguments
+fn updater(matches: &ArgMatches, token: Option<&str>) -> Result<(UpdateBuilder, PathBuf)> {
+    let (owner, name) = parse_repo(matches.value_of("repo").expect("required"))?;
+    let bin = matches.value_of("bin").expect("required");
+    let path = install_dir(matches)?.join(format!("{}{}", bin, env::consts::EXE_SUFFIX));
+    let mut builder = Update::configure();
+    builder
+        .repo_owner(owner)
+        .repo_name(name)
+        .bin_name(bin)
+        .bin_install_path(&path)
+        .show_download_progress(true)
+        .no_confirm(matches.is_present("yes"));
+    if let Some(target) = matches.value_of("target") {
+        builder.target(target);
+    }
+    if let Some(tag) = matches.value_of("version") {
+        builder.target_version_tag(tag);
+    }
+    if matches.is_present("select") {
+        builder.select_release(SELECT_RELEASES);
+    }
+    if let Some(token) = token {
+        builder.auth_token(token);
+    }
+    Ok((builder, path))
+}
+
+fn run(matches: &ArgMatches) -> Result<()> {
+    // without `--token`, the updaters pick up the token of the environment
+    let token = matches.value_of("token");
+    match matches.subcommand() {
+        ("install", Some(matches)) => {
+            let (mut builder, _) = updater(matches, token)?;
+            if !matches.is_present("version") && !matches.is_present("select") {
+                // pinning the latest tag installs it regardless of what's installed
+                let latest = builder
+                    .current_version("0.0.0")
+                    .build()?
+                    .get_latest_release()?;
+                builder.target_version_tag(&latest.tag);
+            }
+            let status = builder.current_version("0.0.0").build()?.update()?;
+            println!("Installed v{}", status.version());
+        }
+        ("update", Some(matches)) => {
+            let (mut builder, path) = updater(matches, token)?;
+            let current = match matches.value_of("current") {
+                Some(current) => current.trim_start_matches('v').to_owned(),
+                None => installed_version(&path)?,
+            };
+            let status = builder.current_version(&current).build()?.update()?;
+            if status.updated() {
+                println!("Updated v{} to v{}", current, status.version());
+            } else {
+                println!("v{} is the latest version", current);
+            }
+        }
+        ("list", Some(matches)) => {
+            let (owner, name) = parse_repo(matches.value_of("repo").expect("required"))?;
+            let mut builder = ReleaseList::configure();
+            builder.repo_owner(owner).repo_name(name);
+            if let Some(token) = token {
+                builder.auth_token(token);
+            }
+            for release in builder.build()?.iter() {
+                let release = release?;
+                println!(
+                    "{}\t{}\t{}",
+                    release.tag, release.date_created, release.name
+                );
+            }
+        }
+        _ => unreachable!("subcommand required"),
+    }
+    Ok(())
+}
+
+fn main() {
+    let matches = app().get_matches();
+    if let Err(e) = run(&matches) {
+        eprintln!("[ERROR] {}", e);
+        process::exit(e.exit_code());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_args() {
+        assert_eq!(("owner", "app"), parse_repo("owner/app").unwrap());
+        assert!(parse_repo("owner").is_err());
+        assert!(parse_repo("owner/app/extra").is_err());
+        assert_eq!(
+            Some("13.0.0".to_owned()),
+            parse_version_output("ripgrep 13.0.0\n-SIMD -AVX")
+        );
+        assert_eq!(
+            Some("1.2.3".to_owned()),
+            parse_version_output("app v1.2.3 (abc123)")
+        );
+        assert_eq!(None, parse_version_output("app dev"));
+
+        let matches = app().get_matches_from(vec![
+            "self_update",
+            "install",
+            "o/app",
+            "--bin",
+            "app",
+            "-y",
+        ]);
+        let (_, install) = matches.subcommand();
+        assert!(install.unwrap().is_present("yes"));
+        assert!(app()
+            .get_matches_from_safe(vec!["self_update", "install", "o/app"])
+            .is_err());
+        assert!(app()
+            .get_matches_from_safe(vec![
+                "self_update",
+                "install",
+                "o/app",
+                "--bin",
+                "app",
+                "--select",
+                "--version",
+                "v1.0.0",
+            ])
+            .is_err());
+    }
+}
@@ -0,0 +1,391 @@
+/*!
+Block indexes for differential downloads
+
+A block index lists checksums of the fixed-size blocks of a release asset. Given
+the index of a new executable, an updater finds the blocks it already has anywhere
+in the installed executable (like `rsync` / `zsync`) and only downloads the rest
+with ranged requests.
+
+Publish the index of each uncompressed executable asset as an additional asset
+named `<asset name>.blocks.json`:
+
+```no_run
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::blocks::{BlockIndex, DEFAULT_BLOCK_SIZE};
+
+let index = BlockIndex::from_file("target/release/my-app", DEFAULT_BLOCK_SIZE)?;
+std::fs::write("my-app-x86_64-unknown-linux-gnu.blocks.json", index.to_json())?;
+# Ok(())
+# }
+```
+*/
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::errors::*;
+
+/// Block size suited to executables of a few MB up to a few hundred MB
+pub const DEFAULT_BLOCK_SIZE: usize = 16 * 1024;
+
+/// Bytes of each block's sha256 kept in the index
+const STRONG_LEN: usize = 16;
+
+/// Checksums of one block
+#[derive(Clone, Debug, PartialEq)]
+struct Block {
+    /// Rolling checksum, cheap to compute at every offset of the installed executable
+    weak: u32,
+    /// Truncated sha256, confirming a weak match
+    strong: String,
+}
+
+/// Checksums of the blocks of a file, and of the whole file
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockIndex {
+    size: u64,
+    block_size: usize,
+    sha256: String,
+    blocks: Vec<Block>,
+}
+
+impl BlockIndex {
+    /// Index `data` in blocks of `block_size` bytes
+    ///
+    /// * Errors:
+    ///     * Config - `block_size` is zero
+    pub fn from_bytes(data: &[u8], block_size: usize) -> Result<BlockIndex> {
+        if block_size == 0 {
+            bail!(Error::Config, "`block_size` must be greater than zero");
+        }
+        Ok(BlockIndex {
+            size: data.len() as u64,
+            block_size,
+            sha256: hex(&Sha256::digest(data)),
+            blocks: data
+                .chunks(block_size)
+                .map(|block| Block {
+                    weak: Rolling::new(block).digest(),
+                    strong: strong_hash(block),
+                })
+                .collect(),
+        })
+    }
+
+    /// Index the file at `path` in blocks of `block_size` bytes
+    ///
+    /// * Errors:
+    ///     * Io - Reading the file
+    ///     * Config - `block_size` is zero
+    pub fn from_file<P: AsRef<Path>>(path: P, block_size: usize) -> Result<BlockIndex> {
+        BlockIndex::from_bytes(&fs::read(path)?, block_size)
+    }
+
+    /// Serialize the index as json
+    pub fn to_json(&self) -> String {
+        json!({
+            "size": self.size,
+            "block_size": self.block_size,
+            "sha256": self.sha256,
+            "blocks": self
+                .blocks
+                .iter()
+                .map(|b| json!([b.weak, b.strong]))
+                .collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    /// Parse an index serialized with `to_json`
+    ///
+    /// * Errors:
+    ///     * Json - Invalid json
+    ///     * Release - Missing or invalid fields
+    pub fn from_json(content: &str) -> Result<BlockIndex> {
+        let json = serde_json::from_str::<serde_json::Value>(content)?;
+        let invalid = |field: &str| format_err!(Error::Release, "Invalid block index `{}`", field);
+        let size = json["size"].as_u64().ok_or_else(|| invalid("size"))?;
+        let block_size = json["block_size"]
+            .as_u64()
+            .filter(|b| *b > 0)
+            .ok_or_else(|| invalid("block_size"))? as usize;
+        let sha256 = json["sha256"]
+            .as_str()
+            .ok_or_else(|| invalid("sha256"))?
+            .to_owned();
+        let blocks = json["blocks"]
+            .as_array()
+            .ok_or_else(|| invalid("blocks"))?
+            .iter()
+            .map(|b| {
+                let weak = b[0].as_u64().filter(|w| *w <= u64::from(u32::MAX));
+                let strong = b[1].as_str();
+                match (weak, strong) {
+                    (Some(weak), Some(strong)) => Ok(Block {
+                        weak: weak as u32,
+                        strong: strong.to_owned(),
+                    }),
+                    _ => Err(invalid("blocks")),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let expected_blocks = (size as usize).div_ceil(block_size);
+        if blocks.len() != expected_blocks {
+            return Err(invalid("blocks"));
+        }
+        Ok(BlockIndex {
+            size,
+            block_size,
+            sha256,
+            blocks,
+        })
+    }
+
+    /// For each block, the offset in `old` it can be copied from, if any
+    pub(crate) fn find_blocks(&self, old: &[u8]) -> Vec<Option<usize>> {
+        let mut found = vec![None; self.blocks.len()];
+        let mut by_weak = HashMap::<u32, Vec<usize>>::new();
+        for (i, block) in self.blocks.iter().enumerate() {
+            if self.block_len(i) == self.block_size {
+                by_weak.entry(block.weak).or_default().push(i);
+            }
+        }
+        let last = match self.blocks.len() {
+            0 => return found,
+            n => n - 1,
+        };
+        // the final, shorter, block is only looked for at the end of `old`
+        if self.block_len(last) < self.block_size && old.len() >= self.block_len(last) {
+            let offset = old.len() - self.block_len(last);
+            if strong_hash(&old[offset..]) == self.blocks[last].strong {
+                found[last] = Some(offset);
+            }
+        }
+        if old.len() < self.block_size || by_weak.is_empty() {
+            return found;
+        }
+
+        let mut offset = 0;
+        let mut rolling = Rolling::new(&old[..self.block_size]);
+        loop {
+            let window = &old[offset..offset + self.block_size];
+            let mut matched = false;
+            if let Some(candidates) = by_weak.get(&rolling.digest()) {
+                let strong = strong_hash(window);
+                for &i in candidates {
+                    if found[i].is_none() && self.blocks[i].strong == strong {
+                        found[i] = Some(offset);
+                        matched = true;
+                    }
+                }
+            }
+            // skip past matched blocks, their content is covered
+            let next = if matched {
+                offset + self.block_size
+            } else {
+                offset + 1
+            };
+            if next + self.block_size > old.len() {
+                break;
+            }
+            if matched {
+                rolling = Rolling::new(&old[next..next + self.block_size]);
+            } else {
+                rolling.roll(old[offset], old[offset + self.block_size]);
+            }
+            offset = next;
+        }
+        found
+    }
+
+    /// Byte ranges (`start..end`) of the blocks missing from `found`, contiguous
+    /// blocks merged into a single range
+    pub(crate) fn missing_ranges(&self, found: &[Option<usize>]) -> Vec<(u64, u64)> {
+        let mut ranges: Vec<(u64, u64)> = vec![];
+        for (i, _) in found.iter().enumerate().filter(|(_, f)| f.is_none()) {
+            let start = (i * self.block_size) as u64;
+            let end = start + self.block_len(i) as u64;
+            match ranges.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => ranges.push((start, end)),
+            }
+        }
+        ranges
+    }
+
+    /// Rebuild the indexed file from the blocks `found` in `old` and the missing
+    /// ranges returned by `fetch`, verifying every block and the whole file
+    ///
+    /// * Errors:
+    ///     * ChecksumMismatch - A fetched block or the rebuilt file is corrupt
+    ///     * Any error returned by `fetch`
+    pub(crate) fn assemble<F>(
+        &self,
+        old: &[u8],
+        found: &[Option<usize>],
+        mut fetch: F,
+    ) -> Result<Vec<u8>>
+    where
+        F: FnMut(u64, u64) -> Result<Vec<u8>>,
+    {
+        let mut new = vec![0u8; self.size as usize];
+        for (i, offset) in found.iter().enumerate() {
+            if let Some(offset) = *offset {
+                let start = i * self.block_size;
+                let len = self.block_len(i);
+                new[start..start + len].copy_from_slice(&old[offset..offset + len]);
+            }
+        }
+        for (start, end) in self.missing_ranges(found) {
+            let data = fetch(start, end)?;
+            if data.len() as u64 != end - start {
+                bail!(
+                    Error::Release,
+                    "Expected {} bytes for range {}-{}, got {}",
+                    end - start,
+                    start,
+                    end,
+                    data.len()
+                );
+            }
+            new[start as usize..end as usize].copy_from_slice(&data);
+        }
+        let actual = hex(&Sha256::digest(&new));
+        if actual != self.sha256 {
+            return Err(Error::ChecksumMismatch {
+                expected: self.sha256.clone(),
+                actual,
+            });
+        }
+        Ok(new)
+    }
+
+    /// Number of bytes of the indexed file that would be downloaded, given `found`
+    pub(crate) fn download_size(&self, found: &[Option<usize>]) -> u64 {
+        self.missing_ranges(found)
+            .iter()
+            .map(|(start, end)| end - start)
+            .sum()
+    }
+
+    fn block_len(&self, i: usize) -> usize {
+        let start = (i * self.block_size) as u64;
+        self.size.saturating_sub(start).min(self.block_size as u64) as usize
+    }
+}
+
+/// rsync's rolling checksum over a fixed-size window
+struct Rolling {
+    a: u32,
+    b: u32,
+    len: u32,
+}
+
+impl Rolling {
+    fn new(window: &[u8]) -> Rolling {
+        let len = window.len() as u32;
+        let (mut a, mut b) = (0u32, 0u32);
+        for (i, &byte) in window.iter().enumerate() {
+            a = a.wrapping_add(u32::from(byte));
+            b = b.wrapping_add((len - i as u32).wrapping_mul(u32::from(byte)));
+        }
+        Rolling { a, b, len }
+    }
+
+    /// Slide the window one byte, dropping `out` and appending `into`
+    fn roll(&mut self, out: u8, into: u8) {
+        self.a = self
+            .a
+            .wrapping_sub(u32::from(out))
+            .wrapping_add(u32::from(into));
+        self.b = self
+            .b
+            .wrapping_sub(self.len.wrapping_mul(u32::from(out)))
+            .wrapping_add(self.a);
+    }
+
+    fn digest(&self) -> u32 {
+        (self.a & 0xffff) | (self.b << 16)
+    }
+}
+
+fn strong_hash(block: &[u8]) -> String {
+    hex(&Sha256::digest(block)[..STRONG_LEN])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rolling_matches_fresh_checksum() {
+        let data = sample(64, 1);
+        let mut rolling = Rolling::new(&data[..16]);
+        for offset in 1..=48 {
+            rolling.roll(data[offset - 1], data[offset + 15]);
+            assert_eq!(
+                Rolling::new(&data[offset..offset + 16]).digest(),
+                rolling.digest()
+            );
+        }
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let index = BlockIndex::from_bytes(&sample(100, 2), 32).unwrap();
+        assert_eq!(4, index.blocks.len());
+        assert_eq!(index, BlockIndex::from_json(&index.to_json()).unwrap());
+        assert!(BlockIndex::from_json(
+            r#"{"size": 100, "block_size": 32, "sha256": "", "blocks": []}"#
+        )
+        .is_err());
+        assert!(BlockIndex::from_bytes(b"data", 0).is_err());
+    }
+
+    #[test]
+    fn differential_rebuild() {
+        let old = sample(1000, 3);
+        // shift content by an insertion and change a block in the middle
+        let mut new = b"inserted".to_vec();
+        new.extend_from_slice(&old[..500]);
+        new.extend_from_slice(&sample(64, 4));
+        new.extend_from_slice(&old[564..]);
+        let index = BlockIndex::from_bytes(&new, 64).unwrap();
+
+        let found = index.find_blocks(&old);
+        assert!(found.iter().filter(|f| f.is_some()).count() >= 10);
+        let missing = index.download_size(&found);
+        assert!(missing > 0 && missing < new.len() as u64 / 2, "{}", missing);
+
+        let rebuilt = index
+            .assemble(&old, &found, |start, end| {
+                Ok(new[start as usize..end as usize].to_vec())
+            })
+            .unwrap();
+        assert_eq!(new, rebuilt);
+
+        match index.assemble(&old, &found, |start, end| {
+            Ok(vec![0; (end - start) as usize])
+        }) {
+            Err(Error::ChecksumMismatch { .. }) => (),
+            r => panic!("expected ChecksumMismatch, got: {:?}", r.map(|_| ())),
+        }
+    }
+}
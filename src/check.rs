@@ -0,0 +1,360 @@
+/*!
+Helpers for checking whether a newer release is available
+
+```
+# use self_update::check::CheckCache;
+# use std::time::Duration;
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+let updater = self_update::backends::github::Update::configure()
+    .repo_owner("jaemk")
+    .repo_name("self_update")
+    .bin_name("self_update_example")
+    .current_version(self_update::cargo_crate_version!())
+    .build()?;
+
+// only ask GitHub once every 12 hours
+let latest = CheckCache::new("self_update_example")?
+    .ttl(Duration::from_secs(12 * 60 * 60))
+    .latest_version(|| Ok(updater.get_latest_release()?.version().to_owned()))?;
+println!("latest version: v{}", latest);
+# Ok(())
+# }
+```
+*/
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::json;
+
+use crate::errors::*;
+use crate::messages::{fill, Messages};
+use crate::version;
+
+/// Persists the result of the last update check so that the release API
+/// is queried at most once every `ttl`
+#[derive(Clone, Debug)]
+pub struct CheckCache {
+    path: PathBuf,
+    ttl: Duration,
+}
+
+impl CheckCache {
+    /// Store check results in the platform cache directory, e.g.
+    /// `~/.cache/<app_name>/self_update_check.json` on linux
+    ///
+    /// * Errors:
+    ///     * Config - The platform has no cache directory
+    pub fn new(app_name: &str) -> Result<Self> {
+        let dir = dirs::cache_dir().ok_or_else(|| {
+            Error::Config("Unable to determine the platform cache directory".into())
+        })?;
+        Ok(Self::at_path(
+            dir.join(app_name).join("self_update_check.json"),
+        ))
+    }
+
+    /// Store check results in the file at `path`
+    pub fn at_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            ttl: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// Set how long a check result is reused, defaults to 24 hours
+    pub fn ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Path of the file check results are stored in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the latest available version, calling `check` to look it up only if
+    /// the stored result is missing or older than `ttl`. A fresh result is stored
+    /// for subsequent calls.
+    ///
+    /// * Errors:
+    ///     * Any error returned by `check`
+    ///     * Io - Writing the check result
+    pub fn latest_version<F>(&self, check: F) -> Result<String>
+    where
+        F: FnOnce() -> Result<String>,
+    {
+        let now = unix_now();
+        if let Some((checked_at, version)) = self.load() {
+            let age = now.checked_sub(checked_at).map(Duration::from_secs);
+            if age.is_some_and(|age| age < self.ttl) {
+                return Ok(version);
+            }
+        }
+        let version = check()?;
+        self.store(now, &version)?;
+        Ok(version)
+    }
+
+    /// Forget the stored check result
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => Ok(r?),
+        }
+    }
+
+    fn load(&self) -> Option<(u64, String)> {
+        let content = fs::read(&self.path).ok()?;
+        let state = serde_json::from_slice::<serde_json::Value>(&content).ok()?;
+        let checked_at = state["checked_at"].as_u64()?;
+        let version = state["latest_version"].as_str()?;
+        Some((checked_at, version.to_owned()))
+    }
+
+    fn store(&self, checked_at: u64, version: &str) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let state = json!({ "checked_at": checked_at, "latest_version": version });
+        fs::write(&self.path, serde_json::to_vec(&state)?)?;
+        Ok(())
+    }
+}
+
+/// Produces a ready-to-print "new version available" notice, checking for
+/// new releases at most once per `CheckCache` ttl.
+///
+/// ```
+/// # use self_update::check::Notice;
+/// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+/// # let updater = self_update::backends::github::Update::configure().build()?;
+/// let notice = Notice::new("myapp", self_update::cargo_crate_version!())?
+///     .update_command("myapp self-update")
+///     .check(|| Ok(updater.get_latest_release()?.version().to_owned()))?;
+/// if let Some(notice) = notice {
+///     // "myapp v1.3.0 is available (you have v1.1.2); run `myapp self-update`"
+///     eprintln!("{}", notice);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Notice {
+    app_name: String,
+    current_version: String,
+    update_command: Option<String>,
+    cache: CheckCache,
+    messages: Messages,
+}
+
+impl Notice {
+    /// Notice for `app_name` currently at `current_version`, caching check
+    /// results in the platform cache directory (see `CheckCache::new`)
+    pub fn new(app_name: &str, current_version: &str) -> Result<Self> {
+        Ok(Self {
+            app_name: app_name.to_owned(),
+            current_version: current_version.to_owned(),
+            update_command: None,
+            cache: CheckCache::new(app_name)?,
+            messages: Messages::default(),
+        })
+    }
+
+    /// Set the command users should run to update, mentioned in the notice
+    pub fn update_command(&mut self, command: &str) -> &mut Self {
+        self.update_command = Some(command.to_owned());
+        self
+    }
+
+    /// Set the messages the notice is made of, see `Messages::update_available`
+    /// and `Messages::update_command`
+    pub fn messages(&mut self, messages: Messages) -> &mut Self {
+        self.messages = messages;
+        self
+    }
+
+    /// Set the cache used to limit how often `check` looks up the latest version
+    pub fn cache(&mut self, cache: CheckCache) -> &mut Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Return a notice if the latest version, looked up by `latest_version` unless
+    /// a fresh result is cached, is newer than the current version
+    ///
+    /// * Errors:
+    ///     * Any error returned by `latest_version`
+    ///     * SemVer - The current or latest version isn't valid semver
+    ///     * Io - Writing the check result
+    pub fn check<F>(&self, latest_version: F) -> Result<Option<String>>
+    where
+        F: FnOnce() -> Result<String>,
+    {
+        let latest = self.cache.latest_version(latest_version)?;
+        let current = self.current_version.trim_start_matches('v');
+        let latest = latest.trim_start_matches('v');
+        if !version::bump_is_greater(current, latest)? {
+            return Ok(None);
+        }
+        let mut notice = fill(
+            &self.messages.update_available,
+            &[
+                ("app", &self.app_name),
+                ("latest", &latest),
+                ("current", &current),
+            ],
+        );
+        if let Some(ref command) = self.update_command {
+            notice.push_str(&fill(
+                &self.messages.update_command,
+                &[("command", command)],
+            ));
+        }
+        Ok(Some(notice))
+    }
+}
+
+/// An update check running on a background thread while the application does
+/// its normal work. The result can be collected once, e.g. right before exiting
+/// to print a "new version available" notice.
+///
+/// ```
+/// # use self_update::check::BackgroundCheck;
+/// let check = BackgroundCheck::spawn(|| {
+///     // e.g. `updater.get_latest_release()`
+///     Ok("1.2.3".to_owned())
+/// });
+///
+/// // ... run the application ...
+///
+/// if let Some(Ok(latest)) = check.wait_timeout(std::time::Duration::from_millis(500)) {
+///     println!("latest version: v{}", latest);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BackgroundCheck<T> {
+    receiver: mpsc::Receiver<Result<T>>,
+}
+
+impl<T: Send + 'static> BackgroundCheck<T> {
+    /// Run `check` on a new thread
+    pub fn spawn<F>(check: F) -> Self
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            // the receiver may have been dropped if the app no longer cares
+            let _ = sender.send(check());
+        });
+        Self { receiver }
+    }
+
+    /// Return the result if the check has finished, without blocking.
+    /// Returns `None` while the check is running or once the result has been taken.
+    pub fn try_result(&self) -> Option<Result<T>> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Wait up to `timeout` for the check to finish, returning `None` if it hasn't
+    pub fn wait_timeout(&self, timeout: Duration) -> Option<Result<T>> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Block until the check finishes
+    ///
+    /// * Errors:
+    ///     * Any error returned by the check
+    ///     * Update - The check panicked
+    pub fn wait(self) -> Result<T> {
+        self.receiver
+            .recv()
+            .unwrap_or_else(|_| Err(Error::Update("Background update check panicked".into())))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tempdir::TempDir;
+
+    #[test]
+    fn reuses_fresh_result() {
+        let tmp = TempDir::new("self_update_check_cache").expect("tempdir fail");
+        let cache = CheckCache::at_path(tmp.path().join("nested").join("check.json"));
+        let checks = Cell::new(0);
+        let check = || {
+            checks.set(checks.get() + 1);
+            Ok("1.2.3".to_owned())
+        };
+
+        assert_eq!("1.2.3", cache.latest_version(check).unwrap());
+        assert_eq!("1.2.3", cache.latest_version(check).unwrap());
+        assert_eq!(1, checks.get());
+
+        cache.clear().unwrap();
+        assert_eq!("1.2.3", cache.latest_version(check).unwrap());
+        assert_eq!(2, checks.get());
+    }
+
+    #[test]
+    fn notice() {
+        let tmp = TempDir::new("self_update_check_notice").expect("tempdir fail");
+        let mut notice = Notice::new("myapp", "1.1.2").unwrap();
+        notice.cache(CheckCache::at_path(tmp.path().join("check.json")));
+        assert_eq!(
+            Some("myapp v1.3.0 is available (you have v1.1.2)".to_owned()),
+            notice.check(|| Ok("v1.3.0".to_owned())).unwrap()
+        );
+
+        notice
+            .update_command("myapp self-update")
+            .cache(CheckCache::at_path(tmp.path().join("check2.json")));
+        assert_eq!(
+            Some("myapp v1.3.0 is available (you have v1.1.2); run `myapp self-update`".to_owned()),
+            notice.check(|| Ok("1.3.0".to_owned())).unwrap()
+        );
+
+        notice.cache(CheckCache::at_path(tmp.path().join("check3.json")));
+        assert_eq!(None, notice.check(|| Ok("1.1.2".to_owned())).unwrap());
+    }
+
+    #[test]
+    fn background_check() {
+        let check = BackgroundCheck::spawn(|| Ok(42));
+        assert_eq!(42, check.wait().unwrap());
+
+        let check = BackgroundCheck::spawn(|| {
+            thread::sleep(Duration::from_millis(200));
+            Ok(1)
+        });
+        assert!(check.try_result().is_none());
+        assert!(check.wait_timeout(Duration::from_secs(5)).is_some());
+        assert!(check.try_result().is_none());
+
+        let check = BackgroundCheck::<()>::spawn(|| panic!("check failed"));
+        assert!(check.wait().is_err());
+    }
+
+    #[test]
+    fn expired_result_is_refreshed() {
+        let tmp = TempDir::new("self_update_check_cache_ttl").expect("tempdir fail");
+        let mut cache = CheckCache::at_path(tmp.path().join("check.json"));
+        cache.ttl(Duration::from_secs(0));
+        cache.latest_version(|| Ok("1.0.0".to_owned())).unwrap();
+        let latest = cache.latest_version(|| Ok("2.0.0".to_owned())).unwrap();
+        assert_eq!("2.0.0", latest);
+    }
+}
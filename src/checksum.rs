@@ -0,0 +1,289 @@
+/*!
+Checksums verifying downloaded release assets
+
+Release pipelines publish checksums either in a file next to each asset, named after
+it (e.g. `app-x86_64-unknown-linux-gnu.tar.gz.sha256`), or in one file listing the
+checksums of all assets (e.g. `SHA256SUMS`), as written by `sha256sum`, `sha512sum`
+or `b3sum`.
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::checksum::Algorithm;
+
+self_update::backends::github::Update::configure()
+    // fail unless the asset matches its published SHA-512 checksum
+    .checksum_algorithm(Algorithm::Sha512)
+    // ...
+#   .repo_owner("jaemk")
+#   .repo_name("self_update")
+#   .bin_name("self_update_example")
+#   .current_version(self_update::cargo_crate_version!())
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::errors::*;
+
+/// A checksum algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Algorithm {
+    Sha256,
+    Sha512,
+    /// Requires the `checksum-blake3` feature
+    Blake3,
+}
+
+impl Algorithm {
+    /// Every algorithm, strongest first
+    pub const ALL: &'static [Algorithm] =
+        &[Algorithm::Blake3, Algorithm::Sha512, Algorithm::Sha256];
+
+    /// Whether this algorithm is available with the enabled features
+    pub fn is_supported(self) -> bool {
+        match self {
+            Algorithm::Blake3 => cfg!(feature = "checksum-blake3"),
+            Algorithm::Sha256 | Algorithm::Sha512 => true,
+        }
+    }
+
+    /// Suffixes of files holding the checksum of the asset they're named after
+    pub(crate) fn sidecar_suffixes(self) -> &'static [&'static str] {
+        match self {
+            Algorithm::Sha256 => &[".sha256", ".sha256sum"],
+            Algorithm::Sha512 => &[".sha512", ".sha512sum"],
+            Algorithm::Blake3 => &[".b3", ".blake3"],
+        }
+    }
+
+    /// Names of files holding the checksums of all assets of a release
+    pub(crate) fn manifest_names(self) -> &'static [&'static str] {
+        match self {
+            Algorithm::Sha256 => &["SHA256SUMS", "sha256sums.txt"],
+            Algorithm::Sha512 => &["SHA512SUMS", "sha512sums.txt"],
+            Algorithm::Blake3 => &["B3SUMS", "b3sums.txt"],
+        }
+    }
+
+    /// Length of a hex encoded checksum
+    fn hex_len(self) -> usize {
+        match self {
+            Algorithm::Sha256 | Algorithm::Blake3 => 64,
+            Algorithm::Sha512 => 128,
+        }
+    }
+
+    /// Hex encoded checksum of the file at `path`
+    ///
+    /// * Errors:
+    ///     * Io - Reading the file
+    ///     * Config - The algorithm isn't supported, see `is_supported`
+    pub fn file_checksum<P: AsRef<Path>>(self, path: P) -> Result<String> {
+        let mut hasher = self.hasher()?;
+        io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+        Ok(hasher.finish())
+    }
+
+    /// A hasher computing checksums of this algorithm incrementally
+    ///
+    /// * Errors:
+    ///     * Config - The algorithm isn't supported, see `is_supported`
+    pub(crate) fn hasher(self) -> Result<Hasher> {
+        Ok(match self {
+            Algorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            Algorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            #[cfg(feature = "checksum-blake3")]
+            Algorithm::Blake3 => Hasher::Blake3(Box::default()),
+            #[cfg(not(feature = "checksum-blake3"))]
+            Algorithm::Blake3 => bail!(
+                Error::Config,
+                "BLAKE3 checksums require the `checksum-blake3` feature"
+            ),
+        })
+    }
+
+    /// Check that the file at `path` has the hex encoded checksum `expected`
+    ///
+    /// * Errors:
+    ///     * ChecksumMismatch - The checksums differ
+    ///     * the `file_checksum` errors
+    pub fn verify<P: AsRef<Path>>(self, path: P, expected: &str) -> Result<()> {
+        check(self.file_checksum(path)?, expected)
+    }
+}
+
+/// Checksum computation of data written to it
+#[derive(Debug)]
+pub(crate) enum Hasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    #[cfg(feature = "checksum-blake3")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    /// Hex encoded checksum of the data written
+    pub(crate) fn finish(self) -> String {
+        let digest = match self {
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+            #[cfg(feature = "checksum-blake3")]
+            Hasher::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        };
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(buf),
+            Hasher::Sha512(hasher) => hasher.update(buf),
+            #[cfg(feature = "checksum-blake3")]
+            Hasher::Blake3(hasher) => {
+                hasher.update(buf);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Check that the hex checksum `actual` is the `expected` one
+///
+/// * Errors:
+///     * ChecksumMismatch - The checksums differ
+pub(crate) fn check(actual: String, expected: &str) -> Result<()> {
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(Error::ChecksumMismatch {
+            expected: expected.to_owned(),
+            actual,
+        });
+    }
+    Ok(())
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha512 => "SHA-512",
+            Algorithm::Blake3 => "BLAKE3",
+        })
+    }
+}
+
+/// The algorithm and hex checksum of a `<algorithm>:<hex>` digest, as provided by the
+/// GitHub api for release assets, e.g. `sha256:2cf24d...`
+pub(crate) fn parse_digest(digest: &str) -> Option<(Algorithm, String)> {
+    let (name, checksum) = digest.split_once(':')?;
+    let algorithm = match name.to_lowercase().as_str() {
+        "sha256" => Algorithm::Sha256,
+        "sha512" => Algorithm::Sha512,
+        "blake3" => Algorithm::Blake3,
+        _ => return None,
+    };
+    let valid =
+        checksum.len() == algorithm.hex_len() && checksum.chars().all(|c| c.is_ascii_hexdigit());
+    Some((algorithm, checksum.to_lowercase())).filter(|_| valid)
+}
+
+/// The `algorithm` checksum of the file `name` in the `content` of a checksum file,
+/// either a lone checksum or `<checksum>  <name>` lines, with `*` before binary mode
+/// names and possibly a directory
+pub(crate) fn find_checksum(content: &str, name: &str, algorithm: Algorithm) -> Option<String> {
+    let is_checksum =
+        |s: &str| s.len() == algorithm.hex_len() && s.chars().all(|c| c.is_ascii_hexdigit());
+    let lines = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if let [line] = lines[..] {
+        if is_checksum(line) {
+            return Some(line.to_lowercase());
+        }
+    }
+    lines.iter().find_map(|line| {
+        let (checksum, file) = line.split_once(char::is_whitespace)?;
+        let file = file.trim_start().trim_start_matches('*');
+        let file = file.rsplit('/').next().unwrap_or(file);
+        Some(checksum.to_lowercase()).filter(|_| is_checksum(checksum) && file == name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    const SHA256_HELLO: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    #[test]
+    fn checksums() {
+        let tmp = TempDir::new("self_update_checksum").expect("tempdir fail");
+        let path = tmp.path().join("hello");
+        fs::write(&path, "hello").unwrap();
+        assert_eq!(
+            SHA256_HELLO,
+            Algorithm::Sha256.file_checksum(&path).unwrap()
+        );
+        assert_eq!(128, Algorithm::Sha512.file_checksum(&path).unwrap().len());
+        Algorithm::Sha256
+            .verify(&path, &SHA256_HELLO.to_uppercase())
+            .unwrap();
+        match Algorithm::Sha256.verify(&path, &"0".repeat(64)) {
+            Err(Error::ChecksumMismatch { actual, .. }) => assert_eq!(SHA256_HELLO, actual),
+            r => panic!("expected ChecksumMismatch, got {:?}", r),
+        }
+        assert_eq!(
+            cfg!(feature = "checksum-blake3"),
+            Algorithm::Blake3.file_checksum(&path).is_ok()
+        );
+    }
+
+    #[test]
+    fn checksum_files() {
+        let sha256 = Algorithm::Sha256;
+        assert_eq!(
+            Some(SHA256_HELLO.to_owned()),
+            find_checksum(&format!("{}\n", SHA256_HELLO), "app.tar.gz", sha256)
+        );
+        let sums = format!(
+            "{}  other.tar.gz\n{} *dist/app.tar.gz\n",
+            "1".repeat(64),
+            SHA256_HELLO
+        );
+        assert_eq!(
+            Some(SHA256_HELLO.to_owned()),
+            find_checksum(&sums, "app.tar.gz", sha256)
+        );
+        assert_eq!(None, find_checksum(&sums, "app.zip", sha256));
+        assert_eq!(
+            None,
+            find_checksum(SHA256_HELLO, "app.tar.gz", Algorithm::Sha512)
+        );
+    }
+
+    #[test]
+    fn digests() {
+        assert_eq!(
+            Some((Algorithm::Sha256, SHA256_HELLO.to_owned())),
+            parse_digest(&format!("sha256:{}", SHA256_HELLO.to_uppercase()))
+        );
+        assert_eq!(None, parse_digest(&format!("md5:{}", SHA256_HELLO)));
+        assert_eq!(None, parse_digest("sha256:abc"));
+        assert_eq!(None, parse_digest(SHA256_HELLO));
+    }
+}
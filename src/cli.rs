@@ -0,0 +1,129 @@
+/*!
+Ready-made `self-update` subcommand for `clap` applications
+
+Requires the `clap` feature.
+
+```no_run
+# use self_update::cli;
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+let matches = clap::App::new("myapp")
+    .subcommand(cli::subcommand())
+    .get_matches();
+
+if let Some(matches) = matches.subcommand_matches(cli::SUBCOMMAND) {
+    cli::run(
+        matches,
+        self_update::backends::github::Update::configure()
+            .repo_owner("me")
+            .repo_name("myapp")
+            .bin_name("myapp")
+            .current_version(self_update::cargo_crate_version!()),
+    )?;
+}
+# Ok(())
+# }
+```
+*/
+use clap::{App, Arg, ArgMatches, SubCommand};
+
+use crate::backends::github::UpdateBuilder;
+use crate::errors::*;
+use crate::version;
+
+/// Name of the subcommand returned by `subcommand`
+pub const SUBCOMMAND: &str = "self-update";
+
+/// The `self-update` subcommand, supporting:
+///
+/// * `--check` - only report whether a newer release is available
+/// * `--version <tag>` - install the release tagged `<tag>` instead of the latest
+/// * `--force` - install even if the release isn't newer than the current version
+/// * `--no-confirm` - don't ask for confirmation before installing
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name(SUBCOMMAND)
+        .about("Update to the latest release")
+        .arg(
+            Arg::with_name("check")
+                .long("check")
+                .help("Only check whether a newer release is available")
+                .conflicts_with_all(&["force", "version", "no-confirm"]),
+        )
+        .arg(
+            Arg::with_name("version")
+                .long("version")
+                .value_name("tag")
+                .takes_value(true)
+                .help("Install the release with this tag instead of the latest"),
+        )
+        .arg(
+            Arg::with_name("force")
+                .long("force")
+                .help("Install even if the release isn't newer than the current version"),
+        )
+        .arg(
+            Arg::with_name("no-confirm")
+                .long("no-confirm")
+                .help("Don't ask for confirmation before installing"),
+        )
+}
+
+/// Drive the updater configured by `builder` according to the flags in `matches`,
+/// the matches of the `subcommand` returned by `subcommand`.
+///
+/// On failure, `Error::exit_code` suggests a meaningful status to exit with.
+///
+/// * Errors:
+///     * Any error returned by `UpdateBuilder::build`, `Update::get_latest_release`
+///       or `Update::update`
+pub fn run(matches: &ArgMatches, builder: &mut UpdateBuilder) -> Result<()> {
+    if matches.is_present("check") {
+        let updater = builder.build()?;
+        let latest = updater.get_latest_release()?;
+        let current = updater.current_version();
+        if version::bump_is_greater(current, latest.version())? {
+            println!("v{} is available (you have v{})", latest.version(), current);
+        } else {
+            println!("v{} is the latest version", current);
+        }
+        return Ok(());
+    }
+
+    if matches.is_present("no-confirm") {
+        builder.no_confirm(true);
+    }
+    if let Some(tag) = matches.value_of("version") {
+        builder.target_version_tag(tag);
+    } else if matches.is_present("force") {
+        // pinning the latest tag skips the version comparison
+        let latest = builder.build()?.get_latest_release()?;
+        builder.target_version_tag(&latest.tag);
+    }
+
+    let status = builder.build()?.update()?;
+    println!("Update status: `v{}`!", status.version());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flags() {
+        let app = App::new("myapp").subcommand(subcommand());
+        let matches = app.clone().get_matches_from(vec![
+            "myapp",
+            "self-update",
+            "--version",
+            "v1.2.3",
+            "--force",
+        ]);
+        let matches = matches.subcommand_matches(SUBCOMMAND).unwrap();
+        assert_eq!(Some("v1.2.3"), matches.value_of("version"));
+        assert!(matches.is_present("force"));
+        assert!(!matches.is_present("no-confirm"));
+
+        let result = app.get_matches_from_safe(vec!["myapp", "self-update", "--check", "--force"]);
+        assert!(result.is_err());
+    }
+}
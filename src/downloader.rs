@@ -0,0 +1,49 @@
+/*!
+Pluggable transport for release assets
+
+Release assets are downloaded over HTTP unless the updater is given a custom
+`Downloader`, e.g. a client of an internal artifact store. Version checks still go
+through the release api, and the downloaded asset is verified and installed as usual.
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use std::io;
+
+self_update::backends::github::Update::configure()
+    .downloader(|url: &str, dest: &mut dyn io::Write| {
+        let path = url.replace("https://github.com/", "/mnt/mirror/");
+        io::copy(&mut std::fs::File::open(path)?, dest)?;
+        Ok(())
+    })
+    // ...
+#   .repo_owner("jaemk")
+#   .repo_name("self_update")
+#   .bin_name("self_update_example")
+#   .current_version(self_update::cargo_crate_version!())
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::io;
+
+use crate::errors::*;
+
+/// Fetches the content of release assets
+pub trait Downloader: Send + Sync {
+    /// Write the content of the asset at `url`, its download url in the release
+    /// metadata, to `dest`
+    ///
+    /// * Errors:
+    ///     * Any failure to fetch the asset, e.g. Network, HttpStatus or Io
+    fn download(&self, url: &str, dest: &mut dyn io::Write) -> Result<()>;
+}
+
+impl<F> Downloader for F
+where
+    F: Fn(&str, &mut dyn io::Write) -> Result<()> + Send + Sync,
+{
+    fn download(&self, url: &str, dest: &mut dyn io::Write) -> Result<()> {
+        self(url, dest)
+    }
+}
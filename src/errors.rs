@@ -2,24 +2,92 @@
 Error type, conversions, and macros
 
 */
-use reqwest;
 use semver;
 use serde_json;
 use std;
+use std::path::PathBuf;
+#[cfg(feature = "archive-zip")]
 use zip::result::ZipError;
 
+use crate::package_manager::PackageManager;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Errors that can occur while checking for, downloading, or installing an update.
+///
+/// Variants are added as new failure modes are supported, so matches should
+/// include a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
+    /// Generic update failure
     Update(String),
-    Network(String),
+    /// Transport-level request failure (dns, connect, tls, timeouts, ...), holding the
+    /// error of the http client, e.g. a `reqwest::Error` or `ureq::Transport`
+    Network(Box<dyn std::error::Error + Send + Sync>),
+    /// A request completed with an unsuccessful status code
+    HttpStatus {
+        url: String,
+        status: u16,
+    },
+    /// The release API's rate limit was exceeded. `reset_at` is the unix timestamp
+    /// at which the limit resets, if the server provided one.
+    RateLimited {
+        reset_at: Option<u64>,
+    },
+    /// Release metadata was missing or malformed
     Release(String),
+    /// The release doesn't contain an asset for the contained target
+    NoAssetForTarget(String),
+    /// The file to extract could not be found in the downloaded archive
+    ArchiveEntryNotFound(PathBuf),
+    /// The download of `url` ended after `received` of the `expected` bytes declared
+    /// by its `Content-Length`
+    Truncated {
+        url: String,
+        expected: u64,
+        received: u64,
+    },
+    /// The update didn't complete within the contained total timeout
+    TimedOut(std::time::Duration),
+    /// Downloaded content doesn't match its expected checksum
+    ChecksumMismatch {
+        expected: String,
+        actual: String,
+    },
+    /// Only `available` bytes are left in `path`, `required` are needed to download
+    /// and extract the update
+    InsufficientSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+    /// Insufficient permissions to write to `path`
+    PermissionDenied {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// The user declined to continue with the update
+    Aborted,
+    /// Confirmation was required but there's no terminal to ask on, see
+    /// `NonInteractive`
+    NonInteractive,
+    /// Updates were disabled by the contained environment variable
+    Disabled(String),
+    /// Another update holds the contained lock file
+    Locked(PathBuf),
+    /// The executable to replace, which isn't the running one, is being run by
+    /// another process
+    InUse(PathBuf),
+    /// The executable was installed by the contained package manager, which should
+    /// be used to update it instead
+    PackageManaged(PackageManager),
+    /// Invalid updater configuration
     Config(String),
     Io(std::io::Error),
+    #[cfg(feature = "archive-zip")]
     Zip(ZipError),
     Json(serde_json::Error),
-    Reqwest(reqwest::Error),
     SemVer(semver::SemVerError),
 }
 
@@ -28,29 +96,195 @@ impl std::fmt::Display for Error {
         use Error::*;
         match *self {
             Update(ref s) => write!(f, "UpdateError: {}", s),
-            Network(ref s) => write!(f, "NetworkError: {}", s),
+            Network(ref e) => write!(f, "NetworkError: {}", e),
+            HttpStatus { ref url, status } => write!(
+                f,
+                "HttpStatusError: request failed with status: {} - for: {:?}",
+                status, url
+            ),
+            RateLimited {
+                reset_at: Some(reset),
+            } => write!(
+                f,
+                "RateLimitedError: api rate limit exceeded, resets at: {}",
+                reset
+            ),
+            RateLimited { reset_at: None } => {
+                write!(f, "RateLimitedError: api rate limit exceeded")
+            }
             Release(ref s) => write!(f, "ReleaseError: {}", s),
+            NoAssetForTarget(ref target) => {
+                write!(f, "ReleaseError: No asset found for target: `{}`", target)
+            }
+            ArchiveEntryNotFound(ref path) => write!(
+                f,
+                "ArchiveError: Could not find the required path in the archive: {:?}",
+                path
+            ),
+            Truncated {
+                ref url,
+                expected,
+                received,
+            } => write!(
+                f,
+                "DownloadError: {:?} ended after {} of {} bytes",
+                url, received, expected
+            ),
+            TimedOut(timeout) => write!(
+                f,
+                "TimeoutError: Not done within the total timeout of {:?}",
+                timeout
+            ),
+            ChecksumMismatch {
+                ref expected,
+                ref actual,
+            } => write!(
+                f,
+                "ChecksumMismatchError: expected: {}, found: {}",
+                expected, actual
+            ),
+            InsufficientSpace {
+                ref path,
+                required,
+                available,
+            } => write!(
+                f,
+                "InsufficientSpaceError: {} bytes required in {:?}, only {} available",
+                required, path, available
+            ),
+            PermissionDenied {
+                ref path,
+                ref source,
+            } => write!(f, "PermissionDeniedError: {:?}: {}", path, source),
+            Aborted => write!(f, "UpdateError: Update aborted"),
+            NonInteractive => write!(
+                f,
+                "UpdateError: Confirmation required but not running in a terminal, \
+                 set `no_confirm` to update non-interactively"
+            ),
+            PackageManaged(ref manager) => write!(
+                f,
+                "UpdateError: Installed with {}, update with `{}` instead",
+                manager,
+                manager.upgrade_command()
+            ),
+            Locked(ref path) => write!(
+                f,
+                "UpdateError: Another update is in progress, lock held on: {:?}",
+                path
+            ),
+            InUse(ref path) => write!(f, "UpdateError: {:?} is being run by another process", path),
+            Disabled(ref var) => write!(f, "UpdateError: Updates are disabled by `{}`", var),
             Config(ref s) => write!(f, "ConfigError: {}", s),
             Io(ref e) => write!(f, "IoError: {}", e),
             Json(ref e) => write!(f, "JsonError: {}", e),
-            Reqwest(ref e) => write!(f, "ReqwestError: {}", e),
             SemVer(ref e) => write!(f, "SemVerError: {}", e),
+            #[cfg(feature = "archive-zip")]
             Zip(ref e) => write!(f, "ZipError: {}", e),
         }
     }
 }
 
-impl std::error::Error for Error {
-    fn description(&self) -> &str {
-        "Self Update Error"
+/// Broad category of an `Error`, see `Error::category`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// The release server couldn't be reached or refused the request
+    Network,
+    /// The executable or its directory isn't writable
+    Permission,
+    /// The requested release, asset or archive entry doesn't exist
+    NotFound,
+    /// Downloaded content or release metadata failed validation
+    Verification,
+    /// The user declined to continue with the update
+    AbortedByUser,
+    /// The updater is misconfigured, e.g. needs a confirmation it can't ask for, or
+    /// updates are disabled or managed elsewhere
+    Config,
+    /// Another update is in progress, or the executable to replace is running
+    Busy,
+    /// Any other failure
+    Other,
+}
+
+impl ErrorCategory {
+    /// Conventional process exit code for errors of this category, following
+    /// `sysexits.h` where it has a match:
+    ///
+    /// * Network - 69 (`EX_UNAVAILABLE`)
+    /// * Permission - 77 (`EX_NOPERM`)
+    /// * NotFound - 66 (`EX_NOINPUT`)
+    /// * Verification - 65 (`EX_DATAERR`)
+    /// * AbortedByUser - 130, as for a command interrupted with Ctrl-C
+    /// * Config - 78 (`EX_CONFIG`)
+    /// * Busy - 75 (`EX_TEMPFAIL`)
+    /// * Other - 1
+    pub fn exit_code(self) -> i32 {
+        use ErrorCategory::*;
+        match self {
+            Network => 69,
+            Permission => 77,
+            NotFound => 66,
+            Verification => 65,
+            AbortedByUser => 130,
+            Config => 78,
+            Busy => 75,
+            Other => 1,
+        }
     }
+}
 
-    fn cause(&self) -> Option<&std::error::Error> {
+impl Error {
+    /// The broad category of this error, e.g. to report it or pick an exit code
+    pub fn category(&self) -> ErrorCategory {
+        use Error::*;
+        match *self {
+            Network(_) | RateLimited { .. } | Truncated { .. } | TimedOut(_) => {
+                ErrorCategory::Network
+            }
+            HttpStatus { status: 404, .. } => ErrorCategory::NotFound,
+            HttpStatus { .. } => ErrorCategory::Network,
+            NoAssetForTarget(_) | ArchiveEntryNotFound(_) => ErrorCategory::NotFound,
+            ChecksumMismatch { .. } | Release(_) | Json(_) | SemVer(_) => {
+                ErrorCategory::Verification
+            }
+            #[cfg(feature = "archive-zip")]
+            Zip(_) => ErrorCategory::Verification,
+            PermissionDenied { .. } => ErrorCategory::Permission,
+            Io(ref e) => match e.kind() {
+                std::io::ErrorKind::PermissionDenied => ErrorCategory::Permission,
+                std::io::ErrorKind::NotFound => ErrorCategory::NotFound,
+                _ => ErrorCategory::Other,
+            },
+            Aborted => ErrorCategory::AbortedByUser,
+            Config(_) | Disabled(_) | PackageManaged(_) | NonInteractive => ErrorCategory::Config,
+            Locked(_) | InUse(_) => ErrorCategory::Busy,
+            Update(_) | InsufficientSpace { .. } => ErrorCategory::Other,
+        }
+    }
+
+    /// Suggested process exit code for this error, see `ErrorCategory::exit_code`
+    ///
+    /// ```
+    /// # use self_update::errors::Error;
+    /// assert_eq!(130, Error::Aborted.exit_code());
+    /// ```
+    pub fn exit_code(&self) -> i32 {
+        self.category().exit_code()
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         use Error::*;
         Some(match *self {
+            Network(ref e) => &**e,
+            PermissionDenied { ref source, .. } => source,
             Io(ref e) => e,
+            #[cfg(feature = "archive-zip")]
+            Zip(ref e) => e,
             Json(ref e) => e,
-            Reqwest(ref e) => e,
             SemVer(ref e) => e,
             _ => return None,
         })
@@ -59,6 +293,13 @@ impl std::error::Error for Error {
 
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Error {
+        // our errors passed through readers, e.g. a truncated download being extracted
+        if e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            if let Some(Ok(inner)) = e.into_inner().map(|inner| inner.downcast::<Error>()) {
+                return *inner;
+            }
+            unreachable!("checked to wrap an `Error`")
+        }
         Error::Io(e)
     }
 }
@@ -69,9 +310,17 @@ impl From<serde_json::Error> for Error {
     }
 }
 
+#[cfg(feature = "reqwest")]
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Error {
-        Error::Reqwest(e)
+        Error::Network(Box::new(e))
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl From<ureq::Transport> for Error {
+    fn from(e: ureq::Transport) -> Error {
+        Error::Network(Box::new(e))
     }
 }
 
@@ -81,8 +330,45 @@ impl From<semver::SemVerError> for Error {
     }
 }
 
+#[cfg(feature = "archive-zip")]
 impl From<ZipError> for Error {
     fn from(e: ZipError) -> Error {
         Error::Zip(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories() {
+        let not_found = Error::HttpStatus {
+            url: "u".into(),
+            status: 404,
+        };
+        assert_eq!(ErrorCategory::NotFound, not_found.category());
+        let unavailable = Error::HttpStatus {
+            url: "u".into(),
+            status: 503,
+        };
+        assert_eq!(69, unavailable.exit_code());
+        let denied = Error::Io(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert_eq!(ErrorCategory::Permission, denied.category());
+        assert_eq!(
+            ErrorCategory::Verification,
+            Error::ChecksumMismatch {
+                expected: "a".into(),
+                actual: "b".into()
+            }
+            .category()
+        );
+        assert_eq!(ErrorCategory::AbortedByUser, Error::Aborted.category());
+        assert_eq!(78, Error::Config("c".into()).exit_code());
+        let network = Error::Network(Box::new(std::io::Error::from(
+            std::io::ErrorKind::ConnectionRefused,
+        )));
+        assert_eq!(ErrorCategory::Network, network.category());
+        assert!(std::error::Error::source(&network).is_some_and(|e| e.is::<std::io::Error>()));
+    }
+}
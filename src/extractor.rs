@@ -0,0 +1,170 @@
+/*!
+Pluggable extraction of executables from release assets
+
+Release assets are handled by the built-in `ArchiveKind`s (tar, zip and plain files,
+optionally gzipped) unless a custom `Extractor` registered with the updater claims them,
+e.g. to support a proprietary packaging format.
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use std::path::{Path, PathBuf};
+use self_update::extractor::Extractor;
+
+/// Assets named `*.pkg`: a 4 byte header followed by the executable
+struct PkgExtractor;
+
+impl Extractor for PkgExtractor {
+    fn handles(&self, archive: &Path) -> bool {
+        archive.extension().is_some_and(|ext| ext == "pkg")
+    }
+
+    fn extract_file(
+        &self,
+        archive: &Path,
+        into_dir: &Path,
+        file_to_extract: &Path,
+    ) -> self_update::errors::Result<PathBuf> {
+        let content = std::fs::read(archive)?;
+        let out = into_dir.join(file_to_extract);
+        std::fs::write(&out, &content[4.min(content.len())..])?;
+        Ok(out)
+    }
+}
+
+self_update::backends::github::Update::configure()
+    .extractor(PkgExtractor)
+    // ...
+#   .repo_owner("jaemk")
+#   .repo_name("self_update")
+#   .bin_name("self_update_example")
+#   .current_version(self_update::cargo_crate_version!())
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::path::{Path, PathBuf};
+
+use crate::errors::*;
+use crate::{detect_archive, ArchiveKind, Extract};
+
+/// Extracts files from a downloaded release asset
+pub trait Extractor: Send + Sync {
+    /// Whether this extractor can extract from the asset at `archive`, typically
+    /// judged by its file name
+    fn handles(&self, archive: &Path) -> bool;
+
+    /// Extract `file_to_extract`, a path within `archive`, into `into_dir` and return
+    /// the path of the extracted file
+    ///
+    /// * Errors:
+    ///     * ArchiveEntryNotFound - `file_to_extract` isn't in the archive
+    ///     * Io - reading the archive / writing the file
+    fn extract_file(
+        &self,
+        archive: &Path,
+        into_dir: &Path,
+        file_to_extract: &Path,
+    ) -> Result<PathBuf>;
+}
+
+/// The built-in formats, each handling the assets whose extension it's detected from
+impl Extractor for ArchiveKind {
+    fn handles(&self, archive: &Path) -> bool {
+        detect_archive(archive) == *self
+    }
+
+    fn extract_file(
+        &self,
+        archive: &Path,
+        into_dir: &Path,
+        file_to_extract: &Path,
+    ) -> Result<PathBuf> {
+        Extract::from_source(archive)
+            .archive(*self)
+            .extract_file(into_dir, file_to_extract)?;
+        Ok(match *self {
+            // single-file sources are extracted under just the file name
+            ArchiveKind::Plain(_) => {
+                into_dir.join(file_to_extract.file_name().ok_or_else(|| {
+                    format_err!(Error::Config, "{:?} has no file-name", file_to_extract)
+                })?)
+            }
+            _ => into_dir.join(file_to_extract),
+        })
+    }
+}
+
+/// The first of the `custom` extractors handling `archive`, if any
+pub(crate) fn find<'e>(
+    custom: &'e [crate::Callback<dyn Extractor>],
+    archive: &Path,
+) -> Option<&'e dyn Extractor> {
+    custom
+        .iter()
+        .map(|extractor| &*extractor.0)
+        .find(|extractor| extractor.handles(archive))
+}
+
+/// Extract `file_to_extract` from `archive` with the first of the `custom` extractors
+/// handling it, or else the built-in format detected from its extension
+pub(crate) fn extract_file(
+    custom: &[crate::Callback<dyn Extractor>],
+    archive: &Path,
+    into_dir: &Path,
+    file_to_extract: &Path,
+) -> Result<PathBuf> {
+    match find(custom, archive) {
+        Some(extractor) => extractor.extract_file(archive, into_dir, file_to_extract),
+        None => detect_archive(archive).extract_file(archive, into_dir, file_to_extract),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Callback;
+    use std::fs;
+    use std::sync::Arc;
+    use tempdir::TempDir;
+
+    struct Reversed;
+
+    impl Extractor for Reversed {
+        fn handles(&self, archive: &Path) -> bool {
+            archive.extension().is_some_and(|ext| ext == "rev")
+        }
+
+        fn extract_file(
+            &self,
+            archive: &Path,
+            into_dir: &Path,
+            file_to_extract: &Path,
+        ) -> Result<PathBuf> {
+            let mut content = fs::read(archive)?;
+            content.reverse();
+            let out = into_dir.join(file_to_extract);
+            fs::write(&out, content)?;
+            Ok(out)
+        }
+    }
+
+    #[test]
+    fn custom_before_builtin() {
+        let tmp = TempDir::new("self_update_extractor").expect("tempdir fail");
+        let custom: Vec<Callback<dyn Extractor>> = vec![Callback(Arc::new(Reversed))];
+        let rev = tmp.path().join("app.rev");
+        fs::write(&rev, "ppa").unwrap();
+        let out = extract_file(&custom, &rev, tmp.path(), Path::new("app")).unwrap();
+        assert_eq!("app", fs::read_to_string(out).unwrap());
+
+        let plain = tmp.path().join("other");
+        fs::write(&plain, "plain").unwrap();
+        let into = tmp.path().join("out");
+        let out = extract_file(&custom, &plain, &into, Path::new("bin/other")).unwrap();
+        assert_eq!(into.join("other"), out);
+        assert_eq!("plain", fs::read_to_string(out).unwrap());
+        assert!(ArchiveKind::Zip.handles(Path::new("app.zip")));
+        assert!(!ArchiveKind::Zip.handles(Path::new("app.rev")));
+    }
+}
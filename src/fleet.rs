@@ -0,0 +1,388 @@
+/*!
+Check and update several tools at once from a manifest
+
+```toml
+[[tool]]
+repo = "BurntSushi/ripgrep"
+bin = "rg"
+install_path = "~/.local/bin/rg"
+current_version = "13.0.0"
+
+[[tool]]
+repo = "sharkdp/fd"
+bin = "fd"
+install_path = "~/.local/bin/fd"
+current_version = "8.7.0"
+# install this release instead of the latest
+target_version = "v9.0.0"
+```
+
+```no_run
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::fleet::Manifest;
+
+let manifest = Manifest::load("tools.toml")?;
+let report = manifest.update_with(|_tool, builder| {
+    builder.auth_token("ghp_...");
+});
+print!("{}", report);
+if report.failed() > 0 {
+    std::process::exit(1);
+}
+# Ok(())
+# }
+```
+*/
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backends::github::{Update, UpdateBuilder};
+use crate::backends::Updater;
+use crate::errors::*;
+
+/// A tool released on GitHub and where it's installed
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tool {
+    pub repo_owner: String,
+    pub repo_name: String,
+    pub bin_name: String,
+    pub install_path: PathBuf,
+    /// Version installed at `install_path`
+    pub current_version: String,
+    /// Release tag to install instead of the latest release
+    pub target_version: Option<String>,
+}
+
+impl Tool {
+    /// An updater for this tool, without output or confirmation
+    fn configure(&self) -> UpdateBuilder {
+        let mut builder = Update::configure();
+        builder
+            .repo_owner(&self.repo_owner)
+            .repo_name(&self.repo_name)
+            .bin_name(&self.bin_name)
+            .bin_install_path(&self.install_path)
+            .current_version(&self.current_version)
+            .show_output(false)
+            .no_confirm(true);
+        if let Some(ref tag) = self.target_version {
+            builder.target_version_tag(tag);
+        }
+        builder
+    }
+}
+
+/// The tools to manage
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Manifest {
+    pub tools: Vec<Tool>,
+}
+
+/// Result of checking or updating one tool
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The contained version is the latest
+    UpToDate(String),
+    /// A newer release is available
+    Available { current: String, latest: String },
+    /// The tool was updated
+    Updated { from: String, to: String },
+}
+
+/// Outcome of checking or updating every tool of a `Manifest`, in manifest order
+#[derive(Debug)]
+pub struct Report {
+    pub results: Vec<(String, Result<Outcome>)>,
+}
+
+impl Report {
+    /// Number of tools whose check or update failed
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|(_, r)| r.is_err()).count()
+    }
+
+    /// Number of tools that were updated
+    pub fn updated(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Updated { .. }))
+    }
+
+    /// Number of tools with a newer release available
+    pub fn available(&self) -> usize {
+        self.count(|outcome| matches!(outcome, Outcome::Available { .. }))
+    }
+
+    fn count<F: Fn(&Outcome) -> bool>(&self, f: F) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, r)| r.as_ref().is_ok_and(&f))
+            .count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (bin, result) in &self.results {
+            match result {
+                Ok(Outcome::UpToDate(version)) => {
+                    writeln!(f, "{}: v{} is up to date", bin, version)?
+                }
+                Ok(Outcome::Available { current, latest }) => {
+                    writeln!(f, "{}: v{} -> v{} available", bin, current, latest)?
+                }
+                Ok(Outcome::Updated { from, to }) => {
+                    writeln!(f, "{}: updated v{} -> v{}", bin, from, to)?
+                }
+                Err(e) => writeln!(f, "{}: failed: {}", bin, e)?,
+            }
+        }
+        writeln!(
+            f,
+            "{} tools: {} updated, {} available, {} failed",
+            self.results.len(),
+            self.updated(),
+            self.available(),
+            self.failed()
+        )
+    }
+}
+
+impl Manifest {
+    /// Load the manifest at `path`
+    ///
+    /// * Errors:
+    ///     * Io - Reading the file
+    ///     * Config - The file isn't a valid manifest
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Manifest> {
+        let path = path.as_ref();
+        Manifest::from_toml(&fs::read_to_string(path)?)
+            .map_err(|e| format_err!(Error::Config, "Invalid manifest {:?}: {}", path, e))
+    }
+
+    /// Parse a manifest from toml, a leading `~/` in install paths is expanded to
+    /// the home directory
+    ///
+    /// * Errors:
+    ///     * Config - Invalid toml, missing or unknown keys, or values of the wrong type
+    pub fn from_toml(content: &str) -> Result<Manifest> {
+        let table = match content.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => bail!(Error::Config, "manifest must be a table"),
+            Err(e) => bail!(Error::Config, "{}", e),
+        };
+        let mut manifest = Manifest::default();
+        for (key, value) in &table {
+            match (key.as_str(), value) {
+                ("tool", toml::Value::Array(tools)) => {
+                    for tool in tools {
+                        manifest.tools.push(parse_tool(tool)?);
+                    }
+                }
+                ("tool", _) => bail!(Error::Config, "`tool` must be an array of tables"),
+                _ => bail!(Error::Config, "unknown manifest key `{}`", key),
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Check every tool for a newer release
+    pub fn check(&self) -> Report {
+        self.check_with(|_, _| ())
+    }
+
+    /// Check every tool for a newer release, with updaters adjusted by `configure`
+    /// (e.g. to set an `auth_token` or `api_url`)
+    pub fn check_with<F>(&self, configure: F) -> Report
+    where
+        F: Fn(&Tool, &mut UpdateBuilder),
+    {
+        self.each(|tool| {
+            let mut builder = tool.configure();
+            configure(tool, &mut builder);
+            let updater = builder.build()?;
+            Ok(match updater.check()? {
+                Some(latest) => Outcome::Available {
+                    current: tool.current_version.clone(),
+                    latest,
+                },
+                None => Outcome::UpToDate(tool.current_version.clone()),
+            })
+        })
+    }
+
+    /// Update every tool, without output or confirmation
+    pub fn update(&self) -> Report {
+        self.update_with(|_, _| ())
+    }
+
+    /// Update every tool, with updaters adjusted by `configure` (e.g. to set an
+    /// `auth_token` or show output). A failed update doesn't stop the others.
+    pub fn update_with<F>(&self, configure: F) -> Report
+    where
+        F: Fn(&Tool, &mut UpdateBuilder),
+    {
+        self.each(|tool| {
+            let mut builder = tool.configure();
+            configure(tool, &mut builder);
+            let status = builder.build()?.update()?;
+            Ok(if status.updated() {
+                Outcome::Updated {
+                    from: tool.current_version.clone(),
+                    to: status.version().to_owned(),
+                }
+            } else {
+                Outcome::UpToDate(status.version().to_owned())
+            })
+        })
+    }
+
+    fn each<F: Fn(&Tool) -> Result<Outcome>>(&self, f: F) -> Report {
+        Report {
+            results: self
+                .tools
+                .iter()
+                .map(|tool| (tool.bin_name.clone(), f(tool)))
+                .collect(),
+        }
+    }
+}
+
+fn parse_tool(value: &toml::Value) -> Result<Tool> {
+    let table = match value {
+        toml::Value::Table(table) => table,
+        _ => bail!(Error::Config, "`tool` must be an array of tables"),
+    };
+    let string = |key: &str| -> Result<Option<String>> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => bail!(Error::Config, "invalid value for `{}`", key),
+        }
+    };
+    let required = |key: &str| -> Result<String> {
+        string(key)?.ok_or_else(|| format_err!(Error::Config, "tool missing `{}`", key))
+    };
+    if let Some(key) = table.keys().find(|key| {
+        ![
+            "repo",
+            "bin",
+            "install_path",
+            "current_version",
+            "target_version",
+        ]
+        .contains(&key.as_str())
+    }) {
+        bail!(Error::Config, "unknown tool key `{}`", key)
+    }
+    let repo = required("repo")?;
+    let (owner, name) = match repo.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() => (owner, name),
+        _ => bail!(
+            Error::Config,
+            "`repo` must be `owner/name`, found {:?}",
+            repo
+        ),
+    };
+    Ok(Tool {
+        repo_owner: owner.to_owned(),
+        repo_name: name.to_owned(),
+        bin_name: required("bin")?,
+        install_path: expand_home(&required("install_path")?)?,
+        current_version: required("current_version")?
+            .trim_start_matches('v')
+            .to_owned(),
+        target_version: string("target_version")?,
+    })
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> Result<PathBuf> {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .ok_or_else(|| Error::Config("Unable to determine the home directory".into())),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::ReleaseServer;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse() {
+        let manifest = Manifest::from_toml(
+            r#"
+            [[tool]]
+            repo = "owner/app"
+            bin = "app"
+            install_path = "/opt/app"
+            current_version = "v1.0.0"
+            target_version = "v1.1.0"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![Tool {
+                repo_owner: "owner".into(),
+                repo_name: "app".into(),
+                bin_name: "app".into(),
+                install_path: PathBuf::from("/opt/app"),
+                current_version: "1.0.0".into(),
+                target_version: Some("v1.1.0".into()),
+            }],
+            manifest.tools
+        );
+        assert_eq!(Manifest::default(), Manifest::from_toml("").unwrap());
+
+        let tool = "[[tool]]\nrepo = \"owner/app\"\nbin = \"app\"\ninstall_path = \"/a\"\n";
+        assert!(Manifest::from_toml(tool).is_err());
+        assert!(Manifest::from_toml(&format!("{}current_version = \"1\"\nx = 1", tool)).is_err());
+        assert!(Manifest::from_toml("tools = []").is_err());
+        assert!(Manifest::from_toml("[[tool]]\nrepo = \"app\"").is_err());
+    }
+
+    #[test]
+    fn update_all() {
+        let tmp = TempDir::new("self_update_fleet").expect("tempdir fail");
+        let fixtures = tmp.path().join("fixtures").join("v1.1.0");
+        fs::create_dir_all(&fixtures).unwrap();
+        fs::write(fixtures.join("app-test-target"), "1.1.0").unwrap();
+        let server = ReleaseServer::start(tmp.path().join("fixtures")).unwrap();
+        let tool = |repo: &str, bin: &str, version: &str| Tool {
+            repo_owner: "owner".into(),
+            repo_name: repo.into(),
+            bin_name: bin.into(),
+            install_path: tmp.path().join("bin").join(bin),
+            current_version: version.into(),
+            target_version: None,
+        };
+        let manifest = Manifest {
+            tools: vec![tool("app", "old", "1.0.0"), tool("app", "new", "1.1.0")],
+        };
+        for tool in &manifest.tools {
+            fs::create_dir_all(tool.install_path.parent().unwrap()).unwrap();
+            fs::write(&tool.install_path, &tool.current_version).unwrap();
+        }
+        let configure = |_: &Tool, builder: &mut UpdateBuilder| {
+            builder.api_url(&server.api_url()).target("test-target");
+        };
+
+        let report = manifest.check_with(configure);
+        assert_eq!((1, 0), (report.available(), report.failed()));
+        let report = manifest.update_with(configure);
+        assert_eq!((1, 0), (report.updated(), report.failed()));
+        assert_eq!(
+            "1.1.0",
+            fs::read_to_string(tmp.path().join("bin").join("old")).unwrap()
+        );
+        assert_eq!(
+            "old: updated v1.0.0 -> v1.1.0\n\
+             new: v1.1.0 is up to date\n\
+             2 tools: 1 updated, 0 available, 0 failed\n",
+            report.to_string()
+        );
+    }
+}
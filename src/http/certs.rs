@@ -0,0 +1,83 @@
+/*!
+Loading of additional TLS root certificates
+*/
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::errors::*;
+
+/// Common locations of the CA bundle on linux distributions
+const SYSTEM_CA_BUNDLES: &[&str] = &[
+    // Debian, Ubuntu, Arch, Gentoo, Alpine
+    "/etc/ssl/certs/ca-certificates.crt",
+    // Fedora, RHEL, CentOS
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    // openSUSE
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/pki/tls/cacert.pem",
+    "/etc/ssl/cert.pem",
+];
+
+/// The system CA bundle, for TLS libraries that are statically linked and don't
+/// know where the distribution keeps it. `None` if `SSL_CERT_FILE` already points
+/// to a bundle, or on other platforms.
+pub(super) fn system_ca_bundle() -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") || std::env::var_os("SSL_CERT_FILE").is_some() {
+        return None;
+    }
+    SYSTEM_CA_BUNDLES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// Read every certificate in the PEM file at `path`
+///
+/// * Errors:
+///     * Io - Reading the file
+///     * Config - The file contains no or invalid certificates
+pub(super) fn read_certificates(path: &Path) -> Result<Vec<reqwest::Certificate>> {
+    let pem = fs::read_to_string(path)?;
+    let certs = split_pem(&pem)
+        .into_iter()
+        .map(|cert| reqwest::Certificate::from_pem(cert.as_bytes()))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| format_err!(Error::Config, "Invalid certificate in {:?}: {}", path, e))?;
+    if certs.is_empty() {
+        bail!(Error::Config, "No certificates found in {:?}", path);
+    }
+    Ok(certs)
+}
+
+/// Split a PEM bundle into its individual certificates
+fn split_pem(pem: &str) -> Vec<&str> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certs = vec![];
+    let mut rest = pem;
+    while let Some(start) = rest.find(BEGIN) {
+        let end = match rest[start..].find(END) {
+            Some(end) => start + end + END.len(),
+            None => break,
+        };
+        certs.push(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    certs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pem_bundles() {
+        let bundle = "# comment\n-----BEGIN CERTIFICATE-----\nAAAA\n-----END CERTIFICATE-----\n\
+                      -----BEGIN CERTIFICATE-----\nBBBB\n-----END CERTIFICATE-----\n";
+        let certs = split_pem(bundle);
+        assert_eq!(2, certs.len());
+        assert!(certs[0].contains("AAAA") && certs[0].ends_with("-----END CERTIFICATE-----"));
+        assert!(certs[1].starts_with("-----BEGIN CERTIFICATE-----\nBBBB"));
+        assert!(split_pem("-----BEGIN CERTIFICATE-----\nAAAA").is_empty());
+    }
+}
@@ -0,0 +1,419 @@
+/*!
+Shared HTTP client configuration
+
+Requests are made with `reqwest`, or with the lighter `ureq` when only the `ureq`
+feature is enabled.
+*/
+use std::cmp::min;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::errors::*;
+
+#[cfg(all(
+    feature = "reqwest",
+    any(feature = "default-tls", feature = "rustls-tls")
+))]
+mod certs;
+#[cfg(feature = "reqwest")]
+mod reqwest_backend;
+#[cfg(feature = "reqwest")]
+use reqwest_backend as backend;
+#[cfg(all(feature = "ureq", not(feature = "reqwest")))]
+mod ureq_backend;
+#[cfg(all(feature = "ureq", not(feature = "reqwest")))]
+use ureq_backend as backend;
+
+#[cfg(not(any(feature = "reqwest", feature = "ureq")))]
+compile_error!(
+    "self_update needs an http client, enable one of the `default-tls`, `rustls-tls` or `ureq` features"
+);
+
+/// User-Agent sent when none is configured
+pub(crate) const DEFAULT_USER_AGENT: &str = concat!("self_update/", env!("CARGO_PKG_VERSION"));
+
+/// Time to wait on the server for the response or any chunk of the body, unless set
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound on the delay between two attempts of the same request
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Settings applied to every request made by an updater
+#[derive(Clone, Debug)]
+pub(crate) struct HttpConfig {
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) max_retries: u32,
+    pub(crate) retry_backoff: Duration,
+    /// Longest wait for an exhausted api rate limit to reset before retrying
+    pub(crate) rate_limit_wait: Option<Duration>,
+    pub(crate) user_agent: Option<String>,
+    /// `Accept` header of requests, e.g. to request the content of an asset from the api
+    pub(crate) accept: Option<String>,
+    /// Additional headers of every request, by name and value
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) auth_token: Option<String>,
+    pub(crate) proxy: Option<String>,
+    pub(crate) root_certificates: Vec<PathBuf>,
+    pub(crate) system_certificates: bool,
+    /// Deadline of the whole operation the requests are made for
+    pub(crate) deadline: Option<Deadline>,
+    /// Client built from the settings above by `init_client`, shared by clones
+    client: Option<backend::Client>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: None,
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            timeout: None,
+            max_retries: 0,
+            retry_backoff: Duration::from_millis(500),
+            rate_limit_wait: None,
+            user_agent: None,
+            accept: None,
+            headers: vec![],
+            auth_token: None,
+            proxy: None,
+            root_certificates: vec![],
+            system_certificates: true,
+            deadline: None,
+            client: None,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Build the client once the settings are final, so that every request made with
+    /// this config and its clones shares its connection pool. Settings changed
+    /// afterwards are ignored.
+    ///
+    /// * Errors:
+    ///     * Config - Invalid proxy or certificates
+    ///     * Network - Building the client
+    pub(crate) fn init_client(&mut self) -> Result<()> {
+        self.client = None;
+        self.client = Some(self.client()?);
+        Ok(())
+    }
+
+    fn client(&self) -> Result<backend::Client> {
+        match self.client {
+            Some(ref client) => Ok(client.clone()),
+            None => backend::build_client(self),
+        }
+    }
+
+    /// Timeout of waiting on the response and of each individual read of its body
+    fn read_timeout(&self) -> Option<Duration> {
+        match (self.read_timeout, self.timeout) {
+            (Some(read), Some(total)) => Some(min(read, total)),
+            (read, total) => read.or(total),
+        }
+    }
+
+    /// Delay before the given (zero-based) retry: `retry_backoff` doubled for
+    /// every previous retry, capped at `MAX_RETRY_BACKOFF`
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry).unwrap_or(u32::MAX);
+        self.retry_backoff
+            .checked_mul(factor)
+            .map_or(MAX_RETRY_BACKOFF, |delay| min(delay, MAX_RETRY_BACKOFF))
+    }
+
+    /// Delay until a rate limit resetting at the unix timestamp `reset_at` is reset,
+    /// if it's known and within `rate_limit_wait`
+    pub(crate) fn rate_limit_delay(&self, reset_at: Option<u64>) -> Option<Duration> {
+        let max = self.rate_limit_wait?;
+        // a second of margin for the clock difference with the server
+        let delay = Duration::from_secs(reset_at?.saturating_sub(unix_now()) + 1);
+        let in_time = self.deadline.is_none_or(|d| delay < d.remaining());
+        (delay <= max && in_time).then_some(delay)
+    }
+
+    /// Send a GET request to `url`, retrying up to `max_retries` times on
+    /// transient network failures and 5xx responses
+    pub(crate) fn get(&self, url: &str) -> Result<Response> {
+        self.send(url, &[])
+    }
+
+    /// Request `url` unless it changed since the response with the given `ETag` /
+    /// `Last-Modified` headers, like `get`. Unchanged resources are answered with
+    /// `304 Not Modified`.
+    pub(crate) fn get_conditional(
+        &self,
+        url: &str,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Response> {
+        let mut headers = vec![];
+        if let Some(etag) = etag {
+            headers.push(("if-none-match", etag));
+        }
+        if let Some(last_modified) = last_modified {
+            headers.push(("if-modified-since", last_modified));
+        }
+        self.send(url, &headers)
+    }
+
+    /// Request the bytes `start..end` of `url`, like `get`
+    ///
+    /// * Errors:
+    ///     * HttpStatus - The server doesn't support range requests
+    pub(crate) fn get_range(&self, url: &str, start: u64, end: u64) -> Result<Response> {
+        let range = format!("bytes={}-{}", start, end - 1);
+        let resp = self.send(url, &[("range", &range)])?;
+        if resp.status() != 206 {
+            return Err(Error::HttpStatus {
+                url: url.to_owned(),
+                status: resp.status(),
+            });
+        }
+        Ok(resp)
+    }
+
+    /// Request the bytes of `url` from `start` on if it still has the `ETag` `etag`,
+    /// or else the whole of it, like `get`. Partial responses have status
+    /// `206 Partial Content`.
+    pub(crate) fn get_from(&self, url: &str, start: u64, etag: &str) -> Result<Response> {
+        let range = format!("bytes={}-", start);
+        self.send(url, &[("range", &range), ("if-range", etag)])
+    }
+
+    fn send(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response> {
+        let client = self.client()?;
+        let user_agent = self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let authorization = self.auth_token.as_ref().map(|t| format!("token {}", t));
+        let mut all_headers = vec![("user-agent", user_agent)];
+        if let Some(ref authorization) = authorization {
+            all_headers.push(("authorization", authorization));
+        }
+        if let Some(ref accept) = self.accept {
+            all_headers.push(("accept", accept));
+        }
+        all_headers.extend(self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        all_headers.extend_from_slice(headers);
+        let mut retry = 0;
+        loop {
+            if let Some(deadline) = self.deadline {
+                deadline.check()?;
+            }
+            let deadline = self.timeout.map(|t| Instant::now() + t);
+            let result = backend::send(&client, url, &all_headers);
+            let transient = match result {
+                Ok(ref resp) => resp.status() >= 500,
+                Err(ref e) => backend::is_transient(e),
+            };
+            if transient && retry < self.max_retries {
+                let backoff = self.backoff(retry);
+                thread::sleep(
+                    self.deadline
+                        .map_or(backoff, |d| min(backoff, d.remaining())),
+                );
+                retry += 1;
+                continue;
+            }
+            let mut resp = result?;
+            resp.deadline = deadline;
+            resp.operation_deadline = self.deadline;
+            return Ok(resp);
+        }
+    }
+}
+
+/// Instant by which an operation made of several requests, e.g. an update, must be
+/// done, see `Error::TimedOut`
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Deadline {
+    at: Instant,
+    timeout: Duration,
+}
+
+impl Deadline {
+    /// The deadline `timeout` from now
+    pub(crate) fn after(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now() + timeout,
+            timeout,
+        }
+    }
+
+    /// Time left until the deadline
+    pub(crate) fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// * Errors:
+    ///     * TimedOut - The deadline has passed
+    pub(crate) fn check(&self) -> Result<()> {
+        if Instant::now() >= self.at {
+            return Err(Error::TimedOut(self.timeout));
+        }
+        Ok(())
+    }
+}
+
+/// Seconds since the unix epoch
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// A response whose body reads fail once the configured overall `timeout` has elapsed,
+/// or the deadline of the operation it's part of has passed
+pub(crate) struct Response {
+    status: u16,
+    /// Lowercase header names and their values, repeated headers appear once per value
+    headers: Vec<(String, String)>,
+    body: Box<dyn io::Read + Send>,
+    deadline: Option<Instant>,
+    operation_deadline: Option<Deadline>,
+}
+
+impl Response {
+    fn new<R: io::Read + Send + 'static>(
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: R,
+    ) -> Self {
+        Self {
+            status,
+            headers,
+            body: Box::new(body),
+            deadline: None,
+            operation_deadline: None,
+        }
+    }
+
+    pub(crate) fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Whether the status is `2xx`
+    pub(crate) fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// First value of the header `name`
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every value of the header `name`
+    pub(crate) fn header_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.headers
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Deserialize the response body as json
+    pub(crate) fn json(&mut self) -> Result<serde_json::Value> {
+        Ok(serde_json::from_reader(self)?)
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl io::Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "request exceeded its configured timeout",
+                ));
+            }
+        }
+        if let Some(deadline) = self.operation_deadline {
+            deadline
+                .check()
+                .map_err(|e| io::Error::new(io::ErrorKind::TimedOut, e))?;
+        }
+        self.body.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_until_capped() {
+        let config = HttpConfig {
+            retry_backoff: Duration::from_secs(1),
+            ..HttpConfig::default()
+        };
+        assert_eq!(Duration::from_secs(1), config.backoff(0));
+        assert_eq!(Duration::from_secs(2), config.backoff(1));
+        assert_eq!(Duration::from_secs(16), config.backoff(4));
+        assert_eq!(MAX_RETRY_BACKOFF, config.backoff(5));
+        assert_eq!(MAX_RETRY_BACKOFF, config.backoff(40));
+    }
+
+    #[test]
+    fn read_timeout_defaults() {
+        let mut config = HttpConfig::default();
+        assert_eq!(Some(DEFAULT_READ_TIMEOUT), config.read_timeout());
+        config.timeout = Some(Duration::from_secs(10));
+        assert_eq!(Some(Duration::from_secs(10)), config.read_timeout());
+    }
+
+    #[test]
+    fn rate_limit_delays() {
+        let mut config = HttpConfig::default();
+        let reset_at = unix_now() + 10;
+        assert_eq!(None, config.rate_limit_delay(Some(reset_at)));
+        config.rate_limit_wait = Some(Duration::from_secs(60));
+        let delay = config.rate_limit_delay(Some(reset_at)).unwrap();
+        assert!(delay > Duration::from_secs(9) && delay <= Duration::from_secs(11));
+        assert_eq!(
+            Some(Duration::from_secs(1)),
+            config.rate_limit_delay(Some(0))
+        );
+        assert_eq!(None, config.rate_limit_delay(Some(reset_at + 3600)));
+        assert_eq!(None, config.rate_limit_delay(None));
+    }
+
+    #[test]
+    fn past_deadline() {
+        let config = HttpConfig {
+            deadline: Some(Deadline::after(Duration::from_secs(0))),
+            rate_limit_wait: Some(Duration::from_secs(60)),
+            ..HttpConfig::default()
+        };
+        match config.get("http://127.0.0.1:1/unreachable") {
+            Err(Error::TimedOut(timeout)) => assert_eq!(Duration::from_secs(0), timeout),
+            r => panic!("expected TimedOut, got {:?}", r),
+        }
+        assert_eq!(None, config.rate_limit_delay(Some(unix_now())));
+        assert!(Deadline::after(Duration::from_secs(60)).check().is_ok());
+    }
+
+    #[test]
+    fn client_shared_by_clones() {
+        let mut config = HttpConfig::default();
+        assert!(config.client.is_none());
+        config.init_client().unwrap();
+        assert!(config.clone().client.is_some());
+
+        config.proxy = Some("ftp://proxy:21".into());
+        assert!(config.init_client().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+/*!
+Requests made with `reqwest`
+*/
+use crate::errors::*;
+
+use super::{HttpConfig, Response};
+
+pub(super) type Client = reqwest::Client;
+
+pub(super) fn build_client(config: &HttpConfig) -> Result<Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(connect) = config.connect_timeout {
+        builder = builder.connect_timeout(connect);
+    }
+    // reqwest applies its timeout to waiting on the response and to each
+    // individual read of the body, which is what we call the read timeout.
+    if let Some(read) = config.read_timeout() {
+        builder = builder.timeout(read);
+    }
+    if let Some(ref proxy) = config.proxy {
+        let proxy = reqwest::Proxy::all(proxy.as_str())
+            .map_err(|e| format_err!(Error::Config, "Invalid proxy {:?}: {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+    builder = add_certificates(config, builder)?;
+    Ok(builder.build()?)
+}
+
+#[cfg(any(feature = "default-tls", feature = "rustls-tls"))]
+fn add_certificates(
+    config: &HttpConfig,
+    mut builder: reqwest::ClientBuilder,
+) -> Result<reqwest::ClientBuilder> {
+    use super::certs;
+
+    #[cfg(feature = "rustls-tls")]
+    {
+        builder = builder.use_rustls_tls();
+    }
+    if config.system_certificates {
+        // best effort, TLS falls back to the built-in default locations
+        let certs =
+            certs::system_ca_bundle().and_then(|bundle| certs::read_certificates(&bundle).ok());
+        for cert in certs.unwrap_or_default() {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    for path in &config.root_certificates {
+        for cert in certs::read_certificates(path)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    Ok(builder)
+}
+
+#[cfg(not(any(feature = "default-tls", feature = "rustls-tls")))]
+fn add_certificates(
+    config: &HttpConfig,
+    builder: reqwest::ClientBuilder,
+) -> Result<reqwest::ClientBuilder> {
+    if !config.root_certificates.is_empty() {
+        bail!(
+            Error::Config,
+            "`root_certificates` requires the `default-tls` or `rustls-tls` feature of self_update"
+        );
+    }
+    Ok(builder)
+}
+
+/// Send a single GET request
+pub(super) fn send(client: &Client, url: &str, headers: &[(&str, &str)]) -> Result<Response> {
+    let mut request = client.get(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let resp = request.send()?;
+    let headers = resp
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+        .collect();
+    Ok(Response::new(resp.status().as_u16(), headers, resp))
+}
+
+/// Whether a failed request is worth retrying
+pub(super) fn is_transient(err: &Error) -> bool {
+    match *err {
+        Error::Network(ref e) => e
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(|e| e.is_timeout() || e.is_http()),
+        _ => false,
+    }
+}
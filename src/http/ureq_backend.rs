@@ -0,0 +1,68 @@
+/*!
+Requests made with `ureq`
+*/
+use crate::errors::*;
+
+use super::{HttpConfig, Response};
+
+pub(super) type Client = ureq::Agent;
+
+pub(super) fn build_client(config: &HttpConfig) -> Result<Client> {
+    if !config.root_certificates.is_empty() {
+        bail!(
+            Error::Config,
+            "`root_certificates` requires the `default-tls` or `rustls-tls` feature of self_update"
+        );
+    }
+    let mut builder = ureq::AgentBuilder::new();
+    if let Some(connect) = config.connect_timeout {
+        builder = builder.timeout_connect(connect);
+    }
+    if let Some(read) = config.read_timeout() {
+        builder = builder.timeout_read(read);
+    }
+    if let Some(ref proxy) = config.proxy {
+        let proxy = ureq::Proxy::new(proxy)
+            .map_err(|e| format_err!(Error::Config, "Invalid proxy {:?}: {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build())
+}
+
+/// Send a single GET request
+pub(super) fn send(client: &Client, url: &str, headers: &[(&str, &str)]) -> Result<Response> {
+    let mut request = client.get(url);
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    // unsuccessful statuses are reported by the caller like with any other client
+    let resp = match request.call() {
+        Ok(resp) | Err(ureq::Error::Status(_, resp)) => resp,
+        Err(ureq::Error::Transport(e)) => return Err(Error::from(e)),
+    };
+    let mut names = resp.headers_names();
+    names.sort();
+    names.dedup();
+    let headers = names
+        .iter()
+        .flat_map(|name| {
+            resp.all(name)
+                .into_iter()
+                .map(move |value| (name.clone(), value.to_owned()))
+        })
+        .collect::<Vec<_>>();
+    Ok(Response::new(resp.status(), headers, resp.into_reader()))
+}
+
+/// Whether a failed request is worth retrying
+pub(super) fn is_transient(err: &Error) -> bool {
+    match *err {
+        Error::Network(ref e) => e.downcast_ref::<ureq::Transport>().is_some_and(|e| {
+            matches!(
+                e.kind(),
+                ureq::ErrorKind::Io | ureq::ErrorKind::ConnectionFailed
+            )
+        }),
+        _ => false,
+    }
+}
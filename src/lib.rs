@@ -1,4 +1,4 @@
-#![cfg_attr(feature = "cargo-clippy", deny(clippy::all))]
+#![deny(clippy::all)]
 /*!
 
 [![Build status](https://ci.appveyor.com/api/projects/status/xlkq8rd73cla4ixw/branch/master?svg=true)](https://ci.appveyor.com/project/jaemk/self-update/branch/master)
@@ -14,6 +14,51 @@ distribution backends.
 self_update = "0.5"
 ```
 
+## Features
+
+Archive formats can be disabled to trim dependencies, all but zstd and xz are enabled
+by default:
+
+* `archive-tar` - `.tar` archives
+* `archive-zip` - `.zip` archives
+* `compression-flate2` - gzip compressed archives and executables (`.tar.gz`, `.gz`)
+* `compression-zstd` - zstd compressed archives and executables (`.tar.zst`, `.zst`)
+* `compression-xz` - xz compressed archives and executables (`.tar.xz`, `.xz`), decoded
+  on all cores
+
+The TLS backend of `reqwest` is picked the same way:
+
+* `default-tls` - the platform native TLS library (enabled by default)
+* `rustls-tls` - `rustls`, with no dependency on openssl
+
+```toml
+self_update = { version = "0.6", default-features = false, features = ["rustls-tls", "archive-zip"] }
+```
+
+For the smallest binaries, requests can be made with the synchronous `ureq` client
+instead of `reqwest` and its async runtime. `ureq` is used when the `ureq` feature is
+enabled and neither TLS feature above is, custom `root_certificates` aren't supported.
+
+```toml
+self_update = { version = "0.6", default-features = false, features = ["ureq", "archive-tar", "compression-flate2"] }
+```
+
+The `checksum-blake3` feature adds BLAKE3 to the SHA-256 and SHA-512 checksums
+release assets can be verified with, see `checksum`.
+
+The `mock` feature adds `backends::mock`, serving canned releases from memory to test
+update flows without network access. The `test-server` feature adds
+`test_server::ReleaseServer`, a local http server emulating the GitHub release api
+from a directory of fixtures.
+
+The `clap` feature adds `cli`, a ready-made `self-update` subcommand, and the
+`self_update` binary installing and updating any executable released on GitHub:
+
+```shell
+cargo install self_update --features clap
+self_update install BurntSushi/ripgrep --bin rg
+```
+
 ## Usage
 
 Update (replace) the current executable with the latest release downloaded
@@ -25,7 +70,7 @@ producing release-builds via CI (travis/appveyor).
 ```
 #[macro_use] extern crate self_update;
 
-fn update() -> Result<(), Box<::std::error::Error>> {
+fn update() -> Result<(), Box<dyn ::std::error::Error>> {
     let status = self_update::backends::github::Update::configure()
         .repo_owner("jaemk")
         .repo_name("self_update")
@@ -47,7 +92,7 @@ Separate utilities are also exposed:
 ```
 extern crate self_update;
 
-fn update() -> Result<(), Box<::std::error::Error>> {
+fn update() -> Result<(), Box<dyn ::std::error::Error>> {
     let releases = self_update::backends::github::ReleaseList::configure()
         .repo_owner("jaemk")
         .repo_name("self_update")
@@ -87,28 +132,269 @@ fn update() -> Result<(), Box<::std::error::Error>> {
 
 pub use tempdir::TempDir;
 
-use either::Either;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::cmp::min;
+use std::env;
 use std::fs;
 use std::io;
 use std::path;
+use std::process;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[macro_use]
 mod macros;
+mod api_cache;
+mod attributes;
 pub mod backends;
+mod backup;
+pub mod blocks;
+pub mod check;
+pub mod checksum;
+#[cfg(feature = "clap")]
+pub mod cli;
+pub mod downloader;
 pub mod errors;
+pub mod extractor;
+pub mod fleet;
+mod http;
+mod lock;
+pub mod messages;
+mod overrides;
+pub mod package;
+pub mod package_manager;
+pub mod policy;
+pub mod progress;
+pub mod schedule;
+pub mod service;
+pub mod telemetry;
+#[cfg(any(test, feature = "test-server"))]
+pub mod test_server;
 pub mod version;
 
 use errors::*;
+use http::HttpConfig;
 
 /// Get the current target triple.
 ///
-/// Returns a target triple (e.g. `x86_64-unknown-linux-gnu` or `i686-pc-windows-msvc`)
+/// Returns a target triple (e.g. `x86_64-unknown-linux-gnu` or `i686-pc-windows-msvc`).
+/// The triple is the one this crate was compiled for, so every target supported by
+/// rustc is reported, including `aarch64-unknown-linux-gnu`, `aarch64-apple-darwin`,
+/// `aarch64-pc-windows-msvc`, `riscv64gc-unknown-linux-gnu`, `powerpc64le-unknown-linux-gnu`
+/// and the BSDs (`x86_64-unknown-freebsd`, `x86_64-unknown-netbsd`, `x86_64-unknown-openbsd`).
 pub fn get_target() -> &'static str {
     env!("TARGET")
 }
 
+/// Get the targets whose release assets can run on this machine, most preferred first.
+///
+/// This is `get_target()`, except on macOS where the hardware is detected at runtime
+/// (also when running under Rosetta 2): Apple Silicon prefers native `aarch64-apple-darwin`
+/// assets, then universal binaries, then `x86_64-apple-darwin` assets run through Rosetta.
+/// On Android (e.g. Termux), statically linked linux musl assets are accepted as well.
+/// On 32-bit ARM linux the CPU's architecture version is read from `/proc/cpuinfo`, so
+/// ARMv7 CPUs prefer `armv7` assets and ARMv6 CPUs (e.g. Raspberry Pi 1 / Zero) never
+/// get `armv7` assets they can't run. On Windows on ARM, x86 / x64 executables run
+/// through emulation prefer native `aarch64-pc-windows-msvc` assets. RISC-V and POWER
+/// assets are also matched by their short names, e.g. `riscv64-unknown-linux-gnu` and
+/// `ppc64le-unknown-linux-gnu`.
+pub fn get_target_candidates() -> Vec<String> {
+    let target = get_target();
+    if target.ends_with("-apple-darwin") {
+        darwin_target_candidates(is_apple_silicon())
+    } else if target.contains("-linux-android") {
+        android_target_candidates(target)
+    } else if target.contains("-pc-windows-") {
+        windows_target_candidates(target, is_windows_arm64())
+    } else if target.starts_with("arm") && target.contains("-linux-") {
+        arm_target_candidates(target, arm_cpu_architecture())
+    } else {
+        with_arch_aliases(target)
+    }
+}
+
+/// `target` followed by the same target under common alternative architecture names,
+/// e.g. `riscv64-unknown-linux-gnu` for `riscv64gc-unknown-linux-gnu`
+fn with_arch_aliases(target: &str) -> Vec<String> {
+    let (arch, rest) = target.split_once('-').unwrap_or((target, ""));
+    let aliases: &[&str] = match arch {
+        "riscv64gc" => &["riscv64"],
+        "powerpc64le" => &["ppc64le"],
+        "powerpc64" => &["ppc64"],
+        _ => &[],
+    };
+    let mut candidates = vec![target.to_owned()];
+    candidates.extend(aliases.iter().map(|alias| format!("{}-{}", alias, rest)));
+    candidates
+}
+
+/// Candidates for a 32-bit ARM linux `target` on a CPU implementing ARM
+/// architecture version `cpu_arch`, if known
+fn arm_target_candidates(target: &str, cpu_arch: Option<u32>) -> Vec<String> {
+    let cpu_arch = match cpu_arch {
+        Some(cpu_arch) => cpu_arch,
+        None => return vec![target.to_owned()],
+    };
+    let abi = target.split_once('-').map_or("", |(_, abi)| abi);
+    let mut candidates = vec![];
+    // rust's `armv7-*` targets use hard-float, `arm-*` targets are ARMv6
+    if cpu_arch >= 7 && abi.ends_with("hf") {
+        candidates.push(format!("armv7-{}", abi));
+    }
+    if cpu_arch >= 6 {
+        candidates.push(format!("arm-{}", abi));
+    }
+    if !candidates.iter().any(|c| c == target) {
+        candidates.push(target.to_owned());
+    }
+    candidates
+}
+
+/// The "CPU architecture" reported by `/proc/cpuinfo`
+fn arm_cpu_architecture() -> Option<u32> {
+    let cpuinfo = fs::read_to_string("/proc/cpuinfo").ok()?;
+    parse_cpu_architecture(&cpuinfo)
+}
+
+fn parse_cpu_architecture(cpuinfo: &str) -> Option<u32> {
+    // ARM11 cores (Raspberry Pi 1 / Zero) report "CPU architecture: 7" but are ARMv6
+    if cpuinfo.contains("ARMv6") || cpuinfo.contains("(v6l)") {
+        return Some(6);
+    }
+    cpuinfo
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim() == "CPU architecture")
+        .and_then(|(_, value)| {
+            let digits = value
+                .trim()
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>();
+            digits.parse().ok()
+        })
+}
+
+/// Check if running inside the Termux terminal emulator on Android
+pub fn is_termux() -> bool {
+    env::var_os("TERMUX_VERSION").is_some()
+        || env::var("PREFIX").is_ok_and(|prefix| prefix.contains("/com.termux/"))
+}
+
+fn android_target_candidates(target: &str) -> Vec<String> {
+    let musl = match target.split('-').next() {
+        Some("aarch64") => "aarch64-unknown-linux-musl",
+        Some("armv7") => "armv7-unknown-linux-musleabihf",
+        Some("arm") => "arm-unknown-linux-musleabi",
+        Some("i686") => "i686-unknown-linux-musl",
+        Some("x86_64") => "x86_64-unknown-linux-musl",
+        _ => return vec![target.to_owned()],
+    };
+    vec![target.to_owned(), musl.to_owned()]
+}
+
+/// The statically linked musl equivalent of a glibc linux `target`, which runs on
+/// glibc systems too, e.g. `x86_64-unknown-linux-musl` for `x86_64-unknown-linux-gnu`
+fn musl_equivalent(target: &str) -> Option<String> {
+    let idx = target.rfind("-linux-gnu")?;
+    let abi = &target[idx + "-linux-gnu".len()..];
+    Some(format!("{}-linux-musl{}", &target[..idx], abi))
+}
+
+fn darwin_target_candidates(apple_silicon: bool) -> Vec<String> {
+    let mut candidates = vec![];
+    if apple_silicon {
+        candidates.push("aarch64-apple-darwin");
+    }
+    candidates.extend(&[
+        "universal-apple-darwin",
+        "universal2-apple-darwin",
+        "x86_64-apple-darwin",
+    ]);
+    candidates.into_iter().map(String::from).collect()
+}
+
+fn windows_target_candidates(target: &str, arm64: bool) -> Vec<String> {
+    let native = if target.ends_with("-gnullvm") {
+        "aarch64-pc-windows-gnullvm"
+    } else {
+        "aarch64-pc-windows-msvc"
+    };
+    let mut candidates = vec![];
+    if arm64 && target != native {
+        candidates.push(native.to_owned());
+    }
+    candidates.push(target.to_owned());
+    candidates
+}
+
+/// Check if running on Windows on ARM64 hardware, even when this process is emulated
+#[cfg(windows)]
+fn is_windows_arm64() -> bool {
+    const IMAGE_FILE_MACHINE_ARM64: u16 = 0xAA64;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentProcess() -> *mut std::ffi::c_void;
+        fn IsWow64Process2(
+            process: *mut std::ffi::c_void,
+            process_machine: *mut u16,
+            native_machine: *mut u16,
+        ) -> i32;
+    }
+    if cfg!(target_arch = "aarch64") {
+        return true;
+    }
+    let (mut process_machine, mut native_machine) = (0u16, 0u16);
+    let ok = unsafe {
+        IsWow64Process2(
+            GetCurrentProcess(),
+            &mut process_machine,
+            &mut native_machine,
+        )
+    };
+    ok != 0 && native_machine == IMAGE_FILE_MACHINE_ARM64
+}
+
+#[cfg(not(windows))]
+fn is_windows_arm64() -> bool {
+    false
+}
+
+/// Check if this process is an x86_64 executable translated by Rosetta 2 on Apple Silicon.
+///
+/// Updates then prefer native `aarch64-apple-darwin` assets, migrating the
+/// installation to the right architecture.
+pub fn is_rosetta_translated() -> bool {
+    cfg!(all(target_os = "macos", target_arch = "x86_64"))
+        && sysctl_flag("sysctl.proc_translated") == Some(true)
+}
+
+/// Check if running on Apple Silicon hardware, even when this process is translated by Rosetta 2
+fn is_apple_silicon() -> bool {
+    if !cfg!(target_os = "macos") {
+        return false;
+    }
+    is_rosetta_translated()
+        || sysctl_flag("hw.optional.arm64").unwrap_or(cfg!(target_arch = "aarch64"))
+}
+
+/// Read a boolean macOS kernel state value, `None` if it doesn't exist
+fn sysctl_flag(name: &str) -> Option<bool> {
+    let out = process::Command::new("sysctl")
+        .args(["-n", name])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&out.stdout).trim() {
+        "1" => Some(true),
+        "0" => Some(false),
+        _ => None,
+    }
+}
+
 /// Check if a version tag is greater than the current
 #[deprecated(
     since = "0.4.2",
@@ -120,24 +406,517 @@ pub fn should_update(current: &str, latest: &str) -> Result<bool> {
     Ok(Version::parse(latest)? > Version::parse(current)?)
 }
 
-/// Flush a message to stdout and check if they respond `yes`.
-/// Interprets a blank response as yes.
+/// Restart the running program using the executable at `exe`, typically the
+/// freshly installed `bin_install_path`, passing along the original arguments.
+/// The environment and working directory are inherited.
+///
+/// On unix the current process is replaced with the new executable (`exec`). On
+/// other platforms the new executable is spawned and waited on, after which the
+/// current process exits with its exit code.
+///
+/// Only returns if the new executable couldn't be started.
+///
+/// ```no_run
+/// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+/// let status = self_update::backends::github::Update::configure()
+///     // ...
+///     .build()?
+///     .update()?;
+/// if status.updated() {
+///     return Err(self_update::restart(::std::env::current_exe()?).into());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn restart<P: AsRef<path::Path>>(exe: P) -> Error {
+    let mut command = process::Command::new(exe.as_ref());
+    command.args(env::args_os().skip(1));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        Error::Io(command.exec())
+    }
+
+    #[cfg(not(unix))]
+    {
+        match command.status() {
+            Ok(status) => process::exit(status.code().unwrap_or(1)),
+            Err(e) => Error::Io(e),
+        }
+    }
+}
+
+/// Environment variable set for processes started by `restart_elevated`
+pub const ELEVATED_ENV_VAR: &str = "SELF_UPDATE_ELEVATED";
+
+/// Like `restart`, but the executable at `exe` is run with elevated privileges: through
+/// `sudo` (or `pkexec` if `sudo` isn't available) on unix, and a UAC prompt on Windows.
+/// `ELEVATED_ENV_VAR` is set for the new process to detect that it was restarted.
+///
+/// The current process waits for the elevated one and exits with its exit code.
+/// Only returns if the elevated executable couldn't be started.
+pub fn restart_elevated<P: AsRef<path::Path>>(exe: P) -> Error {
+    let exe = exe.as_ref();
+
+    #[cfg(unix)]
+    let mut command = {
+        let path_var = env::var_os("PATH").unwrap_or_default();
+        let elevator = ["sudo", "pkexec"]
+            .iter()
+            .find(|tool| env::split_paths(&path_var).any(|dir| dir.join(tool).is_file()));
+        let elevator = match elevator {
+            Some(elevator) => elevator,
+            None => return Error::Update("Neither `sudo` nor `pkexec` are available".into()),
+        };
+        // both reset the environment, so the marker is set through `env`
+        let mut command = process::Command::new(elevator);
+        command
+            .arg("env")
+            .arg(format!("{}=1", ELEVATED_ENV_VAR))
+            .arg(exe)
+            .args(env::args_os().skip(1));
+        command
+    };
+
+    #[cfg(not(unix))]
+    let mut command = {
+        fn quote(arg: &std::ffi::OsStr) -> String {
+            format!("'{}'", arg.to_string_lossy().replace('\'', "''"))
+        }
+        let args = env::args_os()
+            .skip(1)
+            .map(|arg| quote(&arg))
+            .collect::<Vec<_>>();
+        let mut script = format!(
+            "$env:{}='1'; $p = Start-Process -FilePath {} -Verb RunAs -Wait -PassThru",
+            ELEVATED_ENV_VAR,
+            quote(exe.as_os_str())
+        );
+        if !args.is_empty() {
+            script.push_str(&format!(" -ArgumentList {}", args.join(",")));
+        }
+        script.push_str("; exit $p.ExitCode");
+        let mut command = process::Command::new("powershell");
+        command
+            .args(["-NoProfile", "-NonInteractive", "-Command"])
+            .arg(script);
+        command
+    };
+
+    match command.status() {
+        Ok(status) => process::exit(status.code().unwrap_or(1)),
+        Err(e) => Error::Io(e),
+    }
+}
+
+/// Check that files can be created in `dir`, which doesn't need to exist yet
+///
+/// * Errors:
+///     * PermissionDenied - `dir` isn't writable
+fn check_writable(dir: &path::Path) -> Result<()> {
+    match TempDir::new_in(dir, ".self_update_write_check") {
+        Ok(_) => Ok(()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => Err(Error::PermissionDenied {
+            path: dir.to_path_buf(),
+            source: e,
+        }),
+        Err(e) => Err(Error::Io(e)),
+    }
+}
+
+/// Bytes available to this process on the filesystem containing `path`, which
+/// doesn't need to exist yet. `None` if it can't be determined.
+#[cfg(unix)]
+fn available_space(path: &path::Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let c_path = std::ffi::CString::new(existing.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some((stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64))
+}
+
+/// Bytes available to this process on the volume containing `path`, which
+/// doesn't need to exist yet. `None` if it can't be determined.
+#[cfg(windows)]
+fn available_space(path: &path::Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory: *const u16,
+            available: *mut u64,
+            total: *mut u64,
+            free: *mut u64,
+        ) -> i32;
+    }
+
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let wide = existing
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+    let mut available = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    Some(available).filter(|_| ok != 0)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn available_space(_path: &path::Path) -> Option<u64> {
+    None
+}
+
+/// Check that at least `required` bytes are available in `dir`. Passes if the
+/// available space can't be determined.
+///
+/// * Errors:
+///     * InsufficientSpace - Less than `required` bytes are available
+fn check_space(dir: &path::Path, required: u64) -> Result<()> {
+    match available_space(dir) {
+        Some(available) if available < required => Err(Error::InsufficientSpace {
+            path: dir.to_path_buf(),
+            required,
+            available,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Rough size of the executable extracted from an asset of kind `archive` and
+/// `size` bytes, assuming compressed executables shrink to a third of their size
+fn estimated_extracted_size(archive: ArchiveKind, size: u64) -> u64 {
+    match archive {
+        ArchiveKind::Plain(None) | ArchiveKind::Tar(None) => size,
+        _ => size.saturating_mul(3),
+    }
+}
+
+/// Whether `path` is the executable of the running process
+fn is_current_exe(path: &path::Path) -> bool {
+    match (
+        env::current_exe().and_then(fs::canonicalize),
+        fs::canonicalize(path),
+    ) {
+        (Ok(current), Ok(path)) => current == path,
+        _ => false,
+    }
+}
+
+/// Run `program` with `args` and return its stdout, failing with its output if it
+/// exits unsuccessfully
+pub(crate) fn run_command(program: &str, args: &[&str]) -> Result<String> {
+    let output = process::Command::new(program).args(args).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        // some tools, like `net` and `sc`, report errors on stdout
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = if stderr.trim().is_empty() {
+            stdout.trim()
+        } else {
+            stderr.trim()
+        };
+        bail!(
+            Error::Update,
+            "`{} {}` failed: {}",
+            program,
+            args.join(" "),
+            message
+        );
+    }
+    Ok(stdout)
+}
+
+/// Whether another process is running the executable at `path`, as far as can be
+/// told from the processes visible in `/proc`
+#[cfg(target_os = "linux")]
+fn is_executing(path: &path::Path) -> bool {
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(_) => return false,
+    };
+    let own = process::id().to_string();
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries.filter_map(|entry| entry.ok()).any(|entry| {
+        let name = entry.file_name();
+        let pid = name.to_string_lossy();
+        pid.chars().all(|c| c.is_ascii_digit())
+            && pid != own
+            && fs::read_link(entry.path().join("exe")).is_ok_and(|exe| exe == path)
+    })
+}
+
+/// Whether a process is running the executable at `path`: Windows doesn't allow
+/// opening a running executable for writing
+#[cfg(windows)]
+fn is_executing(path: &path::Path) -> bool {
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    match fs::OpenOptions::new().write(true).open(path) {
+        Err(e) => e.raw_os_error() == Some(ERROR_SHARING_VIOLATION),
+        Ok(_) => false,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn is_executing(_path: &path::Path) -> bool {
+    false
+}
+
+/// Identity of an executable file, unchanged when it's moved or replaced: its
+/// device and inode numbers
+type ExeId = (u64, u64);
+
+/// Identity of the executable file at `path`, if it can be told processes running
+/// it apart, see `processes_running`
+#[cfg(target_os = "linux")]
+fn exe_id(path: &path::Path) -> Option<ExeId> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|meta| (meta.dev(), meta.ino()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn exe_id(_path: &path::Path) -> Option<ExeId> {
+    None
+}
+
+/// Ids of the processes visible in `/proc`, this one included, running the
+/// executable file `id`, even once it's been replaced or deleted
+#[cfg(target_os = "linux")]
+fn processes_running(id: ExeId) -> Vec<u32> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let running = exe_id(&entry.path().join("exe"))?;
+            Some(pid).filter(|_| running == id)
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn processes_running(_id: ExeId) -> Vec<u32> {
+    vec![]
+}
+
+/// Path the replaced executable `exe` is moved aside to: `<exe>.old`
+fn old_exe_path(exe: &path::Path) -> path::PathBuf {
+    let mut name = exe
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_default();
+    name.push(".old");
+    exe.with_file_name(name)
+}
+
+/// Remove the `<exe>.old` file left next to `exe` when it was replaced while running.
+///
+/// A running executable can't be overwritten or deleted on Windows, only renamed, so
+/// updates move it aside to `<exe>.old` and it can only be removed once it has exited.
+/// Call this early on startup, e.g. with `env::current_exe()`. Updating also does this.
+/// A missing `.old` file is not an error.
+pub fn cleanup_old_exe<P: AsRef<path::Path>>(exe: P) -> Result<()> {
+    match fs::remove_file(old_exe_path(exe.as_ref())) {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        r => Ok(r?),
+    }
+}
+
+/// Ask Windows to delete `path` on the next reboot. This requires administrator
+/// privileges; otherwise the file is left for `cleanup_old_exe`.
+#[cfg(windows)]
+fn delete_on_reboot(path: &path::Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt;
+
+    const MOVEFILE_DELAY_UNTIL_REBOOT: u32 = 0x4;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn MoveFileExW(existing: *const u16, new: *const u16, flags: u32) -> i32;
+    }
+
+    let wide = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+    let ok = unsafe { MoveFileExW(wide.as_ptr(), std::ptr::null(), MOVEFILE_DELAY_UNTIL_REBOOT) };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn delete_on_reboot(_path: &path::Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// How to handle a confirmation prompt when there's no terminal to ask on, e.g.
+/// when run from cron, CI, or with its output piped
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonInteractive {
+    /// Continue as if the user had confirmed
+    Proceed,
+    /// Fail with `Error::NonInteractive` instead of waiting for an answer
+    #[default]
+    Abort,
+}
+
+/// Whether stdin and stdout are both terminals, so prompts can be seen and answered
+fn is_interactive() -> bool {
+    use std::io::IsTerminal;
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Whether a progress bar can be drawn, i.e. stdout and stderr are terminals
+/// rather than pipes or log files
+fn can_show_progress() -> bool {
+    use std::io::IsTerminal;
+    io::stdout().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Time to wait for an answer to a prompt, and what to do once it's elapsed, see
+/// `UpdateBuilder::prompt_timeout`
+type PromptTimeout = Option<(Duration, NonInteractive)>;
+
+/// Flush a message to stdout and check if they respond with one of the `yes` answers.
+/// Interprets a blank response as yes. Without a terminal, `non_interactive` decides
+/// instead of reading stdin, and without an answer in time, the `timeout` default.
 ///
 /// * Errors:
 ///     * Io flushing
-///     * User entered anything other than enter/Y/y
-fn confirm(msg: &str) -> Result<()> {
+///     * Aborted - User entered anything other than enter or one of the `yes` answers,
+///       or didn't answer in time and the `timeout` default is `Abort`
+///     * NonInteractive - No terminal to ask on and `non_interactive` is `Abort`
+fn confirm(
+    msg: &str,
+    yes: &[String],
+    non_interactive: NonInteractive,
+    timeout: PromptTimeout,
+) -> Result<()> {
+    if !is_interactive() {
+        return match non_interactive {
+            NonInteractive::Proceed => Ok(()),
+            NonInteractive::Abort => Err(Error::NonInteractive),
+        };
+    }
     print_flush!("{}", msg);
 
-    let mut s = String::new();
-    io::stdin().read_line(&mut s)?;
+    let s = match read_answer(timeout)? {
+        Some(s) => s,
+        None => return unanswered(timeout).map(drop),
+    };
     let s = s.trim().to_lowercase();
-    if !s.is_empty() && s != "y" {
-        bail!(Error::Update, "Update aborted");
+    if !s.is_empty() && !yes.iter().any(|answer| answer.to_lowercase() == s) {
+        return Err(Error::Aborted);
     }
     Ok(())
 }
 
+/// Print the numbered `choices` and a prompt, and return the index of the one the
+/// user picks by number. A blank response picks the first. Without a terminal,
+/// `non_interactive` decides instead of reading stdin, and without an answer in time,
+/// the `timeout` default, `Proceed` picking the first.
+///
+/// * Errors:
+///     * Io flushing
+///     * Aborted - User entered anything other than enter or the number of a choice,
+///       or didn't answer in time and the `timeout` default is `Abort`
+///     * NonInteractive - No terminal to ask on and `non_interactive` is `Abort`
+fn choose(
+    msg: &str,
+    choices: &[String],
+    non_interactive: NonInteractive,
+    timeout: PromptTimeout,
+) -> Result<usize> {
+    if !is_interactive() {
+        return match non_interactive {
+            NonInteractive::Proceed => Ok(0),
+            NonInteractive::Abort => Err(Error::NonInteractive),
+        };
+    }
+    for choice in choices {
+        println!("{}", choice);
+    }
+    print_flush!("{}", msg);
+
+    match read_answer(timeout)? {
+        Some(s) => parse_choice(&s, choices.len()).ok_or(Error::Aborted),
+        None => unanswered(timeout).map(|_| 0),
+    }
+}
+
+/// A line of stdin, `None` if it isn't entered within the `timeout`
+fn read_answer(timeout: PromptTimeout) -> Result<Option<String>> {
+    let read_line = || {
+        let mut s = String::new();
+        io::stdin().read_line(&mut s).map(|_| s)
+    };
+    match timeout {
+        Some((timeout, _)) => read_line_within(timeout, read_line),
+        None => Ok(Some(read_line()?)),
+    }
+}
+
+/// Run `read_line` on another thread and wait for it up to `timeout`, returning
+/// `None` once it elapses. A thread waiting on stdin can't be interrupted, it's left
+/// to finish whenever a line is entered, or the process exits.
+fn read_line_within<F>(timeout: Duration, read_line: F) -> Result<Option<String>>
+where
+    F: FnOnce() -> io::Result<String> + Send + 'static,
+{
+    let (tx, rx) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let _ = tx.send(read_line());
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(line) => Ok(Some(line?)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// The outcome of a prompt left unanswered until its `timeout`
+fn unanswered(timeout: PromptTimeout) -> Result<()> {
+    // end the prompt's line
+    println!();
+    match timeout {
+        Some((_, NonInteractive::Proceed)) => Ok(()),
+        _ => Err(Error::Aborted),
+    }
+}
+
+/// The index of the choice numbered `answer`, from 1 to `count`, blank for the first
+fn parse_choice(answer: &str, count: usize) -> Option<usize> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Some(0);
+    }
+    match answer.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= count => Some(n - 1),
+        _ => None,
+    }
+}
+
 /// Status returned after updating
 ///
 /// Wrapped `String`s are version tags
@@ -158,18 +937,12 @@ impl Status {
 
     /// Returns `true` if `Status::UpToDate`
     pub fn uptodate(&self) -> bool {
-        match *self {
-            Status::UpToDate(_) => true,
-            _ => false,
-        }
+        matches!(*self, Status::UpToDate(_))
     }
 
     /// Returns `true` if `Status::Updated`
     pub fn updated(&self) -> bool {
-        match *self {
-            Status::Updated(_) => true,
-            _ => false,
-        }
+        matches!(*self, Status::Updated(_))
     }
 }
 
@@ -183,6 +956,121 @@ impl std::fmt::Display for Status {
     }
 }
 
+/// A user supplied callback. Wrapped so that the builders holding one can still
+/// derive `Debug` and `Clone`.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Callback(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> std::fmt::Debug for Callback<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Callback")
+    }
+}
+
+/// Make sure the extracted file at `path` can be executed. Archive formats without
+/// unix permissions (zip, plain files) otherwise produce non-executable files.
+#[cfg(unix)]
+fn set_executable(path: &path::Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    if permissions.mode() & 0o111 == 0 {
+        permissions.set_mode(permissions.mode() | 0o755);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &path::Path) -> Result<()> {
+    Ok(())
+}
+
+/// `path` made absolute and `\\?\` prefixed on Windows, lifting the `MAX_PATH`
+/// limit of 260 characters for archives with deep directory trees. Prefixed paths
+/// aren't normalized by Windows, which `path::absolute` does beforehand.
+#[cfg(windows)]
+fn long_path(path: &path::Path) -> path::PathBuf {
+    use std::path::{Component, Prefix};
+
+    let path = match path::absolute(path) {
+        Ok(path) => path,
+        Err(_) => return path.to_path_buf(),
+    };
+    let mut components = path.components();
+    let mut long = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut long = std::ffi::OsString::from(r"\\?\");
+                long.push(prefix.as_os_str());
+                path::PathBuf::from(long)
+            }
+            Prefix::UNC(server, share) => {
+                let mut long = std::ffi::OsString::from(r"\\?\UNC\");
+                long.push(server);
+                long.push("\\");
+                long.push(share);
+                path::PathBuf::from(long)
+            }
+            // already verbatim, or a device path
+            _ => return path,
+        },
+        _ => return path,
+    };
+    for component in components {
+        long.push(component);
+    }
+    long
+}
+
+#[cfg(not(windows))]
+fn long_path(path: &path::Path) -> path::PathBuf {
+    path.to_path_buf()
+}
+
+/// `dir` joined with the path of an archive `entry`, one component at a time since
+/// `/` isn't a separator in `long_path`s
+#[cfg(feature = "archive-zip")]
+fn entry_path(dir: &path::Path, entry: &str) -> path::PathBuf {
+    let mut path = dir.to_path_buf();
+    for component in path::Path::new(entry).components() {
+        path.push(component);
+    }
+    path
+}
+
+/// The file `path` points to if it's a symlink, otherwise `path` itself
+///
+/// * Errors:
+///     * Io - `path` is a dangling symlink
+fn resolve_symlink(path: &path::Path) -> Result<path::PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(ref meta) if meta.file_type().is_symlink() => Ok(fs::canonicalize(path)?),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+/// Apply the bsdiff `patch` to the file `old`, writing the patched file to `new`
+///
+/// * Errors:
+///     * Io - reading `old` / writing `new`, or a malformed patch
+fn apply_patch(old: &path::Path, patch: &[u8], new: &path::Path) -> Result<()> {
+    let old = fs::read(old)?;
+    let mut patched = vec![];
+    bsdiff::patch(&old, &mut &patch[..], &mut patched)?;
+    fs::write(new, patched)?;
+    Ok(())
+}
+
+/// Check whether `dir` is one of the directories listed in a `PATH`-style `paths` value
+fn is_in_path(dir: &path::Path, paths: &std::ffi::OsStr) -> bool {
+    env::split_paths(paths).any(|p| p == dir)
+}
+
 /// Supported archive formats
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ArchiveKind {
@@ -192,29 +1080,60 @@ pub enum ArchiveKind {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum Compression {
     Gz,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// Whether decompressing this format is enabled by its cargo feature
+    pub fn is_supported(self) -> bool {
+        match self {
+            Compression::Gz => cfg!(feature = "compression-flate2"),
+            Compression::Zstd => cfg!(feature = "compression-zstd"),
+            Compression::Xz => cfg!(feature = "compression-xz"),
+        }
+    }
 }
 
 fn detect_archive(path: &path::Path) -> ArchiveKind {
-    match path.extension() {
-        Some(extension) if extension == std::ffi::OsStr::new("zip") => ArchiveKind::Zip,
-        Some(extension) if extension == std::ffi::OsStr::new("tar") => ArchiveKind::Tar(None),
-        Some(extension) if extension == std::ffi::OsStr::new("gz") => match path
-            .file_stem()
-            .map(|e| path::Path::new(e))
-            .and_then(|f| f.extension())
-        {
-            Some(extension) if extension == std::ffi::OsStr::new("tar") => {
-                ArchiveKind::Tar(Some(Compression::Gz))
-            }
-            _ => ArchiveKind::Plain(Some(Compression::Gz)),
-        },
-        _ => ArchiveKind::Plain(None),
+    let compression = match path.extension().and_then(|e| e.to_str()) {
+        Some("zip") => return ArchiveKind::Zip,
+        Some("tar") => return ArchiveKind::Tar(None),
+        Some("gz") => Compression::Gz,
+        Some("zst") => Compression::Zstd,
+        Some("xz") => Compression::Xz,
+        _ => return ArchiveKind::Plain(None),
+    };
+    match path
+        .file_stem()
+        .map(path::Path::new)
+        .and_then(|f| f.extension())
+    {
+        Some(extension) if extension == std::ffi::OsStr::new("tar") => {
+            ArchiveKind::Tar(Some(compression))
+        }
+        _ => ArchiveKind::Plain(Some(compression)),
     }
 }
 
-/// Extract contents of an encoded archive (e.g. tar.gz) file to a specified directory
+/// Extract contents of an encoded archive (e.g. tar.gz) file to a specified directory,
+/// e.g. to install a downloaded release with custom logic
+///
+/// ```no_run
+/// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+/// use self_update::{ArchiveKind, Compression, Extract};
+/// use std::path::Path;
+///
+/// Extract::from_source(Path::new("app-1.2.0.tar.gz"))
+///     .archive(ArchiveKind::Tar(Some(Compression::Gz)))
+///     .file_to_extract(Path::new("app"))
+///     .extract_into(Path::new("bin"))?;
+/// # Ok(())
+/// # }
+/// ```
 ///
 /// * Errors:
 ///     * Io - opening files
@@ -224,6 +1143,7 @@ fn detect_archive(path: &path::Path) -> ArchiveKind {
 pub struct Extract<'a> {
     source: &'a path::Path,
     archive: Option<ArchiveKind>,
+    file_to_extract: Option<&'a path::Path>,
 }
 impl<'a> Extract<'a> {
     /// Create an `Extract`or from a source path
@@ -231,6 +1151,7 @@ impl<'a> Extract<'a> {
         Self {
             source,
             archive: None,
+            file_to_extract: None,
         }
     }
 
@@ -241,26 +1162,27 @@ impl<'a> Extract<'a> {
         self
     }
 
-    fn get_archive_reader(
-        source: fs::File,
-        compression: Option<Compression>,
-    ) -> Either<fs::File, flate2::read::GzDecoder<fs::File>> {
-        match compression {
-            Some(Compression::Gz) => Either::Right(flate2::read::GzDecoder::new(source)),
-            None => Either::Left(source),
-        }
+    /// Only extract the file at `path` in the archive in `extract_into`, like `extract_file`
+    pub fn file_to_extract(&mut self, path: &'a path::Path) -> &mut Self {
+        self.file_to_extract = Some(path);
+        self
     }
 
     /// Extract an entire source archive into a specified path. If the source is a single compressed
     /// file and not an archive, it will be extracted into a file with the same name inside of
-    /// `into_dir`.
+    /// `into_dir`. Only `file_to_extract` is extracted if set.
     pub fn extract_into(&self, into_dir: &path::Path) -> Result<()> {
+        if let Some(file_to_extract) = self.file_to_extract {
+            fs::create_dir_all(into_dir)?;
+            return self.extract_file(into_dir, file_to_extract);
+        }
+        let into_dir = &long_path(into_dir);
         let source = fs::File::open(self.source)?;
-        let archive = self.archive.unwrap_or_else(|| detect_archive(&self.source));
+        let archive = self.archive.unwrap_or_else(|| detect_archive(self.source));
 
         match archive {
             ArchiveKind::Plain(compression) | ArchiveKind::Tar(compression) => {
-                let mut reader = Self::get_archive_reader(source, compression);
+                let mut reader = decompress(source, compression)?;
 
                 match archive {
                     ArchiveKind::Plain(_) => {
@@ -277,23 +1199,34 @@ impl<'a> Extract<'a> {
                         })?;
                         let mut out_path = into_dir.join(file_name);
                         out_path.set_extension("");
-                        let mut out_file = fs::File::create(&out_path)?;
-                        io::copy(&mut reader, &mut out_file)?;
+                        write_new_file(&mut reader, &out_path)?;
                     }
+                    #[cfg(feature = "archive-tar")]
                     ArchiveKind::Tar(_) => {
                         let mut archive = tar::Archive::new(reader);
                         archive.unpack(into_dir)?;
                     }
+                    #[cfg(not(feature = "archive-tar"))]
+                    ArchiveKind::Tar(_) => return Err(unsupported("tar", "archive-tar")),
                     _ => unreachable!(),
                 };
             }
+            #[cfg(not(feature = "archive-zip"))]
+            ArchiveKind::Zip => return Err(unsupported("zip", "archive-zip")),
+            #[cfg(feature = "archive-zip")]
             ArchiveKind::Zip => {
                 let mut archive = zip::ZipArchive::new(source)?;
                 for i in 0..archive.len() {
                     let mut file = archive.by_index(i)?;
-                    let path = into_dir.join(file.name());
-                    let mut output = fs::File::create(path)?;
-                    io::copy(&mut file, &mut output)?;
+                    let path = entry_path(into_dir, file.name());
+                    if file.name().ends_with('/') {
+                        fs::create_dir_all(&path)?;
+                        continue;
+                    }
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    write_new_file(&mut file, &path)?;
                 }
             }
         };
@@ -309,63 +1242,165 @@ impl<'a> Extract<'a> {
         file_to_extract: T,
     ) -> Result<()> {
         let file_to_extract = file_to_extract.as_ref();
+        let into_dir = &long_path(into_dir);
         let source = fs::File::open(self.source)?;
-        let archive = self.archive.unwrap_or_else(|| detect_archive(&self.source));
+        let archive = self.archive.unwrap_or_else(|| detect_archive(self.source));
 
         match archive {
-            ArchiveKind::Plain(compression) | ArchiveKind::Tar(compression) => {
-                let mut reader = Self::get_archive_reader(source, compression);
-
-                match archive {
-                    ArchiveKind::Plain(_) => {
-                        match fs::create_dir_all(into_dir) {
-                            Ok(_) => (),
-                            Err(e) => {
-                                if e.kind() != io::ErrorKind::AlreadyExists {
-                                    return Err(Error::Io(e));
-                                }
-                            }
-                        }
-                        let file_name = file_to_extract.file_name().ok_or_else(|| {
-                            Error::Update("Extractor source has no file-name".into())
-                        })?;
-                        let out_path = into_dir.join(file_name);
-                        let mut out_file = fs::File::create(&out_path)?;
-                        io::copy(&mut reader, &mut out_file)?;
-                    }
-                    ArchiveKind::Tar(_) => {
-                        let mut archive = tar::Archive::new(reader);
-                        let mut entry = archive
-                            .entries()?
-                            .filter_map(|e| e.ok())
-                            .find(|e| e.path().ok().filter(|p| p == file_to_extract).is_some())
-                            .ok_or_else(|| {
-                                Error::Update(format!(
-                                    "Could not find the required path in the archive: {:?}",
-                                    file_to_extract
-                                ))
-                            })?;
-                        entry.unpack_in(into_dir)?;
-                    }
-                    _ => {
-                        panic!("Unreasonable code");
-                    }
-                };
+            ArchiveKind::Plain(_) | ArchiveKind::Tar(_) => {
+                extract_file_from(source, archive, into_dir, file_to_extract)?;
             }
+            #[cfg(not(feature = "archive-zip"))]
+            ArchiveKind::Zip => return Err(unsupported("zip", "archive-zip")),
+            #[cfg(feature = "archive-zip")]
             ArchiveKind::Zip => {
                 let mut archive = zip::ZipArchive::new(source)?;
-                let mut file = archive.by_name(file_to_extract.to_str().unwrap())?;
-                let mut output = fs::File::create(into_dir.join(file.name()))?;
-                io::copy(&mut file, &mut output)?;
+                let name = file_to_extract
+                    .to_str()
+                    .ok_or_else(|| Error::ArchiveEntryNotFound(file_to_extract.to_path_buf()))?;
+                let mut file = match archive.by_name(name) {
+                    Err(zip::result::ZipError::FileNotFound) => {
+                        return Err(Error::ArchiveEntryNotFound(file_to_extract.to_path_buf()))
+                    }
+                    file => file?,
+                };
+                let path = entry_path(into_dir, file.name());
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                write_new_file(&mut file, &path)?;
             }
         };
         Ok(())
     }
 }
 
-/// Moves a file from the given path to the specified destination.
+/// Wrap `reader` with a decoder for `compression`
 ///
-/// `source` and `dest` must be on the same filesystem.
+/// * Errors:
+///     * Config - Support for `compression` isn't enabled
+fn decompress<'r, R: io::Read + 'r>(
+    reader: R,
+    compression: Option<Compression>,
+) -> Result<Box<dyn io::Read + 'r>> {
+    match compression {
+        #[cfg(feature = "compression-flate2")]
+        Some(Compression::Gz) => Ok(Box::new(flate2::read::GzDecoder::new(reader))),
+        #[cfg(not(feature = "compression-flate2"))]
+        Some(Compression::Gz) => Err(unsupported("gzip", "compression-flate2")),
+        #[cfg(feature = "compression-zstd")]
+        Some(Compression::Zstd) => Ok(Box::new(zstd::stream::read::Decoder::new(reader)?)),
+        #[cfg(not(feature = "compression-zstd"))]
+        Some(Compression::Zstd) => Err(unsupported("zstd", "compression-zstd")),
+        #[cfg(feature = "compression-xz")]
+        Some(Compression::Xz) => Ok(Box::new(xz_decoder(reader)?)),
+        #[cfg(not(feature = "compression-xz"))]
+        Some(Compression::Xz) => Err(unsupported("xz", "compression-xz")),
+        None => Ok(Box::new(reader)),
+    }
+}
+
+/// Decoder of the xz compressed `reader` using all cores. Decoding is only spread
+/// over threads for streams compressed in several blocks, e.g. by `xz -T0`.
+///
+/// * Errors:
+///     * Update - The decoder couldn't be started
+#[cfg(feature = "compression-xz")]
+fn xz_decoder<R: io::Read>(reader: R) -> Result<liblzma::read::XzDecoder<R>> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    let stream = liblzma::stream::MtStreamBuilder::new()
+        .threads(threads)
+        .memlimit_stop(u64::MAX)
+        .decoder()
+        .map_err(|e| format_err!(Error::Update, "Failed to start the xz decoder: {}", e))?;
+    Ok(liblzma::read::XzDecoder::new_stream(reader, stream))
+}
+
+/// Error for an archive `format` whose cargo `feature` isn't enabled
+#[cfg(not(all(
+    feature = "archive-tar",
+    feature = "archive-zip",
+    feature = "compression-flate2",
+    feature = "compression-zstd",
+    feature = "compression-xz"
+)))]
+fn unsupported(format: &str, feature: &str) -> Error {
+    format_err!(
+        Error::Config,
+        "{} support requires the `{}` feature of self_update",
+        format,
+        feature
+    )
+}
+
+/// Extract `file_to_extract` from a (possibly compressed) tar or plain file read
+/// from `reader`, reading only as far as the entry
+fn extract_file_from<R: io::Read>(
+    reader: R,
+    archive: ArchiveKind,
+    into_dir: &path::Path,
+    file_to_extract: &path::Path,
+) -> Result<()> {
+    let into_dir = &long_path(into_dir);
+    match archive {
+        ArchiveKind::Plain(compression) => {
+            fs::create_dir_all(into_dir)?;
+            let file_name = file_to_extract
+                .file_name()
+                .ok_or_else(|| Error::Update("Extractor source has no file-name".into()))?;
+            write_new_file(
+                &mut decompress(reader, compression)?,
+                &into_dir.join(file_name),
+            )?;
+        }
+        #[cfg(not(feature = "archive-tar"))]
+        ArchiveKind::Tar(_) => return Err(unsupported("tar", "archive-tar")),
+        #[cfg(feature = "archive-tar")]
+        ArchiveKind::Tar(compression) => {
+            let mut archive = tar::Archive::new(decompress(reader, compression)?);
+            let mut entry = archive
+                .entries()?
+                .filter_map(|e| e.ok())
+                .find(|e| e.path().ok().filter(|p| p == file_to_extract).is_some())
+                .ok_or_else(|| Error::ArchiveEntryNotFound(file_to_extract.to_path_buf()))?;
+            if let Err(e) = entry.unpack_in(into_dir) {
+                let _ = fs::remove_file(into_dir.join(file_to_extract));
+                return Err(e.into());
+            }
+        }
+        ArchiveKind::Zip => bail!(
+            Error::Config,
+            "zip archives can't be extracted from a stream"
+        ),
+    }
+    Ok(())
+}
+
+/// Copy `reader` into a new file at `path`, removing the file if that fails part way
+fn write_new_file<R: io::Read + ?Sized>(reader: &mut R, path: &path::Path) -> Result<()> {
+    let mut file = fs::File::create(path)?;
+    if let Err(e) = io::copy(reader, &mut file) {
+        drop(file);
+        let _ = fs::remove_file(path);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Flush the content of the file at `path` to disk. `fsync` works on read-only files,
+/// e.g. executables extracted with mode `0555`, but `FlushFileBuffers` needs write access.
+fn sync_file(path: &path::Path) -> io::Result<()> {
+    let file = if cfg!(windows) {
+        fs::OpenOptions::new().write(true).open(path)?
+    } else {
+        fs::File::open(path)?
+    };
+    file.sync_all()
+}
+
+/// Moves a file from the given path to the specified destination.
+///
+/// `source` and `dest` must be on the same filesystem.
 /// If `replace_using_temp` is specified, the destination file will be
 /// replaced using the given temporary path.
 ///
@@ -383,11 +1418,14 @@ impl<'a> Move<'a> {
     }
 
     /// If specified and the destination file already exists, the "destination"
-    /// file will be moved to the given temporary location before the "source"
-    /// file is moved to the "destination" file.
+    /// file will be preserved at the given temporary location.
     ///
-    /// In the event of an `io` error while renaming "source" to "destination",
-    /// the temporary file will be moved back to "destination".
+    /// On unix the "destination" file is hard-linked (or copied) to the temporary
+    /// location and then atomically replaced by renaming "source" over it, so
+    /// "destination" always refers to either the complete old or new file. On Windows,
+    /// where a running executable can't be replaced, "destination" is first renamed to
+    /// the temporary location; in the event of an `io` error while renaming "source"
+    /// to "destination", the temporary file will be moved back to "destination".
     ///
     /// The `temp` dir must be explicitly provided since `rename` operations require
     /// files to live on the same filesystem.
@@ -397,35 +1435,87 @@ impl<'a> Move<'a> {
     }
 
     /// Move source file to specified destination
+    ///
+    /// * Errors:
+    ///     * PermissionDenied - `dest` (or `temp`) isn't writable
+    ///     * Io - copying / renaming
     pub fn to_dest(&self, dest: &path::Path) -> Result<()> {
-        match self.temp {
-            None => {
-                fs::rename(self.source, dest)?;
+        // make sure the new content is on disk before it becomes visible at `dest`
+        sync_file(&long_path(self.source))?;
+        self.move_to(dest).map_err(|e| match e {
+            Error::Io(source) if source.kind() == io::ErrorKind::PermissionDenied => {
+                Error::PermissionDenied {
+                    path: dest.to_path_buf(),
+                    source,
+                }
             }
-            Some(temp) => {
-                if dest.exists() {
-                    fs::rename(dest, temp)?;
-                    if let Err(e) = fs::rename(self.source, dest) {
-                        fs::rename(temp, dest)?;
-                        return Err(Error::from(e));
-                    }
-                } else {
-                    fs::rename(self.source, dest)?;
+            e => e,
+        })
+    }
+
+    fn move_to(&self, dest: &path::Path) -> Result<()> {
+        let (source, dest) = (&long_path(self.source), &long_path(dest));
+        match self.temp.map(long_path) {
+            Some(ref temp) if dest.exists() && cfg!(windows) => {
+                fs::rename(dest, temp)?;
+                if let Err(e) = fs::rename(source, dest) {
+                    fs::rename(temp, dest)?;
+                    return Err(Error::from(e));
+                }
+            }
+            Some(ref temp) if dest.exists() => {
+                if fs::hard_link(dest, temp).is_err() {
+                    fs::copy(dest, temp)?;
+                }
+                if let Err(e) = fs::rename(source, dest) {
+                    let _ = fs::remove_file(temp);
+                    return Err(Error::from(e));
                 }
             }
+            _ => {
+                fs::rename(source, dest)?;
+            }
         };
+        // persist the rename itself, not supported by every platform / filesystem
+        if let Some(dir) = dest.parent() {
+            let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
+        }
         Ok(())
     }
 }
 
-/// Download things into files
+/// Download things into files or writers
+///
+/// With optional progress bar and reporting, retries of transient failures, resumed
+/// and concurrent downloads, and checksum verification, for release assets as well as
+/// any other file an application needs to fetch.
+///
+/// ```no_run
+/// # fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+/// use self_update::{checksum::Algorithm, Download};
 ///
-/// With optional progress bar
+/// let mut model = Vec::new();
+/// Download::from_url("https://example.com/model.bin")
+///     .header("x-api-key", "secret")
+///     .max_retries(3)
+///     .on_progress(|progress| println!("{} bytes", progress.bytes))
+///     .checksum(Algorithm::Sha256, "ab12...")
+///     .download_to(&mut model)?;
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug)]
 pub struct Download {
     show_progress: bool,
     url: String,
     progress_style: ProgressStyle,
+    http: HttpConfig,
+    max_download_speed: Option<u64>,
+    connections: usize,
+    progress: Option<Callback<progress::ProgressHook>>,
+    progress_interval: Duration,
+    checksum: Option<(checksum::Algorithm, String)>,
+    resume: Option<path::PathBuf>,
 }
 impl Download {
     /// Specify download url
@@ -433,60 +1523,460 @@ impl Download {
         Self {
             show_progress: false,
             url: url.to_owned(),
-            progress_style: ProgressStyle::default_bar()
-                .template("[{elapsed_precise}] [{bar:40}] {bytes}/{total_bytes} ({eta}) {msg}")
-                .progress_chars("=>-"),
+            progress_style: progress::BarStyle::default().build(),
+            http: HttpConfig::default(),
+            max_download_speed: None,
+            connections: 1,
+            progress: None,
+            progress_interval: progress::DEFAULT_REPORT_INTERVAL,
+            checksum: None,
+            resume: None,
         }
     }
 
-    /// Toggle download progress bar
+    /// Verify the downloaded content has the hex encoded checksum `expected`. The
+    /// checksum is computed while downloading, `download_extract_file` then reads
+    /// the whole download even once the file is extracted.
+    pub fn checksum(&mut self, algorithm: checksum::Algorithm, expected: &str) -> &mut Self {
+        self.checksum = Some((algorithm, expected.to_owned()));
+        self
+    }
+
+    /// Toggle download progress bar. It's only drawn when stdout and stderr are terminals.
     pub fn show_progress(&mut self, b: bool) -> &mut Self {
         self.show_progress = b;
         self
     }
 
-    /// Set the progress style
+    /// Set the progress style, see `progress::BarStyle` to adjust the default one
     pub fn set_progress_style(&mut self, progress_style: ProgressStyle) -> &mut Self {
         self.progress_style = progress_style;
         self
     }
 
+    /// Call `hook` with the download's progress, independently of the terminal
+    /// progress bar, at most once per `progress_interval` and once when done.
+    ///
+    /// ```
+    /// # use self_update::Download;
+    /// let (tx, rx) = std::sync::mpsc::channel();
+    /// Download::from_url("https://example.com/asset").on_progress(move |progress| {
+    ///     let _ = tx.send(progress);
+    /// });
+    /// ```
+    pub fn on_progress<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(progress::DownloadProgress) + Send + Sync + 'static,
+    {
+        self.progress = Some(Callback(Arc::new(hook)));
+        self
+    }
+
+    /// Set the minimum interval between two `on_progress` reports. Defaults to 100 milliseconds.
+    pub fn progress_interval(&mut self, interval: Duration) -> &mut Self {
+        self.progress_interval = interval;
+        self
+    }
+
+    /// Set the maximum time allowed to establish a connection. Unlimited by default.
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time to wait on the server for the response or any
+    /// chunk of the body. Defaults to 30 seconds.
+    pub fn read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time the whole request, including reading the entire body,
+    /// may take. Unlimited by default.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.http.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the number of times a request is retried after a transient network
+    /// failure or a 5xx response. Defaults to `0`.
+    pub fn max_retries(&mut self, retries: u32) -> &mut Self {
+        self.http.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry, doubled for each following retry
+    /// (up to 30 seconds). Defaults to 500 milliseconds.
+    pub fn retry_backoff(&mut self, backoff: Duration) -> &mut Self {
+        self.http.retry_backoff = backoff;
+        self
+    }
+
+    /// Trust the PEM encoded certificates in `path`, e.g. a corporate root CA,
+    /// in addition to the system's root certificates
+    pub fn root_certificates<A: AsRef<path::Path>>(&mut self, path: A) -> &mut Self {
+        self.http
+            .root_certificates
+            .push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Toggle loading the system CA bundle on linux, for statically linked TLS
+    /// libraries that can't find it on their own. Defaults to `true`.
+    pub fn system_certificates(&mut self, load: bool) -> &mut Self {
+        self.http.system_certificates = load;
+        self
+    }
+
+    /// Set the `User-Agent` header sent with every request.
+    /// Defaults to `self_update/<version>`.
+    pub fn user_agent(&mut self, user_agent: &str) -> &mut Self {
+        self.http.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Send the header `name: value` with the requests, e.g. the credentials of a
+    /// private server. Use `user_agent` to set the `User-Agent` header.
+    pub fn header(&mut self, name: &str, value: &str) -> &mut Self {
+        self.http.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Limit the average download rate to `bytes_per_sec`. Unlimited by default.
+    pub fn max_download_speed(&mut self, bytes_per_sec: u64) -> &mut Self {
+        self.max_download_speed = Some(bytes_per_sec);
+        self
+    }
+
+    /// Download with up to `connections` concurrent ranged requests in `download_to_file`,
+    /// for servers that throttle each connection. Defaults to `1`.
+    pub fn connections(&mut self, connections: usize) -> &mut Self {
+        self.connections = connections.max(1);
+        self
+    }
+
+    /// Download into the file `partial` first in `download_to_file`, keeping it and
+    /// its state (url and `ETag`) in `<partial>.json` if the download is interrupted,
+    /// even by the process being killed. A later download of the same url with the
+    /// same `partial` file then resumes where it stopped, if the server supports range
+    /// requests and the content didn't change, else it starts over. The file is moved
+    /// to its destination once complete. Only downloads over a single connection are
+    /// resumed.
+    pub fn resume_from<P: AsRef<path::Path>>(&mut self, partial: P) -> &mut Self {
+        self.resume = Some(partial.as_ref().to_path_buf());
+        self
+    }
+
+    /// Download the file behind the given `url` into the file at `path`.
+    ///
+    /// With more than one `connections`, the file is split into that many segments
+    /// downloaded concurrently if the server supports range requests. The progress
+    /// bar and `max_download_speed` then apply to all segments combined.
+    ///
+    /// The file is removed if the download fails part way or doesn't match its
+    /// `checksum`, rather than left truncated or unverified.
+    ///
+    /// * Errors:
+    ///     * Io - Creating / writing the file
+    ///     * the `download_to` errors
+    pub fn download_to_file<P: AsRef<path::Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let ranged_size = match self.connections {
+            1 => None,
+            _ => self.ranged_size()?,
+        };
+        let result = match ranged_size {
+            Some(size) => self
+                .download_segments(path, size)
+                .and_then(|()| match self.checksum {
+                    Some((algorithm, ref expected)) => algorithm.verify(path, expected),
+                    None => Ok(()),
+                }),
+            None => match self.resume {
+                Some(ref partial) => self.download_resumable(path, partial),
+                None => {
+                    let file = fs::File::create(path)?;
+                    self.download_to(file)
+                }
+            },
+        };
+        if result.is_err() {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    /// Download into `partial`, resuming an earlier download, then move it to `path`
+    fn download_resumable(&self, path: &path::Path, partial: &path::Path) -> Result<()> {
+        if let Some(dir) = partial.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let state_path = {
+            let mut name = partial.as_os_str().to_owned();
+            name.push(".json");
+            path::PathBuf::from(name)
+        };
+        let mut src = match self.resume_state(&state_path, partial) {
+            Some((offset, etag)) => match self.start_from(Some((offset, &etag))) {
+                // the partial is complete, or larger than the content
+                Err(Error::HttpStatus { status: 416, .. }) => {
+                    let _ = fs::remove_file(partial);
+                    let _ = fs::remove_file(&state_path);
+                    self.start()?
+                }
+                src => src?,
+            },
+            None => self.start()?,
+        };
+        let mut file = if src.received > 0 {
+            if let Some((ref mut hasher, _)) = src.checksum {
+                let mut downloaded = io::Read::take(fs::File::open(partial)?, src.received);
+                io::copy(&mut downloaded, hasher)?;
+            }
+            fs::OpenOptions::new().append(true).open(partial)?
+        } else {
+            fs::File::create(partial)?
+        };
+        // without a validator, the content could change before the download is resumed
+        match src.resp.header("etag") {
+            Some(etag) => {
+                let state = serde_json::json!({"url": self.url, "etag": etag});
+                fs::write(&state_path, serde_json::to_vec(&state)?)?;
+            }
+            None => {
+                let _ = fs::remove_file(&state_path);
+            }
+        }
+        io::copy(&mut src, &mut file)?;
+        file.sync_all()?;
+        drop(file);
+        if let Err(e) = src.finish() {
+            let _ = fs::remove_file(partial);
+            let _ = fs::remove_file(&state_path);
+            return Err(e);
+        }
+        if fs::rename(partial, path).is_err() {
+            // e.g. across filesystems
+            fs::copy(partial, path)?;
+            let _ = fs::remove_file(partial);
+        }
+        let _ = fs::remove_file(&state_path);
+        Ok(())
+    }
+
+    /// Bytes already downloaded into `partial` and the `ETag` they were downloaded
+    /// with, if its state at `state_path` is of the same url
+    fn resume_state(&self, state_path: &path::Path, partial: &path::Path) -> Option<(u64, String)> {
+        let content = fs::read(state_path).ok()?;
+        let state = serde_json::from_slice::<serde_json::Value>(&content).ok()?;
+        if state["url"].as_str()? != self.url {
+            return None;
+        }
+        let etag = state["etag"].as_str()?.to_owned();
+        let offset = fs::metadata(partial).ok()?.len();
+        Some((offset, etag)).filter(|(offset, _)| *offset > 0)
+    }
+
+    /// Size of the download if the server supports range requests
+    fn ranged_size(&self) -> Result<Option<u64>> {
+        let resp = match self.http.get_range(&self.url, 0, 1) {
+            Err(Error::HttpStatus { .. }) => return Ok(None),
+            r => r?,
+        };
+        // `Content-Range: bytes 0-0/<size>`
+        Ok(resp
+            .header("content-range")
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|size| size.parse::<u64>().ok()))
+    }
+
+    fn download_segments(&self, path: &path::Path, size: u64) -> Result<()> {
+        fs::File::create(path)?.set_len(size)?;
+        let segment_size = size.div_ceil(self.connections as u64).max(1);
+        let bar = if self.show_progress && size > 0 && can_show_progress() {
+            let pb = ProgressBar::new(size);
+            pb.set_style(self.progress_style.clone());
+            Some(pb)
+        } else {
+            None
+        };
+        let reporter = self.reporter(Some(size));
+        let received = std::sync::atomic::AtomicU64::new(0);
+        let started = Instant::now();
+
+        let download_segment = |start: u64, end: u64| -> Result<()> {
+            use io::{Read, Seek, Write};
+            let mut resp = self.http.get_range(&self.url, start, end)?;
+            let mut file = fs::OpenOptions::new().write(true).open(path)?;
+            file.seek(io::SeekFrom::Start(start))?;
+            let mut buf = vec![0; 64 * 1024];
+            let mut written = 0;
+            loop {
+                let n = resp.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                file.write_all(&buf[..n])?;
+                written += n as u64;
+                let total =
+                    received.fetch_add(n as u64, std::sync::atomic::Ordering::SeqCst) + n as u64;
+                if let Some(bytes_per_sec) = self.max_download_speed {
+                    if let Some(delay) = throttle_delay(total, bytes_per_sec, started.elapsed()) {
+                        thread::sleep(delay);
+                    }
+                }
+                if let Some(ref bar) = bar {
+                    progress::update_bar(bar, total, size, started.elapsed());
+                }
+                if let Some(ref reporter) = reporter {
+                    reporter.report(total, false);
+                }
+            }
+            if written != end - start {
+                return Err(Error::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "bytes {}-{} of {} ended after {} bytes",
+                        start, end, self.url, written
+                    ),
+                )));
+            }
+            Ok(())
+        };
+
+        let segments = (0..size)
+            .step_by(segment_size as usize)
+            .map(|start| (start, min(start + segment_size, size)));
+        thread::scope(|scope| {
+            segments
+                .map(|(start, end)| scope.spawn(move || download_segment(start, end)))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(Error::Update("download thread panicked".into())))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+        if let Some(ref bar) = bar {
+            bar.finish_with_message("Done");
+        }
+        if let Some(ref reporter) = reporter {
+            reporter.report(size, true);
+        }
+        Ok(())
+    }
+
     /// Download the file behind the given `url` into the specified `dest`.
     /// Show a sliding progress bar if specified.
     /// If the resource doesn't specify a content-length, the progress bar will not be shown
     ///
     /// * Errors:
-    ///     * `reqwest` network errors
+    ///     * Network - request failure
+    ///     * Truncated - The response ended before its declared content-length
+    ///     * Io - `TimedOut` if the download exceeds the configured `timeout`
     ///     * Unsuccessful response status
     ///     * Progress-bar errors
     ///     * Reading from response to `BufReader`-buffer
     ///     * Writing from `BufReader`-buffer to `File`
     pub fn download_to<T: io::Write>(&self, mut dest: T) -> Result<()> {
-        use io::BufRead;
+        let mut src = self.start()?;
+        io::copy(&mut src, &mut dest)?;
+        src.finish()
+    }
 
-        set_ssl_vars!();
-        let resp = reqwest::get(&self.url)?;
-        let size = resp
-            .headers()
-            .get(reqwest::header::CONTENT_LENGTH)
-            .map(|val| {
-                val.to_str()
-                    .map(|s| s.parse::<u64>().unwrap_or(0))
-                    .unwrap_or(0)
-            })
-            .unwrap_or(0);
-        if !resp.status().is_success() {
+    /// Download a (possibly compressed) tar or plain file, extracting `file_to_extract`
+    /// into `into_dir` while it's being downloaded, like `Extract::extract_file`.
+    ///
+    /// Nothing but the extracted file is written to disk, and the download stops
+    /// once the file is extracted, unless there's a `checksum` to verify. The file is
+    /// extracted into a temporary directory in `into_dir`, and only moved into place
+    /// once the download is complete and verified.
+    ///
+    /// * Errors:
+    ///     * Config - `archive` is a zip, which can't be read as a stream
+    ///     * ArchiveEntryNotFound - `file_to_extract` isn't in the archive
+    ///     * the `download_to` errors
+    pub fn download_extract_file<T: AsRef<path::Path>>(
+        &self,
+        archive: ArchiveKind,
+        into_dir: &path::Path,
+        file_to_extract: T,
+    ) -> Result<()> {
+        if archive == ArchiveKind::Zip {
             bail!(
-                Error::Update,
-                "Download request failed with status: {:?}",
-                resp.status()
-            )
+                Error::Config,
+                "zip archives can't be extracted while downloading"
+            );
+        }
+        let file_to_extract = file_to_extract.as_ref();
+        // the file is only moved into place once the download is verified
+        fs::create_dir_all(into_dir)?;
+        let tmp_dir = tempdir::TempDir::new_in(into_dir, ".self_update_extract")?;
+        let mut src = self.start()?;
+        extract_file_from(&mut src, archive, tmp_dir.path(), file_to_extract)?;
+        if self.checksum.is_some() {
+            io::copy(&mut src, &mut io::sink())?;
+        }
+        src.finish()?;
+        let extracted = match archive {
+            ArchiveKind::Plain(_) => {
+                path::Path::new(file_to_extract.file_name().unwrap_or_default())
+            }
+            _ => file_to_extract,
+        };
+        let dest = into_dir.join(extracted);
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir)?;
         }
-        let show_progress = if size == 0 { false } else { self.show_progress };
+        fs::rename(long_path(&tmp_dir.path().join(extracted)), long_path(&dest))?;
+        Ok(())
+    }
 
-        let mut src = io::BufReader::new(resp);
-        let mut downloaded = 0;
-        let mut bar = if show_progress {
+    /// Send the request and wrap the response body with progress reporting and throttling
+    fn start(&self) -> Result<DownloadReader> {
+        self.start_from(None)
+    }
+
+    /// Like `start`, requesting the content from the given offset on if it still has
+    /// the given `ETag`. The reader's `received` bytes are the offset the response
+    /// resumes from, `0` if the server sent the whole content.
+    fn start_from(&self, resume: Option<(u64, &str)>) -> Result<DownloadReader> {
+        let resp = match resume {
+            Some((offset, etag)) => self.http.get_from(&self.url, offset, etag)?,
+            None => self.http.get(&self.url)?,
+        };
+        if !resp.is_success() {
+            return Err(Error::HttpStatus {
+                url: self.url.clone(),
+                status: resp.status(),
+            });
+        }
+        // `Content-Range: bytes <offset>-<last>/<size>`
+        let offset = match resume {
+            Some((offset, _)) if resp.status() == 206 => {
+                let content_range = resp
+                    .header("content-range")
+                    .and_then(|v| v.strip_prefix("bytes "));
+                let range_start = content_range
+                    .and_then(|v| v.split('-').next())
+                    .and_then(|start| start.parse::<u64>().ok());
+                let total = content_range
+                    .and_then(|v| v.rsplit('/').next())
+                    .and_then(|size| size.parse::<u64>().ok());
+                if range_start != Some(offset) || total.is_some_and(|total| offset >= total) {
+                    return self.start();
+                }
+                offset
+            }
+            _ => 0,
+        };
+        let size = resp
+            .header("content-length")
+            .map(|val| val.parse::<u64>().unwrap_or(0) + offset)
+            .unwrap_or(0);
+        let bar = if self.show_progress && size > 0 && can_show_progress() {
             let pb = ProgressBar::new(size);
             pb.set_style(self.progress_style.clone());
 
@@ -494,40 +1984,476 @@ impl Download {
         } else {
             None
         };
-        loop {
-            let n = {
-                let buf = src.fill_buf()?;
-                dest.write_all(&buf)?;
-                buf.len()
-            };
-            if n == 0 {
-                break;
-            }
-            src.consume(n);
-            downloaded = min(downloaded + n as u64, size);
+        Ok(DownloadReader {
+            url: self.url.clone(),
+            resp,
+            size,
+            received: offset,
+            started: Instant::now(),
+            max_download_speed: self.max_download_speed,
+            bar,
+            reporter: self.reporter(Some(size).filter(|size| *size > 0)),
+            checksum: match self.checksum {
+                Some((algorithm, ref expected)) => Some((algorithm.hasher()?, expected.clone())),
+                None => None,
+            },
+        })
+    }
+
+    fn reporter(&self, total: Option<u64>) -> Option<progress::Reporter> {
+        self.progress
+            .clone()
+            .map(|hook| progress::Reporter::new(hook, self.progress_interval, total))
+    }
+}
+
+/// A download's response body, updating the progress bar and throttling as it's read
+struct DownloadReader {
+    url: String,
+    resp: http::Response,
+    size: u64,
+    received: u64,
+    started: Instant,
+    max_download_speed: Option<u64>,
+    bar: Option<ProgressBar>,
+    reporter: Option<progress::Reporter>,
+    /// Hasher of the content read and the checksum it should have
+    checksum: Option<(checksum::Hasher, String)>,
+}
+
+impl DownloadReader {
+    /// Finish progress reporting, and check the checksum of the content if expected
+    ///
+    /// * Errors:
+    ///     * ChecksumMismatch - The content read doesn't have the expected checksum
+    fn finish(&mut self) -> Result<()> {
+        if let Some(ref bar) = self.bar {
+            bar.finish_with_message("Done");
+        }
+        if let Some(ref reporter) = self.reporter {
+            reporter.report(self.received, true);
+        }
+        match self.checksum.take() {
+            Some((hasher, expected)) => checksum::check(hasher.finish(), &expected),
+            None => Ok(()),
+        }
+    }
+}
+
+impl io::Read for DownloadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = match self.resp.read(buf) {
+            Err(ref e) if self.received < self.size && is_unexpected_eof(e) => 0,
+            r => r?,
+        };
+        self.received += n as u64;
+        if let Some((ref mut hasher, _)) = self.checksum {
+            io::Write::write_all(hasher, &buf[..n])?;
+        }
+        if n == 0 && !buf.is_empty() && self.received < self.size {
+            // rather than a confusing error extracting a partial archive
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Error::Truncated {
+                    url: self.url.clone(),
+                    expected: self.size,
+                    received: self.received,
+                },
+            ));
+        }
 
-            if let Some(ref mut bar) = bar {
-                bar.set_position(downloaded);
+        if let Some(bytes_per_sec) = self.max_download_speed {
+            if let Some(delay) =
+                throttle_delay(self.received, bytes_per_sec, self.started.elapsed())
+            {
+                thread::sleep(delay);
             }
         }
-        if let Some(ref mut bar) = bar {
-            bar.finish_with_message("Done");
+
+        if let Some(ref bar) = self.bar {
+            progress::update_bar(bar, self.received, self.size, self.started.elapsed());
         }
-        Ok(())
+        if let Some(ref reporter) = self.reporter {
+            reporter.report(self.received, false);
+        }
+        Ok(n)
+    }
+}
+
+/// Whether `e` is, or is caused by, a response body ending before its content-length,
+/// which the http clients report wrapped in their own errors
+fn is_unexpected_eof(e: &io::Error) -> bool {
+    let mut error: Option<&(dyn std::error::Error + 'static)> = Some(e);
+    while let Some(e) = error {
+        error = match e.downcast_ref::<io::Error>() {
+            Some(e) if e.kind() == io::ErrorKind::UnexpectedEof => return true,
+            // the source of an `io::Error` skips the error it wraps
+            Some(e) => e.get_ref().map(|inner| inner as _),
+            None => e.source(),
+        };
     }
+    false
+}
+
+/// Time to wait so that `received` bytes over `elapsed` time averages
+/// no more than `bytes_per_sec`
+fn throttle_delay(received: u64, bytes_per_sec: u64, elapsed: Duration) -> Option<Duration> {
+    if bytes_per_sec == 0 {
+        return None;
+    }
+    let expected = Duration::from_secs_f64(received as f64 / bytes_per_sec as f64);
+    expected
+        .checked_sub(elapsed)
+        .filter(|delay| *delay > Duration::from_millis(0))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use flate2;
+    #[cfg(feature = "compression-flate2")]
     use flate2::write::GzEncoder;
     use std::fs::{self, File};
-    use std::io::{self, Read, Write};
+    use std::io::{Read, Write};
     use std::path::{Path, PathBuf};
-    use tar;
     use tempdir::TempDir;
-    use zip;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_resolution() {
+        let tmp_dir = TempDir::new("self_update_symlink").expect("tempdir fail");
+        let target = tmp_dir.path().join("app-1.2").join("app");
+        fs::create_dir_all(target.parent().unwrap()).unwrap();
+        fs::write(&target, "app").unwrap();
+        let link = tmp_dir.path().join("app");
+        std::os::unix::fs::symlink("app-1.2/app", &link).unwrap();
+
+        assert_eq!(
+            fs::canonicalize(&target).unwrap(),
+            resolve_symlink(&link).unwrap()
+        );
+        assert_eq!(target, resolve_symlink(&target).unwrap());
+        let missing = tmp_dir.path().join("missing");
+        assert_eq!(missing, resolve_symlink(&missing).unwrap());
+    }
+
+    #[test]
+    fn long_paths() {
+        #[cfg(windows)]
+        {
+            assert_eq!(
+                Path::new(r"\\?\C:\app\bin"),
+                long_path(Path::new(r"C:\app\..\app/bin"))
+            );
+            assert_eq!(
+                Path::new(r"\\?\UNC\server\share\app"),
+                long_path(Path::new(r"\\server\share\app"))
+            );
+            assert_eq!(
+                Path::new(r"\\?\C:\app"),
+                long_path(Path::new(r"\\?\C:\app"))
+            );
+        }
+
+        let tmp_dir = TempDir::new("self_update_long_path").expect("tempdir fail");
+        let deep = (0..6).fold(tmp_dir.path().to_path_buf(), |dir, i| {
+            dir.join(format!("{}{}", i, "d".repeat(50)))
+        });
+        assert!(deep.as_os_str().len() > 300);
+        let source = tmp_dir.path().join("app");
+        fs::write(&source, "app").unwrap();
+        Extract::from_source(&source)
+            .archive(ArchiveKind::Plain(None))
+            .extract_file(&deep, "app")
+            .expect("extract fail");
+        cmp_content(deep.join("app"), "app");
+        Move::from_source(&deep.join("app"))
+            .to_dest(&deep.join("moved"))
+            .expect("move fail");
+        cmp_content(deep.join("moved"), "app");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn replaced_exe_processes() {
+        let tmp_dir = TempDir::new("self_update_processes").expect("tempdir fail");
+        let exe = tmp_dir.path().join("app");
+        fs::copy("/bin/sleep", &exe).unwrap();
+        let mut child = process::Command::new(&exe).arg("10").spawn().unwrap();
+        let id = exe_id(&exe).unwrap();
+        let new = tmp_dir.path().join("new");
+        fs::write(&new, "new").unwrap();
+        Move::from_source(&new).to_dest(&exe).expect("move fail");
+        let running = processes_running(id);
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert_eq!(vec![child.id()], running);
+        assert_ne!(Some(id), exe_id(&exe));
+    }
+
+    #[test]
+    fn move_replaces_dest_keeping_temp() {
+        let tmp_dir = TempDir::new("self_update_move").expect("tempdir fail");
+        let source = tmp_dir.path().join("new");
+        let dest = tmp_dir.path().join("app");
+        let temp = tmp_dir.path().join("app.tmp");
+        fs::write(&source, "new").unwrap();
+        fs::write(&dest, "old").unwrap();
+
+        Move::from_source(&source)
+            .replace_using_temp(&temp)
+            .to_dest(&dest)
+            .expect("move fail");
+        cmp_content(&dest, "new");
+        cmp_content(&temp, "old");
+        assert!(!source.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn move_read_only_source() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = TempDir::new("self_update_move_read_only").expect("tempdir fail");
+        let source = tmp_dir.path().join("new");
+        let dest = tmp_dir.path().join("app");
+        fs::write(&source, "new").unwrap();
+        fs::set_permissions(&source, fs::Permissions::from_mode(0o555)).unwrap();
+        Move::from_source(&source)
+            .to_dest(&dest)
+            .expect("move fail");
+        cmp_content(&dest, "new");
+        assert_eq!(
+            0o555,
+            fs::metadata(&dest).unwrap().permissions().mode() & 0o777
+        );
+    }
+
+    #[test]
+    fn confirm_without_terminal() {
+        if is_interactive() {
+            return;
+        }
+        let yes = vec!["y".to_owned()];
+        assert!(confirm("", &yes, NonInteractive::Proceed, None).is_ok());
+        match confirm("", &yes, NonInteractive::Abort, None) {
+            Err(Error::NonInteractive) => (),
+            r => panic!("expected NonInteractive, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn prompt_timeout() {
+        let answer = read_line_within(Duration::from_secs(5), || Ok("y\n".to_owned()));
+        assert_eq!(Some("y\n".to_owned()), answer.unwrap());
+        let answer = read_line_within(Duration::from_millis(10), || {
+            thread::sleep(Duration::from_secs(1));
+            Ok("late\n".to_owned())
+        });
+        assert_eq!(None, answer.unwrap());
+        assert!(unanswered(Some((Duration::ZERO, NonInteractive::Proceed))).is_ok());
+        match unanswered(Some((Duration::ZERO, NonInteractive::Abort))) {
+            Err(Error::Aborted) => (),
+            r => panic!("expected Aborted, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn choices() {
+        assert_eq!(Some(0), parse_choice("\n", 3));
+        assert_eq!(Some(2), parse_choice(" 3\n", 3));
+        assert_eq!(None, parse_choice("4", 3));
+        assert_eq!(None, parse_choice("0", 3));
+        assert_eq!(None, parse_choice("y", 3));
+    }
+
+    #[test]
+    fn disk_space() {
+        let tmp_dir = TempDir::new("self_update_space").expect("tempdir fail");
+        let missing = tmp_dir.path().join("missing").join("dir");
+        check_space(&missing, 1).expect("space check fail");
+        if cfg!(any(unix, windows)) {
+            assert!(available_space(&missing).is_some());
+            match check_space(&missing, u64::MAX) {
+                Err(Error::InsufficientSpace { required, .. }) => assert_eq!(u64::MAX, required),
+                r => panic!("expected InsufficientSpace, got {:?}", r),
+            }
+        }
+        assert_eq!(30, estimated_extracted_size(ArchiveKind::Zip, 10));
+        assert_eq!(10, estimated_extracted_size(ArchiveKind::Plain(None), 10));
+    }
+
+    #[test]
+    fn current_exe_detection() {
+        let exe = env::current_exe().unwrap();
+        assert!(is_current_exe(&exe));
+        assert!(!is_executing(&exe));
+        let tmp_dir = TempDir::new("self_update_running").expect("tempdir fail");
+        let other = tmp_dir.path().join("other");
+        fs::write(&other, "").unwrap();
+        assert!(!is_current_exe(&other));
+        assert!(!is_executing(&other));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn running_exe_detection() {
+        let tmp_dir = TempDir::new("self_update_running").expect("tempdir fail");
+        let sleep = tmp_dir.path().join("sleep");
+        fs::copy("/bin/sleep", &sleep).unwrap();
+        let mut child = process::Command::new(&sleep).arg("10").spawn().unwrap();
+        let running = is_executing(&sleep);
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(running);
+    }
+
+    #[test]
+    fn writable_dir() {
+        let tmp_dir = TempDir::new("self_update_writable").expect("tempdir fail");
+        check_writable(tmp_dir.path()).expect("writable check fail");
+        check_writable(&tmp_dir.path().join("missing")).expect("missing dir check fail");
+        assert_eq!(0, fs::read_dir(tmp_dir.path()).unwrap().count());
+    }
+
+    #[test]
+    fn old_exe_cleanup() {
+        let tmp_dir = TempDir::new("self_update_old_exe").expect("tempdir fail");
+        let exe = tmp_dir.path().join("app.exe");
+        assert_eq!(tmp_dir.path().join("app.exe.old"), old_exe_path(&exe));
+        cleanup_old_exe(&exe).expect("cleanup of missing file fail");
+        fs::write(old_exe_path(&exe), "old").unwrap();
+        cleanup_old_exe(&exe).expect("cleanup fail");
+        assert!(!old_exe_path(&exe).exists());
+    }
+
+    #[test]
+    fn arm_candidates() {
+        let target = "arm-unknown-linux-gnueabihf";
+        assert_eq!(
+            vec![
+                "armv7-unknown-linux-gnueabihf",
+                "arm-unknown-linux-gnueabihf"
+            ],
+            arm_target_candidates(target, Some(7))
+        );
+        assert_eq!(
+            vec!["arm-unknown-linux-gnueabihf"],
+            arm_target_candidates(target, Some(6))
+        );
+        assert_eq!(vec![target], arm_target_candidates(target, None));
+        assert_eq!(
+            vec!["arm-unknown-linux-gnueabi"],
+            arm_target_candidates("arm-unknown-linux-gnueabi", Some(7))
+        );
+
+        let cpuinfo = "processor\t: 0\nmodel name\t: ARMv6-compatible processor rev 7 (v6l)\n\
+                       CPU architecture: 7\nCPU revision\t: 7\n";
+        assert_eq!(Some(6), parse_cpu_architecture(cpuinfo));
+        assert_eq!(Some(7), parse_cpu_architecture("CPU architecture: 7\n"));
+        assert_eq!(Some(8), parse_cpu_architecture("CPU architecture: 8\n"));
+        assert_eq!(None, parse_cpu_architecture("Hardware\t: BCM2835\n"));
+    }
+
+    #[test]
+    fn android_candidates() {
+        assert_eq!(
+            vec!["aarch64-linux-android", "aarch64-unknown-linux-musl"],
+            android_target_candidates("aarch64-linux-android")
+        );
+        assert_eq!(
+            vec!["armv7-linux-androideabi", "armv7-unknown-linux-musleabihf"],
+            android_target_candidates("armv7-linux-androideabi")
+        );
+    }
+
+    #[test]
+    fn windows_candidates() {
+        assert_eq!(
+            vec!["aarch64-pc-windows-msvc", "x86_64-pc-windows-msvc"],
+            windows_target_candidates("x86_64-pc-windows-msvc", true)
+        );
+        assert_eq!(
+            vec!["aarch64-pc-windows-msvc"],
+            windows_target_candidates("aarch64-pc-windows-msvc", true)
+        );
+        assert_eq!(
+            vec!["i686-pc-windows-msvc"],
+            windows_target_candidates("i686-pc-windows-msvc", false)
+        );
+    }
+
+    #[test]
+    fn arch_aliases() {
+        assert_eq!(
+            vec!["riscv64gc-unknown-linux-gnu", "riscv64-unknown-linux-gnu"],
+            with_arch_aliases("riscv64gc-unknown-linux-gnu")
+        );
+        assert_eq!(
+            vec!["powerpc64le-unknown-linux-gnu", "ppc64le-unknown-linux-gnu"],
+            with_arch_aliases("powerpc64le-unknown-linux-gnu")
+        );
+        assert_eq!(
+            vec!["x86_64-unknown-linux-gnu"],
+            with_arch_aliases("x86_64-unknown-linux-gnu")
+        );
+    }
+
+    #[test]
+    fn musl_fallback() {
+        assert_eq!(
+            Some("x86_64-unknown-linux-musl".to_owned()),
+            musl_equivalent("x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(
+            Some("arm-unknown-linux-musleabihf".to_owned()),
+            musl_equivalent("arm-unknown-linux-gnueabihf")
+        );
+        assert_eq!(None, musl_equivalent("x86_64-unknown-linux-musl"));
+        assert_eq!(None, musl_equivalent("x86_64-pc-windows-gnu"));
+    }
+
+    #[test]
+    fn darwin_candidates() {
+        assert_eq!(
+            vec![
+                "aarch64-apple-darwin",
+                "universal-apple-darwin",
+                "universal2-apple-darwin",
+                "x86_64-apple-darwin",
+            ],
+            darwin_target_candidates(true)
+        );
+        assert_eq!("universal-apple-darwin", darwin_target_candidates(false)[0]);
+        assert!(!darwin_target_candidates(false).contains(&"aarch64-apple-darwin".to_owned()));
+    }
+
+    #[test]
+    fn target_matches_compiled_arch() {
+        let arch = match env::consts::ARCH {
+            "x86" => "i",
+            arch => arch,
+        };
+        assert!(get_target().starts_with(arch), "{}", get_target());
+        assert!(get_target().contains(env::consts::OS) || cfg!(target_os = "macos"));
+    }
+
+    #[test]
+    fn path_lookup() {
+        let paths = env::join_paths(["/usr/bin", "/opt/app/bin"]).unwrap();
+        assert!(is_in_path(Path::new("/opt/app/bin"), &paths));
+        assert!(!is_in_path(Path::new("/opt/app"), &paths));
+    }
+
+    #[test]
+    fn throttle() {
+        assert_eq!(None, throttle_delay(1000, 0, Duration::from_secs(0)));
+        assert_eq!(None, throttle_delay(1000, 1000, Duration::from_secs(2)));
+        assert_eq!(None, throttle_delay(1000, 1000, Duration::from_secs(1)));
+        assert_eq!(
+            Some(Duration::from_millis(1500)),
+            throttle_delay(2000, 1000, Duration::from_millis(500))
+        );
+    }
 
     #[test]
     fn detect_plain() {
@@ -553,6 +2479,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn detect_zstd_xz() {
+        assert_eq!(
+            ArchiveKind::Tar(Some(Compression::Zstd)),
+            detect_archive(&PathBuf::from("Something.tar.zst"))
+        );
+        assert_eq!(
+            ArchiveKind::Tar(Some(Compression::Xz)),
+            detect_archive(&PathBuf::from("Something.tar.xz"))
+        );
+        assert_eq!(
+            ArchiveKind::Plain(Some(Compression::Xz)),
+            detect_archive(&PathBuf::from("Something.xz"))
+        );
+    }
+
     #[test]
     fn detect_plain_tar() {
         assert_eq!(
@@ -569,6 +2511,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn truncated_download() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/asset", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 100\r\n\r\npartial");
+        });
+        let tmp_dir = TempDir::new("self_update_truncated").expect("tempdir fail");
+        let path = tmp_dir.path().join("asset");
+        match Download::from_url(&url).download_to_file(&path) {
+            Err(Error::Truncated {
+                expected, received, ..
+            }) => assert_eq!((100, 7), (expected, received)),
+            r => panic!("expected Truncated, got {:?}", r),
+        }
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn download_headers() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/asset", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            // echo the request head
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n",
+                request.len()
+            );
+            let _ = stream.write_all(&request);
+        });
+        let mut request = vec![];
+        Download::from_url(&url)
+            .header("x-api-key", "secret")
+            .download_to(&mut request)
+            .unwrap();
+        let request = String::from_utf8(request).unwrap().to_lowercase();
+        assert!(request.contains("\r\nx-api-key: secret\r\n"));
+    }
+
+    /// Serve `content` over http on a local port, honoring `Range` headers if `ranges`
+    fn serve(content: Vec<u8>, ranges: bool) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/asset", listener.local_addr().unwrap());
+        let content = Arc::new(content);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let (mut stream, content) = (stream.unwrap(), content.clone());
+                thread::spawn(move || {
+                    let mut request = vec![];
+                    let mut buf = [0; 1024];
+                    while !request.ends_with(b"\r\n\r\n") {
+                        let n = stream.read(&mut buf).unwrap();
+                        request.extend_from_slice(&buf[..n]);
+                    }
+                    let request = String::from_utf8_lossy(&request).to_lowercase();
+                    let range = request
+                        .lines()
+                        .find_map(|l| l.strip_prefix("range: bytes="))
+                        .and_then(|r| r.trim().split_once('-'))
+                        .map(|(s, e)| (s.parse::<usize>().unwrap(), e.parse::<usize>().unwrap()))
+                        .filter(|_| ranges);
+                    let (status, body, content_range) = match range {
+                        Some((start, end)) => (
+                            "206 Partial Content",
+                            &content[start..=end],
+                            format!(
+                                "content-range: bytes {}-{}/{}\r\n",
+                                start,
+                                end,
+                                content.len()
+                            ),
+                        ),
+                        None => ("200 OK", &content[..], String::new()),
+                    };
+                    let _ = write!(
+                        stream,
+                        "HTTP/1.1 {}\r\ncontent-length: {}\r\n{}connection: close\r\n\r\n",
+                        status,
+                        body.len(),
+                        content_range
+                    );
+                    let _ = stream.write_all(body);
+                });
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn download_segments() {
+        let content = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let tmp_dir = TempDir::new("self_update_download_segments").expect("tempdir fail");
+        for &ranges in &[true, false] {
+            let path = tmp_dir.path().join(format!("asset-{}", ranges));
+            Download::from_url(&serve(content.clone(), ranges))
+                .connections(3)
+                .download_to_file(&path)
+                .expect("download fail");
+            assert_eq!(content, fs::read(&path).unwrap());
+        }
+    }
+
+    #[test]
+    fn failed_download_removed() {
+        let content = (0..100_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let tmp_dir = TempDir::new("self_update_failed_download").expect("tempdir fail");
+        for &connections in &[1, 3] {
+            let path = tmp_dir.path().join(format!("asset-{}", connections));
+            let result = Download::from_url(&serve(content.clone(), true))
+                .connections(connections)
+                .checksum(checksum::Algorithm::Sha256, &"0".repeat(64))
+                .download_to_file(&path);
+            assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+            assert!(!path.exists());
+        }
+
+        let into_dir = tmp_dir.path().join("extracted");
+        let result = Download::from_url(&serve(content, false))
+            .checksum(checksum::Algorithm::Sha256, &"0".repeat(64))
+            .download_extract_file(ArchiveKind::Plain(None), &into_dir, "asset");
+        assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+        assert_eq!(0, fs::read_dir(&into_dir).unwrap().count());
+    }
+
     fn cmp_content<T: AsRef<Path>>(path: T, s: &str) {
         let mut content = String::new();
         let mut f = File::open(&path).unwrap();
@@ -577,6 +2658,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "compression-flate2")]
     fn unpack_plain_gzip() {
         let tmp_dir = TempDir::new("self_update_unpack_plain_gzip_src").expect("tempdir fail");
         let fp = tmp_dir.path().with_file_name("temp.gz");
@@ -588,7 +2670,7 @@ mod tests {
         let out_tmp = TempDir::new("self_update_unpack_plain_gzip_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&fp)
-            .extract_into(&out_path)
+            .extract_into(out_path)
             .expect("extract fail");
         let out_file = out_path.join("temp");
         assert!(out_file.exists());
@@ -596,6 +2678,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "compression-flate2")]
     fn unpack_plain_gzip_double_ext() {
         let tmp_dir =
             TempDir::new("self_update_unpack_plain_gzip_double_ext_src").expect("tempdir fail");
@@ -609,7 +2692,7 @@ mod tests {
             TempDir::new("self_update_unpack_plain_gzip_double_ext_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&fp)
-            .extract_into(&out_path)
+            .extract_into(out_path)
             .expect("extract fail");
         let out_file = out_path.join("temp.txt");
         assert!(out_file.exists());
@@ -617,6 +2700,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(all(feature = "compression-flate2", feature = "archive-tar"))]
     fn unpack_tar_gzip() {
         let tmp_dir = TempDir::new("self_update_unpack_tar_gzip_src").expect("tempdir fail");
         let tmp_path = tmp_dir.path();
@@ -647,7 +2731,7 @@ mod tests {
         let out_tmp = TempDir::new("self_update_unpack_tar_gzip_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&archive_fp)
-            .extract_into(&out_path)
+            .extract_into(out_path)
             .expect("extract fail");
 
         let out_file = out_path.join("inner_archive/temp.txt");
@@ -659,7 +2743,61 @@ mod tests {
         cmp_content(&out_file, "This is a second test!");
     }
 
+    /// A tar archive of `large.bin`, big enough to be compressed in several blocks
+    #[cfg(all(
+        feature = "archive-tar",
+        any(feature = "compression-zstd", feature = "compression-xz")
+    ))]
+    fn large_tar() -> (Vec<u8>, Vec<u8>) {
+        let content = (0..4 << 20).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        let mut ar = tar::Builder::new(vec![]);
+        ar.append_data(&mut header, "large.bin", content.as_slice())
+            .expect("tar append fail");
+        (ar.into_inner().expect("failed getting tar writer"), content)
+    }
+
+    #[test]
+    #[cfg(all(feature = "archive-tar", feature = "compression-zstd"))]
+    fn unpack_tar_zstd() {
+        let tmp_dir = TempDir::new("self_update_unpack_tar_zstd").expect("tempdir fail");
+        let (tar, content) = large_tar();
+        let archive_fp = tmp_dir.path().join("archive_file.tar.zst");
+        let compressed = zstd::stream::encode_all(tar.as_slice(), 3).expect("zstd encode fail");
+        fs::write(&archive_fp, compressed).unwrap();
+
+        Extract::from_source(&archive_fp)
+            .extract_file(tmp_dir.path(), "large.bin")
+            .expect("extract fail");
+        assert!(content == fs::read(tmp_dir.path().join("large.bin")).unwrap());
+    }
+
+    #[test]
+    #[cfg(all(feature = "archive-tar", feature = "compression-xz"))]
+    fn unpack_tar_xz_multithreaded() {
+        let tmp_dir = TempDir::new("self_update_unpack_tar_xz").expect("tempdir fail");
+        let (tar, content) = large_tar();
+        let archive_fp = tmp_dir.path().join("archive_file.tar.xz");
+        let stream = liblzma::stream::MtStreamBuilder::new()
+            .threads(4)
+            .block_size(1 << 20)
+            .encoder()
+            .expect("xz encoder fail");
+        let mut e =
+            liblzma::write::XzEncoder::new_stream(File::create(&archive_fp).unwrap(), stream);
+        e.write_all(&tar).expect("xz encode fail");
+        e.finish().expect("xz finish fail");
+
+        Extract::from_source(&archive_fp)
+            .extract_file(tmp_dir.path(), "large.bin")
+            .expect("extract fail");
+        assert!(content == fs::read(tmp_dir.path().join("large.bin")).unwrap());
+    }
+
     #[test]
+    #[cfg(feature = "compression-flate2")]
     fn unpack_file_plain_gzip() {
         let tmp_dir = TempDir::new("self_update_unpack_file_plain_gzip_src").expect("tempdir fail");
         let fp = tmp_dir.path().with_file_name("temp.gz");
@@ -672,7 +2810,7 @@ mod tests {
             TempDir::new("self_update_unpack_file_plain_gzip_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&fp)
-            .extract_file(&out_path, "renamed_file")
+            .extract_file(out_path, "renamed_file")
             .expect("extract fail");
         let out_file = out_path.join("renamed_file");
         assert!(out_file.exists());
@@ -680,6 +2818,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(all(feature = "compression-flate2", feature = "archive-tar"))]
     fn unpack_file_tar_gzip() {
         let tmp_dir = TempDir::new("self_update_unpack_file_tar_gzip_src").expect("tempdir fail");
         let tmp_path = tmp_dir.path();
@@ -690,6 +2829,7 @@ mod tests {
         let fp = archive_src.join("temp.txt");
         let mut tmp_file = File::create(&fp).expect("temp file create fail");
         tmp_file.write_all(b"This is a test!").unwrap();
+        fs::write(archive_src.join("other.txt"), "other").unwrap();
 
         let mut ar = tar::Builder::new(vec![]);
         ar.append_dir_all("inner_archive", &archive_src)
@@ -707,14 +2847,80 @@ mod tests {
             TempDir::new("self_update_unpack_file_tar_gzip_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&archive_fp)
-            .extract_file(&out_path, "inner_archive/temp.txt")
+            .extract_file(out_path, "inner_archive/temp.txt")
             .expect("extract fail");
         let out_file = out_path.join("inner_archive/temp.txt");
         assert!(out_file.exists());
         cmp_content(&out_file, "This is a test!");
+
+        let selected = out_path.join("selected");
+        Extract::from_source(&archive_fp)
+            .archive(ArchiveKind::Tar(Some(Compression::Gz)))
+            .file_to_extract(Path::new("inner_archive/temp.txt"))
+            .extract_into(&selected)
+            .expect("extract fail");
+        cmp_content(selected.join("inner_archive/temp.txt"), "This is a test!");
+        assert!(!selected.join("inner_archive/other.txt").exists());
+    }
+
+    #[test]
+    #[cfg(all(feature = "compression-flate2", feature = "archive-tar"))]
+    fn unpack_file_from_stream() {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(15);
+        header.set_cksum();
+        let mut ar = tar::Builder::new(vec![]);
+        ar.append_data(&mut header, "bin/app", &b"This is a test!"[..])
+            .expect("tar append fail");
+        ar.append_data(&mut header, "README", &b"Not extracted.."[..])
+            .expect("tar append fail");
+        let mut e = GzEncoder::new(vec![], flate2::Compression::default());
+        e.write_all(&ar.into_inner().expect("tar finish fail"))
+            .expect("gz write fail");
+        let archive = e.finish().expect("gz finish fail");
+
+        let out_tmp = TempDir::new("self_update_unpack_file_from_stream").expect("tempdir fail");
+        let out_path = out_tmp.path();
+        extract_file_from(
+            archive.as_slice(),
+            ArchiveKind::Tar(Some(Compression::Gz)),
+            out_path,
+            Path::new("bin/app"),
+        )
+        .expect("extract fail");
+        cmp_content(out_path.join("bin/app"), "This is a test!");
+        assert!(!out_path.join("README").exists());
+
+        match extract_file_from(
+            archive.as_slice(),
+            ArchiveKind::Tar(Some(Compression::Gz)),
+            out_path,
+            Path::new("missing"),
+        ) {
+            Err(Error::ArchiveEntryNotFound(_)) => (),
+            r => panic!("expected ArchiveEntryNotFound, got: {:?}", r),
+        }
+        assert!(extract_file_from(&b""[..], ArchiveKind::Zip, out_path, Path::new("app")).is_err());
+    }
+
+    #[test]
+    fn binary_patch() {
+        let tmp_dir = TempDir::new("self_update_binary_patch").expect("tempdir fail");
+        let old = b"#!/bin/sh\necho 'version 1.2.3'\n".repeat(20);
+        let new = b"#!/bin/sh\necho 'version 1.2.4'\n".repeat(20);
+        let mut patch = vec![];
+        bsdiff::diff(&old, &new, &mut patch).expect("diff fail");
+        let old_path = tmp_dir.path().join("app");
+        let new_path = tmp_dir.path().join("app.patched");
+        fs::write(&old_path, &old).unwrap();
+
+        apply_patch(&old_path, &patch, &new_path).expect("patch fail");
+        assert_eq!(new, fs::read(&new_path).unwrap());
+        assert!(apply_patch(&old_path, b"not a patch", &new_path).is_err());
     }
 
     #[test]
+    #[cfg(feature = "archive-zip")]
     fn unpack_zip() {
         let tmp_dir = TempDir::new("self_update_unpack_zip_src").expect("tempdir fail");
         let tmp_path = tmp_dir.path();
@@ -737,7 +2943,7 @@ mod tests {
         let out_tmp = TempDir::new("self_update_unpack_zip_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&archive_path)
-            .extract_into(&out_path)
+            .extract_into(out_path)
             .expect("extract fail");
         let out_file = out_path.join("zipped.txt");
         assert!(out_file.exists());
@@ -749,6 +2955,40 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "archive-zip")]
+    fn unpack_nested_zip_file() {
+        let tmp_dir = TempDir::new("self_update_unpack_nested_zip_src").expect("tempdir fail");
+        let tmp_path = tmp_dir.path();
+
+        let archive_path = tmp_path.join("archive.zip");
+        let archive_file = File::create(&archive_path).expect("create file fail");
+        let mut zip = zip::ZipWriter::new(archive_file);
+        let options =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("completions/app.bash", options)
+            .expect("failed starting zip file");
+        zip.write_all(b"complete -F _app app")
+            .expect("failed writing to zip");
+        zip.finish().expect("failed finishing zip");
+
+        let out_tmp = TempDir::new("self_update_unpack_nested_zip_outdir").expect("tempdir fail");
+        let out_path = out_tmp.path();
+        Extract::from_source(&archive_path)
+            .extract_file(out_path, "completions/app.bash")
+            .expect("extract fail");
+        cmp_content(
+            out_path.join("completions/app.bash"),
+            "complete -F _app app",
+        );
+
+        match Extract::from_source(&archive_path).extract_file(out_path, "missing") {
+            Err(Error::ArchiveEntryNotFound(_)) => (),
+            r => panic!("expected ArchiveEntryNotFound, got: {:?}", r),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "archive-zip")]
     fn unpack_zip_file() {
         let tmp_dir = TempDir::new("self_update_unpack_zip_src").expect("tempdir fail");
         let tmp_path = tmp_dir.path();
@@ -771,7 +3011,7 @@ mod tests {
         let out_tmp = TempDir::new("self_update_unpack_zip_outdir").expect("tempdir fail");
         let out_path = out_tmp.path();
         Extract::from_source(&archive_path)
-            .extract_file(&out_path, "zipped2.txt")
+            .extract_file(out_path, "zipped2.txt")
             .expect("extract fail");
         let out_file = out_path.join("zipped2.txt");
         assert!(out_file.exists());
@@ -0,0 +1,145 @@
+/*!
+Advisory locking to prevent concurrent updates of the same executable
+*/
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::errors::*;
+
+/// Path of the lock file guarding updates of `exe`: `.<file name>.lock` next to it
+pub(crate) fn path(exe: &Path) -> PathBuf {
+    let name = exe
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    exe.with_file_name(format!(".{}.lock", name))
+}
+
+/// An exclusive lock on a lock file, released when dropped
+#[derive(Debug)]
+pub(crate) struct UpdateLock {
+    _file: fs::File,
+}
+
+impl UpdateLock {
+    /// Lock the file at `path`, creating it if needed, without waiting
+    ///
+    /// * Errors:
+    ///     * Locked - Another process holds the lock
+    ///     * Io - Creating / locking the file
+    pub(crate) fn acquire(path: &Path) -> Result<UpdateLock> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+        match try_lock(&file) {
+            Ok(true) => Ok(UpdateLock { _file: file }),
+            Ok(false) => Err(Error::Locked(path.to_path_buf())),
+            Err(e) => Err(Error::Io(e)),
+        }
+    }
+}
+
+/// Take an exclusive lock on `file` without waiting, `false` if it's held by another
+/// process. Released when the file is closed.
+#[cfg(unix)]
+fn try_lock(file: &fs::File) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        return Ok(true);
+    }
+    let e = io::Error::last_os_error();
+    match e.kind() {
+        io::ErrorKind::WouldBlock => Ok(false),
+        _ => Err(e),
+    }
+}
+
+/// Take an exclusive lock on `file` without waiting, `false` if it's held by another
+/// process. Released when the file is closed.
+#[cfg(windows)]
+fn try_lock(file: &fs::File) -> io::Result<bool> {
+    use std::os::windows::io::AsRawHandle;
+
+    const LOCKFILE_FAIL_IMMEDIATELY: u32 = 0x1;
+    const LOCKFILE_EXCLUSIVE_LOCK: u32 = 0x2;
+    const ERROR_LOCK_VIOLATION: i32 = 33;
+    #[repr(C)]
+    struct Overlapped {
+        internal: usize,
+        internal_high: usize,
+        offset: u32,
+        offset_high: u32,
+        event: *mut std::ffi::c_void,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LockFileEx(
+            file: *mut std::ffi::c_void,
+            flags: u32,
+            reserved: u32,
+            bytes_low: u32,
+            bytes_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+    }
+
+    let mut overlapped = Overlapped {
+        internal: 0,
+        internal_high: 0,
+        offset: 0,
+        offset_high: 0,
+        event: std::ptr::null_mut(),
+    };
+    let ok = unsafe {
+        LockFileEx(
+            file.as_raw_handle() as *mut _,
+            LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    if ok != 0 {
+        return Ok(true);
+    }
+    let e = io::Error::last_os_error();
+    match e.raw_os_error() {
+        Some(ERROR_LOCK_VIOLATION) => Ok(false),
+        _ => Err(e),
+    }
+}
+
+/// Without a locking primitive, concurrent updates aren't prevented
+#[cfg(not(any(unix, windows)))]
+fn try_lock(_file: &fs::File) -> io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn exclusive() {
+        let tmp = TempDir::new("self_update_lock").expect("tempdir fail");
+        let lock_path = path(&tmp.path().join("app"));
+        assert_eq!(tmp.path().join(".app.lock"), lock_path);
+
+        let lock = UpdateLock::acquire(&lock_path).expect("lock fail");
+        match UpdateLock::acquire(&lock_path) {
+            Err(Error::Locked(ref p)) if *p == lock_path => (),
+            r => panic!("expected Locked, got: {:?}", r),
+        }
+        drop(lock);
+        UpdateLock::acquire(&lock_path).expect("relock fail");
+    }
+}
@@ -1,5 +1,13 @@
 /// Allows you to pull the version from your Cargo.toml at compile time as
-/// `MAJOR.MINOR.PATCH_PKGVERSION_PRE`
+/// `MAJOR.MINOR.PATCH[-PRE]`, without depending on `clap` for its `crate_version!`
+///
+/// ```
+/// # #[macro_use] extern crate self_update;
+/// # fn main() {
+/// let current_version = cargo_crate_version!();
+/// assert_eq!(env!("CARGO_PKG_VERSION"), current_version);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! cargo_crate_version {
     // -- Pulled from clap.rs src/macros.rs
@@ -8,21 +16,6 @@ macro_rules! cargo_crate_version {
     };
 }
 
-/// Set ssl cert env. vars to make sure openssl can find required files
-macro_rules! set_ssl_vars {
-    () => {
-        #[cfg(target_os = "linux")]
-        {
-            if ::std::env::var_os("SSL_CERT_FILE").is_none() {
-                ::std::env::set_var("SSL_CERT_FILE", "/etc/ssl/certs/ca-certificates.crt");
-            }
-            if ::std::env::var_os("SSL_CERT_DIR").is_none() {
-                ::std::env::set_var("SSL_CERT_DIR", "/etc/ssl/certs");
-            }
-        }
-    };
-}
-
 /// Helper to `print!` and immediately `flush` `stdout`
 macro_rules! print_flush {
     ($literal:expr) => {
@@ -0,0 +1,210 @@
+/*!
+User-facing strings, overridable to present updates in the user's language
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::messages::Messages;
+
+let messages = Messages {
+    checking_latest_version: "Neueste Version wird gesucht... ".into(),
+    confirm: "Fortfahren? [J/n] ".into(),
+    confirm_answers: vec!["j".into(), "ja".into()],
+    done: "Fertig".into(),
+    ..Messages::default()
+};
+let updater = self_update::backends::github::Update::configure()
+    .repo_owner("jaemk")
+    .repo_name("self_update")
+    .bin_name("self_update_example")
+    .current_version(self_update::cargo_crate_version!())
+    .messages(messages)
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::fmt::Display;
+
+/// Messages printed while updating, English by default.
+///
+/// Placeholders in braces, e.g. `{version}`, are replaced with their values. The
+/// placeholders available to each message are those of its default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Messages {
+    /// `Checking target-arch... {target}`
+    pub checking_target: String,
+    /// `Checking current version... v{version}`
+    pub checking_current_version: String,
+    /// `Checking current version... not installed at {path}`
+    pub not_installed: String,
+    /// `Checking latest released version... `, followed by `latest_version`
+    pub checking_latest_version: String,
+    /// `v{version}`
+    pub latest_version: String,
+    /// `New release found! v{current} --> v{latest}`
+    pub new_release: String,
+    /// `New release is compatible`
+    pub compatible: String,
+    /// `New release is *NOT* compatible`
+    pub incompatible: String,
+    /// ``v{version} is available but exceeds the allowed `max_upgrade` of the update policy``
+    pub exceeds_policy: String,
+    /// `Looking for tag: {tag}`
+    pub looking_for_tag: String,
+    /// `  {index}) v{version}  {date}  {notes}`, a release to choose from, see
+    /// `UpdateBuilder::select_release`
+    pub release_choice: String,
+    /// `Select a release to install [1-{count}, default 1]: `
+    pub select_release: String,
+    /// `Running under Rosetta 2, switching to the native Apple Silicon release`
+    pub rosetta: String,
+    /// `{path} differs from the released v{version}, updating discards its local changes`,
+    /// see `UpdateBuilder::local_changes`
+    pub locally_modified: String,
+    /// Summary of the update shown before asking for confirmation, with the
+    /// `{bin_name}`, `{path}`, `{asset}` and `{url}` placeholders
+    pub release_status: String,
+    /// Summary of the uninstall shown before asking for confirmation, with the
+    /// `{bin_name}`, `{path}` and `{backup_dir}` placeholders
+    pub uninstall_status: String,
+    /// `Do you want to continue? [Y/n] `
+    pub confirm: String,
+    /// Answers to `confirm` that continue, compared ignoring case. A blank
+    /// answer always continues.
+    pub confirm_answers: Vec<String>,
+    /// `Downloading...`
+    pub downloading: String,
+    /// `Downloading patch {name}...`
+    pub downloading_patch: String,
+    /// `Failed to apply patch ({error}), downloading the full release`
+    pub patch_failed: String,
+    /// `Downloading {size} changed bytes...`
+    pub downloading_changed: String,
+    /// `Differential download failed ({error}), downloading the full release`
+    pub differential_failed: String,
+    /// `Failed to cache {path} ({error})`, see `UpdateBuilder::download_cache`
+    pub cache_failed: String,
+    /// `Extracting archive... `
+    pub extracting: String,
+    /// `Replacing binary file... `
+    pub replacing: String,
+    /// `Installing binary file... `
+    pub installing: String,
+    /// `Installing companion files... `
+    pub installing_companion_files: String,
+    /// `Removing files... `
+    pub removing: String,
+    /// `Restoring v{version}... `
+    pub restoring: String,
+    /// ``Running `{command}`... ``
+    pub running: String,
+    /// `Restarting {service}... `
+    pub restarting_service: String,
+    /// `Done`
+    pub done: String,
+    /// ``Note: {dir} is not on your PATH, add it to run `{bin_name}` from anywhere``
+    pub not_in_path: String,
+    /// `{dir} isn't writable, restarting with elevated privileges...`
+    pub restarting_elevated: String,
+    /// `{dir} isn't writable, re-run the update as an administrator (e.g. with sudo)`
+    pub not_writable: String,
+    /// `{app} v{latest} is available (you have v{current})`, see `check::Notice`
+    pub update_available: String,
+    /// ``; run `{command}` ``, appended to `update_available` if there's an update command
+    pub update_command: String,
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        Self {
+            checking_target: "Checking target-arch... {target}".into(),
+            checking_current_version: "Checking current version... v{version}".into(),
+            not_installed: "Checking current version... not installed at {path}".into(),
+            checking_latest_version: "Checking latest released version... ".into(),
+            latest_version: "v{version}".into(),
+            new_release: "New release found! v{current} --> v{latest}".into(),
+            compatible: "New release is compatible".into(),
+            incompatible: "New release is *NOT* compatible".into(),
+            exceeds_policy:
+                "v{version} is available but exceeds the allowed `max_upgrade` of the update policy"
+                    .into(),
+            looking_for_tag: "Looking for tag: {tag}".into(),
+            release_choice: "  {index}) v{version}  {date}  {notes}".into(),
+            select_release: "Select a release to install [1-{count}, default 1]: ".into(),
+            rosetta: "Running under Rosetta 2, switching to the native Apple Silicon release"
+                .into(),
+            locally_modified:
+                "{path} differs from the released v{version}, updating discards its local changes"
+                    .into(),
+            release_status: "\n{bin_name} release status:\n  \
+                             * Current exe: {path}\n  \
+                             * New exe release: {asset}\n  \
+                             * New exe download url: {url}\n\n\
+                             The new release will be downloaded/extracted and the existing \
+                             binary will be replaced."
+                .into(),
+            uninstall_status: "\n{bin_name} will be uninstalled:\n  \
+                               * Exe: {path}\n  \
+                               * Backups in: {backup_dir}"
+                .into(),
+            confirm: "Do you want to continue? [Y/n] ".into(),
+            confirm_answers: vec!["y".into()],
+            downloading: "Downloading...".into(),
+            downloading_patch: "Downloading patch {name}...".into(),
+            patch_failed: "Failed to apply patch ({error}), downloading the full release".into(),
+            downloading_changed: "Downloading {size} changed bytes...".into(),
+            differential_failed:
+                "Differential download failed ({error}), downloading the full release".into(),
+            cache_failed: "Failed to cache {path} ({error})".into(),
+            extracting: "Extracting archive... ".into(),
+            replacing: "Replacing binary file... ".into(),
+            installing: "Installing binary file... ".into(),
+            installing_companion_files: "Installing companion files... ".into(),
+            removing: "Removing files... ".into(),
+            restoring: "Restoring v{version}... ".into(),
+            running: "Running `{command}`... ".into(),
+            restarting_service: "Restarting {service}... ".into(),
+            done: "Done".into(),
+            not_in_path:
+                "Note: {dir} is not on your PATH, add it to run `{bin_name}` from anywhere".into(),
+            restarting_elevated: "{dir} isn't writable, restarting with elevated privileges..."
+                .into(),
+            not_writable:
+                "{dir} isn't writable, re-run the update as an administrator (e.g. with sudo)"
+                    .into(),
+            update_available: "{app} v{latest} is available (you have v{current})".into(),
+            update_command: "; run `{command}`".into(),
+        }
+    }
+}
+
+/// Replace the `{name}` placeholders of `template` with their values
+pub(crate) fn fill(template: &str, values: &[(&str, &dyn Display)]) -> String {
+    let mut message = template.to_owned();
+    for (name, value) in values {
+        let placeholder = format!("{{{}}}", name);
+        if message.contains(&placeholder) {
+            message = message.replace(&placeholder, &value.to_string());
+        }
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_placeholders() {
+        let messages = Messages::default();
+        assert_eq!(
+            "New release found! v1.0.0 --> v1.1.0",
+            fill(
+                &messages.new_release,
+                &[("current", &"1.0.0"), ("latest", &"1.1.0")]
+            )
+        );
+        assert_eq!("v{version}", fill(&messages.latest_version, &[]));
+        assert_eq!("x {y} x", fill("{x} {y} {x}", &[("x", &'x')]));
+    }
+}
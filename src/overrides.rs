@@ -0,0 +1,176 @@
+/*!
+Environment variable overrides of updater configuration
+
+Packagers and administrators can alter update behavior without code changes.
+For an executable named `my-app` the following variables are read, taking
+precedence over the builder configuration:
+
+* `SELF_UPDATE_DISABLE`, `MY_APP_UPDATE_DISABLE` - refuse to update
+* `MY_APP_UPDATE_NO_CONFIRM` - skip (or with a false value, require) the confirmation prompt
+* `MY_APP_UPDATE_API_URL` - release api base url, e.g. for GitHub Enterprise
+* `MY_APP_UPDATE_TOKEN` - token sent with api requests
+
+Requests to `api.github.com` are otherwise authenticated with the `GH_TOKEN` or
+`GITHUB_TOKEN` token, like the `gh` cli does, unless disabled with
+`auth_token_from_env(false)`.
+
+Flags are true for `1`, `true`, `yes` or `on` (case-insensitive), and false otherwise.
+*/
+use std::env;
+
+/// Variable disabling updates of every application using this crate
+pub(crate) const DISABLE_ALL: &str = "SELF_UPDATE_DISABLE";
+
+/// Variables holding a GitHub token, in order of precedence
+const GITHUB_TOKEN_VARS: &[&str] = &["GH_TOKEN", "GITHUB_TOKEN"];
+
+/// The GitHub token of the process environment, if any
+pub(crate) fn github_token() -> Option<String> {
+    github_token_from_vars(|name| env::var(name).ok())
+}
+
+fn github_token_from_vars<F>(var: F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    GITHUB_TOKEN_VARS
+        .iter()
+        .find_map(|name| var(name).filter(|v| !v.trim().is_empty()))
+}
+
+/// Overrides read from the environment for a single executable
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct EnvOverrides {
+    /// Name of the variable that disabled updates, if any
+    pub(crate) disabled_by: Option<String>,
+    pub(crate) no_confirm: Option<bool>,
+    pub(crate) api_url: Option<String>,
+    pub(crate) auth_token: Option<String>,
+}
+
+impl EnvOverrides {
+    /// Read the overrides for `bin_name` from the process environment
+    pub(crate) fn from_env(bin_name: &str) -> Self {
+        Self::from_vars(bin_name, |name| env::var(name).ok())
+    }
+
+    fn from_vars<F>(bin_name: &str, var: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let prefix = var_prefix(bin_name);
+        let app_var = |suffix: &str| {
+            let name = format!("{}_UPDATE_{}", prefix, suffix);
+            var(&name).filter(|v| !v.is_empty()).map(|v| (name, v))
+        };
+        let disabled_by = var(DISABLE_ALL)
+            .filter(|v| is_true(v))
+            .map(|_| DISABLE_ALL.to_owned())
+            .or_else(|| {
+                app_var("DISABLE")
+                    .filter(|(_, v)| is_true(v))
+                    .map(|(n, _)| n)
+            });
+        Self {
+            disabled_by,
+            no_confirm: app_var("NO_CONFIRM").map(|(_, v)| is_true(&v)),
+            api_url: app_var("API_URL").map(|(_, v)| v.trim_end_matches('/').to_owned()),
+            auth_token: app_var("TOKEN").map(|(_, v)| v),
+        }
+    }
+}
+
+/// `my-app` -> `MY_APP`
+fn var_prefix(bin_name: &str) -> String {
+    bin_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn is_true(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn overrides(vars: &[(&str, &str)]) -> EnvOverrides {
+        let vars = vars
+            .iter()
+            .map(|&(k, v)| (k.to_owned(), v.to_owned()))
+            .collect::<HashMap<_, _>>();
+        EnvOverrides::from_vars("my-app.cli", |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn reads_app_vars() {
+        assert_eq!(EnvOverrides::default(), overrides(&[]));
+
+        let found = overrides(&[
+            ("MY_APP_CLI_UPDATE_NO_CONFIRM", "Yes"),
+            (
+                "MY_APP_CLI_UPDATE_API_URL",
+                "https://github.example.com/api/v3/",
+            ),
+            ("MY_APP_CLI_UPDATE_TOKEN", "secret"),
+            ("OTHER_APP_UPDATE_DISABLE", "1"),
+        ]);
+        assert_eq!(None, found.disabled_by);
+        assert_eq!(Some(true), found.no_confirm);
+        assert_eq!(
+            Some("https://github.example.com/api/v3"),
+            found.api_url.as_deref()
+        );
+        assert_eq!(Some("secret"), found.auth_token.as_deref());
+
+        let found = overrides(&[("MY_APP_CLI_UPDATE_NO_CONFIRM", "0")]);
+        assert_eq!(Some(false), found.no_confirm);
+    }
+
+    #[test]
+    fn github_tokens() {
+        let vars = |vars: &'static [(&str, &str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(None, github_token_from_vars(vars(&[])));
+        assert_eq!(
+            Some("gh".to_owned()),
+            github_token_from_vars(vars(&[("GITHUB_TOKEN", "ci"), ("GH_TOKEN", "gh")]))
+        );
+        assert_eq!(
+            Some("ci".to_owned()),
+            github_token_from_vars(vars(&[("GITHUB_TOKEN", "ci"), ("GH_TOKEN", "")]))
+        );
+    }
+
+    #[test]
+    fn disable() {
+        let found = overrides(&[("SELF_UPDATE_DISABLE", "true")]);
+        assert_eq!(Some(DISABLE_ALL), found.disabled_by.as_deref());
+
+        let found = overrides(&[
+            ("SELF_UPDATE_DISABLE", "0"),
+            ("MY_APP_CLI_UPDATE_DISABLE", "on"),
+        ]);
+        assert_eq!(
+            Some("MY_APP_CLI_UPDATE_DISABLE"),
+            found.disabled_by.as_deref()
+        );
+    }
+}
@@ -0,0 +1,228 @@
+/*!
+Packaging of built executables into the release assets the updater installs, for
+publishing scripts
+
+The archive is named `{bin}-{version}-{target}.tar.gz` (or `.zip`), holding just the
+executable at its root under `bin_name`, which is where `UpdateBuilder::bin_name`
+looks for it by default. Each checksum is written to a sidecar file next to it, e.g.
+`<archive>.sha256`, as found by `UpdateBuilder::verify_checksum`.
+
+```no_run
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::package::Packager;
+
+let packaged = Packager::new("app", "1.2.0", "x86_64-unknown-linux-gnu")
+    .package("target/release/app", "dist")?;
+println!("upload {:?} and {:?}", packaged.archive, packaged.checksums);
+# Ok(())
+# }
+```
+*/
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backends::github::ArchiveFormat;
+use crate::checksum::Algorithm;
+use crate::errors::*;
+
+/// Packages an executable into a release asset, see the module documentation
+#[derive(Clone, Debug)]
+pub struct Packager {
+    bin_name: String,
+    version: String,
+    target: String,
+    format: ArchiveFormat,
+    checksums: Vec<Algorithm>,
+}
+
+/// The files written by `Packager::package`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Packaged {
+    /// The release asset
+    pub archive: PathBuf,
+    /// The checksum sidecar files of the asset, in the order of the algorithms
+    pub checksums: Vec<PathBuf>,
+}
+
+impl Packager {
+    /// Package the executable named `bin_name` of `version` built for `target`. The
+    /// format defaults to zip for Windows targets and tar.gz for others, with a
+    /// SHA-256 checksum.
+    pub fn new(bin_name: &str, version: &str, target: &str) -> Self {
+        Self {
+            bin_name: bin_name.to_owned(),
+            version: version.to_owned(),
+            target: target.to_owned(),
+            format: if target.contains("windows") {
+                ArchiveFormat::Zip
+            } else {
+                ArchiveFormat::Tar
+            },
+            checksums: vec![Algorithm::Sha256],
+        }
+    }
+
+    /// Set the archive format, `ArchiveFormat::Tar` archives are gzip compressed
+    pub fn format(&mut self, format: ArchiveFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the algorithms of the checksum sidecar files, none if empty
+    pub fn checksums(&mut self, algorithms: &[Algorithm]) -> &mut Self {
+        self.checksums = algorithms.to_vec();
+        self
+    }
+
+    /// File name of the archive
+    pub fn archive_name(&self) -> String {
+        let ext = match self.format {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::Tar => "tar.gz",
+        };
+        let bin = self.bin_name.trim_end_matches(".exe");
+        format!("{}-{}-{}.{}", bin, self.version, self.target, ext)
+    }
+
+    /// Package the executable at `bin_path` into `out_dir`, created if missing,
+    /// replacing any earlier package
+    ///
+    /// * Errors:
+    ///     * Io - Reading the executable or writing the package
+    ///     * Config - The format or a checksum algorithm requires a disabled feature
+    pub fn package<A: AsRef<Path>, B: AsRef<Path>>(
+        &self,
+        bin_path: A,
+        out_dir: B,
+    ) -> Result<Packaged> {
+        let out_dir = out_dir.as_ref();
+        fs::create_dir_all(out_dir)?;
+        let name = self.archive_name();
+        let archive = out_dir.join(&name);
+        if let Err(e) = self.write_archive(bin_path.as_ref(), &archive) {
+            let _ = fs::remove_file(&archive);
+            return Err(e);
+        }
+        let mut checksums = vec![];
+        for algorithm in &self.checksums {
+            let sum = algorithm.file_checksum(&archive)?;
+            let sidecar = out_dir.join(format!("{}{}", name, algorithm.sidecar_suffixes()[0]));
+            fs::write(&sidecar, format!("{}  {}\n", sum, name))?;
+            checksums.push(sidecar);
+        }
+        Ok(Packaged { archive, checksums })
+    }
+
+    #[cfg(all(feature = "archive-tar", feature = "compression-flate2"))]
+    fn write_tar_gz(&self, bin_path: &Path, archive: &Path) -> Result<()> {
+        let bin = fs::File::open(bin_path)?;
+        let metadata = bin.metadata()?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(metadata.len());
+        header.set_mode(0o755);
+        if let Ok(modified) = metadata.modified() {
+            let since_epoch = modified.duration_since(std::time::UNIX_EPOCH);
+            header.set_mtime(since_epoch.map(|d| d.as_secs()).unwrap_or(0));
+        }
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(archive)?,
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        builder.append_data(&mut header, &self.bin_name, bin)?;
+        builder.into_inner()?.finish()?.sync_all()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "archive-zip")]
+    fn write_zip(&self, bin_path: &Path, archive: &Path) -> Result<()> {
+        let options = zip::write::FileOptions::default().unix_permissions(0o755);
+        let mut zip = zip::ZipWriter::new(fs::File::create(archive)?);
+        zip.start_file(self.bin_name.as_str(), options)?;
+        std::io::copy(&mut fs::File::open(bin_path)?, &mut zip)?;
+        zip.finish()?.sync_all()?;
+        Ok(())
+    }
+
+    #[cfg_attr(
+        not(all(
+            feature = "archive-tar",
+            feature = "archive-zip",
+            feature = "compression-flate2"
+        )),
+        allow(unused_variables)
+    )]
+    fn write_archive(&self, bin_path: &Path, archive: &Path) -> Result<()> {
+        match self.format {
+            #[cfg(all(feature = "archive-tar", feature = "compression-flate2"))]
+            ArchiveFormat::Tar => self.write_tar_gz(bin_path, archive),
+            #[cfg(not(feature = "archive-tar"))]
+            ArchiveFormat::Tar => Err(crate::unsupported("tar", "archive-tar")),
+            #[cfg(all(feature = "archive-tar", not(feature = "compression-flate2")))]
+            ArchiveFormat::Tar => Err(crate::unsupported("gzip", "compression-flate2")),
+            #[cfg(feature = "archive-zip")]
+            ArchiveFormat::Zip => self.write_zip(bin_path, archive),
+            #[cfg(not(feature = "archive-zip"))]
+            ArchiveFormat::Zip => Err(crate::unsupported("zip", "archive-zip")),
+        }
+    }
+}
+
+#[cfg(all(
+    test,
+    feature = "archive-tar",
+    feature = "archive-zip",
+    feature = "compression-flate2"
+))]
+mod tests {
+    use super::*;
+    use crate::checksum;
+    use crate::Extract;
+    use tempdir::TempDir;
+
+    #[test]
+    fn packages_extract() {
+        let tmp = TempDir::new("self_update_package").expect("tempdir fail");
+        let bin = tmp.path().join("app");
+        fs::write(&bin, "built").unwrap();
+        for format in &[ArchiveFormat::Tar, ArchiveFormat::Zip] {
+            let packaged = Packager::new("app", "1.2.0", "x86_64-unknown-linux-gnu")
+                .format(*format)
+                .checksums(&[Algorithm::Sha256, Algorithm::Sha512])
+                .package(&bin, tmp.path().join("dist"))
+                .unwrap();
+            let name = packaged.archive.file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with("app-1.2.0-x86_64-unknown-linux-gnu."));
+
+            let out = tmp.path().join(format!("{:?}", format));
+            fs::create_dir(&out).unwrap();
+            Extract::from_source(&packaged.archive)
+                .extract_file(&out, "app")
+                .unwrap();
+            assert_eq!("built", fs::read_to_string(out.join("app")).unwrap());
+            let file = fs::File::open(&packaged.archive).unwrap();
+            let mode = match format {
+                ArchiveFormat::Tar => {
+                    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+                    let entry = tar.entries().unwrap().next().unwrap().unwrap();
+                    entry.header().mode().unwrap()
+                }
+                ArchiveFormat::Zip => {
+                    let mut zip = zip::ZipArchive::new(file).unwrap();
+                    let entry = zip.by_index(0).unwrap();
+                    entry.unix_mode().unwrap()
+                }
+            };
+            assert_eq!(0o755, mode & 0o777);
+
+            assert_eq!(2, packaged.checksums.len());
+            let sidecar = fs::read_to_string(&packaged.checksums[1]).unwrap();
+            let sum = checksum::find_checksum(&sidecar, name, Algorithm::Sha512).unwrap();
+            Algorithm::Sha512.verify(&packaged.archive, &sum).unwrap();
+        }
+        assert_eq!(
+            "app-1.2.0-x86_64-pc-windows-msvc.zip",
+            Packager::new("app.exe", "1.2.0", "x86_64-pc-windows-msvc").archive_name()
+        );
+    }
+}
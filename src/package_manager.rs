@@ -0,0 +1,420 @@
+/*!
+Detection of executables installed by a package manager
+
+Replacing such an executable would leave the package manager with a stale view of
+what's installed, so updates should be done through the package manager instead.
+*/
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+
+use crate::errors::*;
+
+/// A package manager an executable was installed with, and the name of its package
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PackageManager {
+    /// Homebrew / Linuxbrew formula
+    Homebrew(String),
+    /// Debian / Ubuntu package
+    Apt(String),
+    /// Termux package
+    Termux(String),
+    /// Fedora / RHEL / openSUSE package
+    Rpm(String),
+    /// Scoop app
+    Scoop(String),
+    /// winget package id
+    Winget(String),
+    /// Chocolatey package
+    Chocolatey(String),
+    /// `cargo install`ed crate
+    Cargo(String),
+}
+
+impl PackageManager {
+    /// Detect the package manager that installed the executable at `exe`, if any
+    pub fn detect<P: AsRef<Path>>(exe: P) -> Option<PackageManager> {
+        let exe = exe.as_ref();
+        // package managers commonly link executables into a `bin` directory
+        let resolved = fs::canonicalize(exe).unwrap_or_else(|_| exe.to_path_buf());
+        if let Some(formula) = component_after(&resolved, &["Cellar"]) {
+            return Some(PackageManager::Homebrew(formula));
+        }
+        if let Some(app) = component_after(exe, &["scoop", "apps"]) {
+            return Some(PackageManager::Scoop(app));
+        }
+        if component_after(exe, &["scoop", "shims"]).is_some() {
+            if let Some(app) = scoop_shim_app(exe) {
+                return Some(PackageManager::Scoop(app));
+            }
+        }
+        // winget links portable packages into `WinGet\Links`
+        if let Some(package) = component_after(&resolved, &["WinGet", "Packages"]) {
+            // `<package id>_<source>`
+            let id = package.split('_').next().unwrap_or_default().to_owned();
+            return Some(PackageManager::Winget(id));
+        }
+        if let Some(package) = component_after(exe, &["chocolatey", "lib"]) {
+            return Some(PackageManager::Chocolatey(package));
+        }
+        if component_after(exe, &["chocolatey", "bin"]).is_some() {
+            // shims don't record their package, which is commonly named after the executable
+            if let Some(stem) = exe.file_stem().and_then(|s| s.to_str()) {
+                return Some(PackageManager::Chocolatey(stem.to_lowercase()));
+            }
+        }
+        if let Some(name) = exe.file_name().and_then(|n| n.to_str()) {
+            // only if cargo recorded it, the dir also holds e.g. cargo-binstall installs
+            if cargo_bin_dir().is_some_and(|dir| exe.parent() == Some(&dir)) {
+                if let Some(crate_name) = cargo_crate_for_bin(name) {
+                    return Some(PackageManager::Cargo(crate_name));
+                }
+            }
+        }
+        if is_system_path(&resolved) {
+            if let Some(package) = query_owner("dpkg-query", &["-S"], &resolved) {
+                // `<package>[:<arch>]: <path>`
+                let package = package.split(':').next().unwrap_or_default().to_owned();
+                if crate::is_termux() {
+                    return Some(PackageManager::Termux(package));
+                }
+                return Some(PackageManager::Apt(package));
+            }
+            if let Some(package) =
+                query_owner("rpm", &["-qf", "--queryformat", "%{NAME}"], &resolved)
+            {
+                return Some(PackageManager::Rpm(package));
+            }
+        }
+        None
+    }
+
+    /// Command users should run to update the package
+    pub fn upgrade_command(&self) -> String {
+        match *self {
+            PackageManager::Homebrew(ref p) => format!("brew upgrade {}", p),
+            PackageManager::Apt(ref p) => format!("sudo apt install --only-upgrade {}", p),
+            PackageManager::Termux(ref p) => format!("pkg upgrade {}", p),
+            PackageManager::Rpm(ref p) => format!("sudo dnf upgrade {}", p),
+            PackageManager::Scoop(ref p) => format!("scoop update {}", p),
+            PackageManager::Winget(ref p) => format!("winget upgrade --exact --id {}", p),
+            PackageManager::Chocolatey(ref p) => format!("choco upgrade {} -y", p),
+            PackageManager::Cargo(ref p) => format!("cargo install --force {}", p),
+        }
+    }
+
+    /// Whether the package can be updated by `upgrade`, without elevated privileges
+    /// or a build from source
+    pub fn can_upgrade(&self) -> bool {
+        self.upgrade_args().is_some()
+    }
+
+    /// Update the package with the package manager
+    ///
+    /// * Errors:
+    ///     * Config - The package can't be updated by running its package manager,
+    ///       see `can_upgrade`
+    ///     * Update - The package manager failed
+    ///     * Io - Running the package manager
+    pub fn upgrade(&self) -> Result<()> {
+        let args = self.upgrade_args().ok_or_else(|| {
+            format_err!(
+                Error::Config,
+                "Packages can't be updated through {}, run `{}`",
+                self,
+                self.upgrade_command()
+            )
+        })?;
+        crate::run_command(args[0], &args[1..]).map(drop)
+    }
+
+    fn upgrade_args(&self) -> Option<Vec<&str>> {
+        Some(match *self {
+            PackageManager::Homebrew(ref p) => vec!["brew", "upgrade", p],
+            // `scoop` is a script, run by its `.cmd` / `.ps1` wrapper
+            PackageManager::Scoop(ref p) => vec!["cmd", "/C", "scoop", "update", p],
+            PackageManager::Winget(ref p) => vec![
+                "winget",
+                "upgrade",
+                "--exact",
+                "--id",
+                p,
+                "--accept-package-agreements",
+                "--accept-source-agreements",
+            ],
+            PackageManager::Chocolatey(ref p) => vec!["choco", "upgrade", p, "-y"],
+            PackageManager::Apt(_)
+            | PackageManager::Termux(_)
+            | PackageManager::Rpm(_)
+            | PackageManager::Cargo(_) => return None,
+        })
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            PackageManager::Homebrew(_) => "Homebrew",
+            PackageManager::Apt(_) => "apt",
+            PackageManager::Termux(_) => "Termux pkg",
+            PackageManager::Rpm(_) => "rpm",
+            PackageManager::Scoop(_) => "Scoop",
+            PackageManager::Winget(_) => "winget",
+            PackageManager::Chocolatey(_) => "Chocolatey",
+            PackageManager::Cargo(_) => "cargo install",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How to update executables installed by Homebrew
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Homebrew {
+    /// Treat Homebrew like the other package managers, see `defer_to_package_manager`
+    #[default]
+    Defer,
+    /// Check the version of the formula instead of GitHub releases, failing with
+    /// `Error::PackageManaged` if it's newer than the installed version
+    Check,
+    /// Check the version of the formula instead of GitHub releases, and run
+    /// `brew upgrade` if it's newer than the installed version
+    Upgrade,
+}
+
+/// Version of `formula` in the local Homebrew taps, without its revision suffix.
+/// Taps are only as recent as the last `brew update`.
+///
+/// * Errors:
+///     * Update - `brew info` failed, e.g. an unknown formula
+///     * Release - Unexpected `brew info` output
+///     * Io - Running `brew`
+pub fn homebrew_formula_version(formula: &str) -> Result<String> {
+    let output = crate::run_command("brew", &["info", "--json=v2", formula])?;
+    let json = serde_json::from_str::<serde_json::Value>(&output)?;
+    stable_formula_version(&json)
+        .ok_or_else(|| format_err!(Error::Release, "No stable version of formula {}", formula))
+}
+
+/// `{"formulae": [{"versions": {"stable": "1.2.3"}, "revision": 1}]}`, the revision
+/// of a formula isn't part of the version, e.g. `1.2.3_1`
+fn stable_formula_version(info: &serde_json::Value) -> Option<String> {
+    info["formulae"][0]["versions"]["stable"]
+        .as_str()
+        .map(|version| match version.rsplit_once('_') {
+            Some((version, revision)) if revision.parse::<u32>().is_ok() => version.to_owned(),
+            _ => version.to_owned(),
+        })
+}
+
+/// The path component following the (case-insensitive) components `parents`
+fn component_after(path: &Path, parents: &[&str]) -> Option<String> {
+    let components = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(c) => c.to_str(),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    components
+        .windows(parents.len() + 1)
+        .find(|w| {
+            w.iter()
+                .zip(parents)
+                .all(|(c, p)| c.eq_ignore_ascii_case(p))
+        })
+        .map(|w| w[parents.len()].to_owned())
+}
+
+/// The app of the Scoop shim `exe`, from the `path = "..\apps\<app>\current\<exe>"`
+/// line of the `.shim` file next to it
+fn scoop_shim_app(exe: &Path) -> Option<String> {
+    let shim = fs::read_to_string(exe.with_extension("shim")).ok()?;
+    shim_app(&shim)
+}
+
+fn shim_app(shim: &str) -> Option<String> {
+    let target = shim
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "path")?
+        .1
+        .trim()
+        .trim_matches('"');
+    // the shim is written on Windows, split on its separators on any platform
+    let components = target.split(['\\', '/']).collect::<Vec<_>>();
+    components
+        .windows(2)
+        .find(|w| w[0].eq_ignore_ascii_case("apps"))
+        .map(|w| w[1].to_owned())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+fn cargo_bin_dir() -> Option<PathBuf> {
+    cargo_home().map(|home| home.join("bin"))
+}
+
+/// Look up the crate that installed `bin_name` in cargo's install metadata
+fn cargo_crate_for_bin(bin_name: &str) -> Option<String> {
+    let content = fs::read(cargo_home()?.join(".crates2.json")).ok()?;
+    let json = serde_json::from_slice::<serde_json::Value>(&content).ok()?;
+    crate_for_bin(&json, bin_name)
+}
+
+/// `{"installs": {"<crate> <version> (<source>)": {"bins": ["<bin>", ..]}}}`
+fn crate_for_bin(crates: &serde_json::Value, bin_name: &str) -> Option<String> {
+    crates["installs"]
+        .as_object()?
+        .iter()
+        .find(|(_, install)| {
+            install["bins"]
+                .as_array()
+                .is_some_and(|bins| bins.iter().any(|b| b.as_str() == Some(bin_name)))
+        })
+        .and_then(|(id, _)| id.split(' ').next())
+        .map(String::from)
+}
+
+/// Paths owned by the system package manager, as opposed to e.g. `/usr/local` or `$HOME`
+fn is_system_path(path: &Path) -> bool {
+    if crate::is_termux() {
+        // Termux packages install into its `$PREFIX`, e.g. `/data/data/com.termux/files/usr`
+        return env::var_os("PREFIX").is_some_and(|prefix| {
+            let prefix = Path::new(&prefix);
+            path.starts_with(prefix) && !path.starts_with(prefix.join("local"))
+        });
+    }
+    cfg!(unix)
+        && ["/usr", "/bin", "/sbin", "/opt"]
+            .iter()
+            .any(|dir| path.starts_with(dir))
+        && !path.starts_with("/usr/local")
+}
+
+/// Run a package manager's "which package owns this file" query
+fn query_owner(program: &str, args: &[&str], path: &Path) -> Option<String> {
+    let output = process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .stderr(process::Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout
+        .lines()
+        .next()
+        .map(|l| l.trim().to_owned())
+        .filter(|l| !l.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempdir::TempDir;
+
+    #[test]
+    fn path_components() {
+        let brew = Path::new("/opt/homebrew/Cellar/mytool/1.2.3/bin/mytool");
+        assert_eq!(
+            Some("mytool".to_owned()),
+            component_after(brew, &["Cellar"])
+        );
+        let scoop = Path::new("/users/me/Scoop/apps/mytool/current/mytool.exe");
+        assert_eq!(
+            Some("mytool".to_owned()),
+            component_after(scoop, &["scoop", "apps"])
+        );
+        assert_eq!(
+            None,
+            component_after(Path::new("/usr/local/bin/mytool"), &["Cellar"])
+        );
+        let choco = Path::new("/ProgramData/chocolatey/lib/mytool/tools/mytool.exe");
+        assert_eq!(
+            Some(PackageManager::Chocolatey("mytool".into())),
+            PackageManager::detect(choco)
+        );
+        let winget = Path::new("/users/me/AppData/Local/Microsoft/WinGet/Packages/Me.MyTool_Microsoft.Winget.Source_8wekyb3d8bbwe/mytool.exe");
+        assert_eq!(
+            Some(PackageManager::Winget("Me.MyTool".into())),
+            PackageManager::detect(winget)
+        );
+    }
+
+    #[test]
+    fn scoop_shims() {
+        let shim = "path = \"C:\\Users\\me\\scoop\\apps\\mytool\\current\\mytool.exe\"\r\nargs = --flag\r\n";
+        assert_eq!(Some("mytool".to_owned()), shim_app(shim));
+        assert_eq!(None, shim_app("args = --flag"));
+    }
+
+    #[test]
+    fn cargo_crate_lookup() {
+        let crates = json!({
+            "installs": {
+                "ripgrep 11.0.2 (registry+https://github.com/rust-lang/crates.io-index)": {
+                    "bins": ["rg"]
+                }
+            }
+        });
+        assert_eq!(Some("ripgrep".to_owned()), crate_for_bin(&crates, "rg"));
+        assert_eq!(None, crate_for_bin(&crates, "fd"));
+    }
+
+    #[test]
+    fn cargo_installs() {
+        let home = TempDir::new("self_update_cargo_home").expect("tempdir fail");
+        let exe = home.path().join("bin").join("app");
+        fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        fs::write(&exe, "").unwrap();
+        env::set_var("CARGO_HOME", home.path());
+        // e.g. installed by cargo-binstall or copied there
+        assert_eq!(None, PackageManager::detect(&exe));
+        let crates = json!({"installs": {"app-cli 1.0.0 (registry)": {"bins": ["app"]}}});
+        fs::write(home.path().join(".crates2.json"), crates.to_string()).unwrap();
+        assert_eq!(
+            Some(PackageManager::Cargo("app-cli".to_owned())),
+            PackageManager::detect(&exe)
+        );
+    }
+
+    #[test]
+    fn formula_version() {
+        let info = json!({"formulae": [{"name": "mytool", "versions": {"stable": "1.2.3"}}]});
+        assert_eq!(Some("1.2.3".to_owned()), stable_formula_version(&info));
+        let info = json!({"formulae": [{"versions": {"stable": "1.2.3_2"}}]});
+        assert_eq!(Some("1.2.3".to_owned()), stable_formula_version(&info));
+        assert_eq!(None, stable_formula_version(&json!({"formulae": []})));
+    }
+
+    #[test]
+    fn upgrade_commands() {
+        assert_eq!(
+            "brew upgrade mytool",
+            PackageManager::Homebrew("mytool".into()).upgrade_command()
+        );
+        assert_eq!(
+            "cargo install --force ripgrep",
+            PackageManager::Cargo("ripgrep".into()).upgrade_command()
+        );
+        assert!(PackageManager::Winget("Me.MyTool".into()).can_upgrade());
+        assert!(!PackageManager::Apt("mytool".into()).can_upgrade());
+        assert!(PackageManager::Apt("mytool".into()).upgrade().is_err());
+    }
+
+    #[test]
+    fn system_paths() {
+        assert_eq!(cfg!(unix), is_system_path(Path::new("/usr/bin/mytool")));
+        assert!(!is_system_path(Path::new("/usr/local/bin/mytool")));
+    }
+}
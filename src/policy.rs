@@ -0,0 +1,187 @@
+/*!
+Admin-controlled update policy files
+
+A policy file lets administrators restrict how an application updates itself,
+taking precedence over the configuration chosen by the application:
+
+```toml
+# refuse to update at all
+disable = false
+# always install this release instead of the latest
+pin_version = "v1.4.2"
+# send all requests through a proxy
+proxy = "http://proxy.example.com:3128"
+# largest allowed version bump: "major" (default), "minor" or "patch"
+max_upgrade = "minor"
+```
+*/
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use semver::Version;
+
+use crate::errors::*;
+
+/// Largest version bump an update may make
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MaxUpgrade {
+    /// Any newer version
+    #[default]
+    Major,
+    /// Newer versions with the same major version
+    Minor,
+    /// Newer versions with the same major and minor version
+    Patch,
+}
+
+/// Update policy loaded from a policy file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Policy {
+    /// Refuse to update
+    pub disable: bool,
+    /// Release tag to install instead of the latest release
+    pub pin_version: Option<String>,
+    /// Proxy url all requests are sent through
+    pub proxy: Option<String>,
+    /// Largest version bump an update may make
+    pub max_upgrade: MaxUpgrade,
+}
+
+impl Policy {
+    /// Default policy file location in the platform config directory, e.g.
+    /// `~/.config/<app_name>/update-policy.toml` on linux
+    ///
+    /// * Errors:
+    ///     * Config - The platform has no config directory
+    pub fn default_path(app_name: &str) -> Result<PathBuf> {
+        let dir = dirs::config_dir().ok_or_else(|| {
+            Error::Config("Unable to determine the platform config directory".into())
+        })?;
+        Ok(dir.join(app_name).join("update-policy.toml"))
+    }
+
+    /// Load the policy file at `path`, returning `None` if it doesn't exist
+    ///
+    /// * Errors:
+    ///     * Io - Reading the file
+    ///     * Config - The file isn't a valid policy
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Option<Policy>> {
+        let path = path.as_ref();
+        let content = match fs::read_to_string(path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            r => r?,
+        };
+        Policy::from_toml(&content)
+            .map(Some)
+            .map_err(|e| format_err!(Error::Config, "Invalid policy file {:?}: {}", path, e))
+    }
+
+    /// Parse a policy from toml
+    ///
+    /// * Errors:
+    ///     * Config - Invalid toml, unknown keys, or values of the wrong type
+    pub fn from_toml(content: &str) -> Result<Policy> {
+        let table = match content.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => bail!(Error::Config, "policy must be a table"),
+            Err(e) => bail!(Error::Config, "{}", e),
+        };
+        let mut policy = Policy::default();
+        for (key, value) in &table {
+            match (key.as_str(), value) {
+                ("disable", toml::Value::Boolean(b)) => policy.disable = *b,
+                ("pin_version", toml::Value::String(s)) => policy.pin_version = Some(s.clone()),
+                ("proxy", toml::Value::String(s)) => policy.proxy = Some(s.clone()),
+                ("max_upgrade", toml::Value::String(s)) => {
+                    policy.max_upgrade = match s.as_str() {
+                        "major" => MaxUpgrade::Major,
+                        "minor" => MaxUpgrade::Minor,
+                        "patch" => MaxUpgrade::Patch,
+                        _ => bail!(
+                            Error::Config,
+                            "`max_upgrade` must be one of \"major\", \"minor\", \"patch\""
+                        ),
+                    }
+                }
+                ("disable", _) | ("pin_version", _) | ("proxy", _) | ("max_upgrade", _) => {
+                    bail!(Error::Config, "invalid value for `{}`", key)
+                }
+                _ => bail!(Error::Config, "unknown policy key `{}`", key),
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Check if updating from `current` to `latest` stays within `max_upgrade`
+    ///
+    /// * Errors:
+    ///     * SemVer - Version parsing
+    pub fn allows(&self, current: &str, latest: &str) -> Result<bool> {
+        let current = Version::parse(current.trim_start_matches('v'))?;
+        let latest = Version::parse(latest.trim_start_matches('v'))?;
+        Ok(match self.max_upgrade {
+            MaxUpgrade::Major => true,
+            MaxUpgrade::Minor => latest.major == current.major,
+            MaxUpgrade::Patch => latest.major == current.major && latest.minor == current.minor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempdir::TempDir;
+
+    #[test]
+    fn parse() {
+        let policy = Policy::from_toml(
+            r#"
+            disable = true
+            pin_version = "v1.4.2"
+            proxy = "http://proxy.example.com:3128"
+            max_upgrade = "minor"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            Policy {
+                disable: true,
+                pin_version: Some("v1.4.2".into()),
+                proxy: Some("http://proxy.example.com:3128".into()),
+                max_upgrade: MaxUpgrade::Minor,
+            },
+            policy
+        );
+        assert_eq!(Policy::default(), Policy::from_toml("").unwrap());
+
+        assert!(Policy::from_toml("disable = \"yes\"").is_err());
+        assert!(Policy::from_toml("max_upgrade = \"all\"").is_err());
+        assert!(Policy::from_toml("disabled = true").is_err());
+    }
+
+    #[test]
+    fn max_upgrade() {
+        let mut policy = Policy::default();
+        assert!(policy.allows("1.2.3", "2.0.0").unwrap());
+        policy.max_upgrade = MaxUpgrade::Minor;
+        assert!(policy.allows("1.2.3", "v1.3.0").unwrap());
+        assert!(!policy.allows("1.2.3", "2.0.0").unwrap());
+        policy.max_upgrade = MaxUpgrade::Patch;
+        assert!(policy.allows("1.2.3", "1.2.9").unwrap());
+        assert!(!policy.allows("1.2.3", "1.3.0").unwrap());
+    }
+
+    #[test]
+    fn load_missing() {
+        let tmp = TempDir::new("self_update_policy").expect("tempdir fail");
+        assert_eq!(None, Policy::load(tmp.path().join("policy.toml")).unwrap());
+        fs::write(tmp.path().join("policy.toml"), "disable = true").unwrap();
+        assert!(
+            Policy::load(tmp.path().join("policy.toml"))
+                .unwrap()
+                .unwrap()
+                .disable
+        );
+    }
+}
@@ -0,0 +1,368 @@
+/*!
+Appearance of the download progress bar
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::progress::BarStyle;
+
+// `[=====>    ] 52% 1.20MB/s` fitted to the terminal width
+let style = BarStyle::new()
+    .fit_terminal()
+    .elapsed(false)
+    .size(false)
+    .eta(false)
+    .percent(true)
+    .speed(true);
+self_update::backends::github::Update::configure()
+    .set_progress_style(style.into())
+    // ...
+#   .repo_owner("jaemk")
+#   .repo_name("self_update")
+#   .bin_name("self_update_example")
+#   .current_version(self_update::cargo_crate_version!())
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+
+use crate::Callback;
+
+/// Interval between two progress reports unless configured otherwise
+pub(crate) const DEFAULT_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Step of an update a `DownloadProgress` report belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Phase {
+    /// Looking up the release to install
+    Checking,
+    /// Downloading the release asset, `bytes` of `total` are done
+    Downloading,
+    /// Checking the downloaded asset against its checksum. Only reported when that's
+    /// a step of its own, checksums are otherwise computed while downloading.
+    Verifying,
+    /// Extracting the executable from the downloaded archive
+    Extracting,
+    /// Replacing the installed executable
+    Installing,
+    /// The update is installed
+    Done,
+}
+
+impl Phase {
+    /// The phases of an update, in order. Phases that don't apply to an update,
+    /// e.g. `Extracting` a plain executable, are skipped.
+    pub const ALL: &'static [Phase] = &[
+        Phase::Checking,
+        Phase::Downloading,
+        Phase::Verifying,
+        Phase::Extracting,
+        Phase::Installing,
+        Phase::Done,
+    ];
+
+    /// Share of the whole update this phase accounts for, in percent
+    pub fn weight(self) -> u32 {
+        match self {
+            Phase::Checking => 5,
+            Phase::Downloading => 75,
+            Phase::Verifying => 5,
+            Phase::Extracting => 10,
+            Phase::Installing => 5,
+            Phase::Done => 0,
+        }
+    }
+
+    /// Percentage of the whole update done when this phase starts
+    fn start(self) -> u32 {
+        Self::ALL
+            .iter()
+            .take_while(|phase| **phase != self)
+            .map(|phase| phase.weight())
+            .sum()
+    }
+}
+
+/// Progress of an update or download, delivered to an `on_progress` callback to
+/// drive e.g. a GUI progress widget
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes downloaded so far
+    pub bytes: u64,
+    /// Size of the download, if known
+    pub total: Option<u64>,
+    pub phase: Phase,
+}
+
+impl DownloadProgress {
+    /// Progress of the whole update from 0 to 100, weighting each phase by
+    /// `Phase::weight`, to drive a single progress bar across all phases. The
+    /// download counts as started until its size is known.
+    pub fn overall_percent(&self) -> f64 {
+        let done = match (self.phase, self.total) {
+            (Phase::Downloading, Some(total)) if total > 0 => {
+                self.bytes.min(total) as f64 / total as f64
+            }
+            _ => 0.0,
+        };
+        f64::from(self.phase.start()) + f64::from(self.phase.weight()) * done
+    }
+}
+
+/// Progress callback
+pub(crate) type ProgressHook = dyn Fn(DownloadProgress) + Send + Sync;
+
+/// Delivers download progress to a callback at most once per `interval`
+pub(crate) struct Reporter {
+    hook: Callback<ProgressHook>,
+    interval: Duration,
+    total: Option<u64>,
+    last: Mutex<Option<Instant>>,
+}
+
+impl Reporter {
+    pub(crate) fn new(
+        hook: Callback<ProgressHook>,
+        interval: Duration,
+        total: Option<u64>,
+    ) -> Self {
+        Self {
+            hook,
+            interval,
+            total,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Report `bytes` downloaded if the interval has passed since the last report,
+    /// or regardless with `force`
+    pub(crate) fn report(&self, bytes: u64, force: bool) {
+        {
+            let mut last = self.last.lock().expect("progress reporter poisoned");
+            let now = Instant::now();
+            if !force && last.is_some_and(|last| now.duration_since(last) < self.interval) {
+                return;
+            }
+            *last = Some(now);
+        }
+        (self.hook.0)(DownloadProgress {
+            bytes,
+            total: self.total,
+            phase: Phase::Downloading,
+        });
+    }
+}
+
+/// Builds the `ProgressStyle` of download progress bars. The default is
+/// `[{elapsed}] [{bar:40}] {bytes}/{total_bytes} ({eta})` with `=>-` as fill characters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BarStyle {
+    width: Option<usize>,
+    chars: String,
+    template: Option<String>,
+    elapsed: bool,
+    percent: bool,
+    size: bool,
+    speed: bool,
+    eta: bool,
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        Self {
+            width: Some(40),
+            chars: "=>-".into(),
+            template: None,
+            elapsed: true,
+            percent: false,
+            size: true,
+            speed: false,
+            eta: true,
+        }
+    }
+}
+
+impl BarStyle {
+    /// The default style
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the width of the bar itself in columns, excluding the other fields
+    pub fn width(mut self, columns: usize) -> Self {
+        self.width = Some(columns);
+        self
+    }
+
+    /// Size the bar to fill the terminal's width next to the other fields
+    pub fn fit_terminal(mut self) -> Self {
+        self.width = None;
+        self
+    }
+
+    /// Set the characters the bar is drawn with: the filled part, the head and the
+    /// unfilled part, e.g. `#>-` or `█▉▊▋▌▍▎▏ ` for a smoother bar
+    pub fn progress_chars(mut self, chars: &str) -> Self {
+        self.chars = chars.to_owned();
+        self
+    }
+
+    /// Use an `indicatif` template instead of the fields below, e.g.
+    /// `{bar:30} {percent}%`. The average download speed is available as `{prefix}`.
+    pub fn template(mut self, template: &str) -> Self {
+        self.template = Some(template.to_owned());
+        self
+    }
+
+    /// Toggle the elapsed time, defaults to `true`
+    pub fn elapsed(mut self, show: bool) -> Self {
+        self.elapsed = show;
+        self
+    }
+
+    /// Toggle the completed percentage, defaults to `false`
+    pub fn percent(mut self, show: bool) -> Self {
+        self.percent = show;
+        self
+    }
+
+    /// Toggle the downloaded and total size, defaults to `true`
+    pub fn size(mut self, show: bool) -> Self {
+        self.size = show;
+        self
+    }
+
+    /// Toggle the average download speed, defaults to `false`
+    pub fn speed(mut self, show: bool) -> Self {
+        self.speed = show;
+        self
+    }
+
+    /// Toggle the estimated time remaining, defaults to `true`
+    pub fn eta(mut self, show: bool) -> Self {
+        self.eta = show;
+        self
+    }
+
+    /// The `indicatif` template of this style
+    pub fn to_template(&self) -> String {
+        if let Some(ref template) = self.template {
+            return template.clone();
+        }
+        let mut fields = vec![];
+        if self.elapsed {
+            fields.push("[{elapsed_precise}]".to_owned());
+        }
+        fields.push(match self.width {
+            Some(width) => format!("[{{bar:{}}}]", width),
+            None => "[{wide_bar}]".to_owned(),
+        });
+        if self.percent {
+            fields.push("{percent}%".to_owned());
+        }
+        if self.size {
+            fields.push("{bytes}/{total_bytes}".to_owned());
+        }
+        if self.speed {
+            fields.push("{prefix}".to_owned());
+        }
+        if self.eta {
+            fields.push("({eta})".to_owned());
+        }
+        fields.push("{msg}".to_owned());
+        fields.join(" ")
+    }
+
+    /// The `indicatif` style to pass to `set_progress_style`
+    pub fn build(&self) -> ProgressStyle {
+        ProgressStyle::default_bar()
+            .template(&self.to_template())
+            .progress_chars(&self.chars)
+    }
+}
+
+impl From<BarStyle> for ProgressStyle {
+    fn from(style: BarStyle) -> ProgressStyle {
+        style.build()
+    }
+}
+
+/// Move `bar` to `received` bytes, showing the average speed since `elapsed` as its prefix
+pub(crate) fn update_bar(bar: &ProgressBar, received: u64, size: u64, elapsed: Duration) {
+    bar.set_position(received.min(size));
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 {
+        let speed = (received as f64 / secs) as u64;
+        bar.set_prefix(&format!("{}/s", HumanBytes(speed)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn reports_throttled() {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let received = reports.clone();
+        let hook: Callback<ProgressHook> = Callback(Arc::new(move |p: DownloadProgress| {
+            received.lock().unwrap().push(p.bytes)
+        }));
+        let reporter = Reporter::new(hook, Duration::from_secs(3600), Some(10));
+        for bytes in 1..=10 {
+            reporter.report(bytes, bytes == 10);
+        }
+        assert_eq!(vec![1, 10], *reports.lock().unwrap());
+    }
+
+    #[test]
+    fn overall_progress() {
+        assert_eq!(
+            100,
+            Phase::ALL.iter().map(|phase| phase.weight()).sum::<u32>()
+        );
+        let progress = |phase, bytes, total| {
+            DownloadProgress {
+                bytes,
+                total,
+                phase,
+            }
+            .overall_percent()
+        };
+        assert_eq!(0.0, progress(Phase::Checking, 0, None));
+        assert_eq!(5.0, progress(Phase::Downloading, 10, None));
+        assert_eq!(42.5, progress(Phase::Downloading, 50, Some(100)));
+        assert_eq!(80.0, progress(Phase::Downloading, 200, Some(100)));
+        assert_eq!(85.0, progress(Phase::Extracting, 0, None));
+        assert_eq!(100.0, progress(Phase::Done, 0, None));
+    }
+
+    #[test]
+    fn templates() {
+        assert_eq!(
+            "[{elapsed_precise}] [{bar:40}] {bytes}/{total_bytes} ({eta}) {msg}",
+            BarStyle::new().to_template()
+        );
+        assert_eq!(
+            "[{wide_bar}] {percent}% {prefix} {msg}",
+            BarStyle::new()
+                .fit_terminal()
+                .elapsed(false)
+                .size(false)
+                .eta(false)
+                .percent(true)
+                .speed(true)
+                .to_template()
+        );
+        assert_eq!(
+            "{bar:10}",
+            BarStyle::new().width(20).template("{bar:10}").to_template()
+        );
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+Limits on how often an application silently updates itself
+
+An `UpdateSchedule` persists when updates were last checked for and installed, and is
+consulted before any network call, so an application updating itself in the
+background on every start only contacts the release server when it's due.
+
+```
+# use std::time::Duration;
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::schedule::UpdateSchedule;
+
+let updater = self_update::backends::github::Update::configure()
+    .repo_owner("jaemk")
+    .repo_name("self_update")
+    .bin_name("self_update_example")
+    .current_version(self_update::cargo_crate_version!())
+    .no_confirm(true)
+    .build()?;
+
+// check at most daily, install at most weekly, and only between 02:00 and 04:00
+let installed = UpdateSchedule::new("self_update_example")?
+    .check_interval(Duration::from_secs(24 * 60 * 60))
+    .install_interval(Duration::from_secs(7 * 24 * 60 * 60))
+    .install_window(2, 4)
+    .run(&updater)?;
+if let Some(version) = installed {
+    println!("updated to v{}", version);
+}
+# Ok(())
+# }
+```
+*/
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::backends::Updater;
+use crate::errors::*;
+use crate::http::unix_now;
+use crate::version;
+
+/// When to check for and install updates, with the times of the last check and
+/// install persisted in a state file
+#[derive(Clone, Debug)]
+pub struct UpdateSchedule {
+    path: PathBuf,
+    check_interval: Duration,
+    install_interval: Duration,
+    install_window: Option<(u32, u32)>,
+}
+
+/// Times of the last check and install, in seconds since the unix epoch
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct State {
+    checked_at: Option<u64>,
+    installed_at: Option<u64>,
+}
+
+impl UpdateSchedule {
+    /// Store the schedule state in the platform data directory, e.g.
+    /// `~/.local/share/<app_name>/self_update_schedule.json` on linux
+    ///
+    /// * Errors:
+    ///     * Config - The platform has no data directory
+    pub fn new(app_name: &str) -> Result<Self> {
+        let dir = dirs::data_dir().ok_or_else(|| {
+            Error::Config("Unable to determine the platform data directory".into())
+        })?;
+        Ok(Self::at_path(
+            dir.join(app_name).join("self_update_schedule.json"),
+        ))
+    }
+
+    /// Store the schedule state in the file at `path`
+    pub fn at_path<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            check_interval: Duration::from_secs(24 * 60 * 60),
+            install_interval: Duration::from_secs(0),
+            install_window: None,
+        }
+    }
+
+    /// Set the least time between checks for a new release, defaults to 24 hours
+    pub fn check_interval(&mut self, interval: Duration) -> &mut Self {
+        self.check_interval = interval;
+        self
+    }
+
+    /// Set the least time between installs, defaults to none
+    pub fn install_interval(&mut self, interval: Duration) -> &mut Self {
+        self.install_interval = interval;
+        self
+    }
+
+    /// Only install, and check for, updates from `start_hour` until `end_hour` local
+    /// time, e.g. `(2, 4)` for 02:00 to 04:00. The window wraps around midnight if
+    /// `end_hour` is before `start_hour`, and is the whole day if they're equal.
+    /// Hours are taken modulo 24. Defaults to the whole day.
+    pub fn install_window(&mut self, start_hour: u32, end_hour: u32) -> &mut Self {
+        self.install_window = Some((start_hour % 24, end_hour % 24));
+        self
+    }
+
+    /// Path of the file the schedule state is stored in
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether `check_interval` has passed since the last recorded check
+    pub fn check_due(&self) -> bool {
+        elapsed(self.load().checked_at, unix_now(), self.check_interval)
+    }
+
+    /// Whether `install_interval` has passed since the last recorded install, and
+    /// it's within the install window
+    pub fn install_due(&self) -> bool {
+        let hour = local_hour(unix_now());
+        elapsed(self.load().installed_at, unix_now(), self.install_interval)
+            && in_window(self.install_window, hour)
+    }
+
+    /// Record a check for a new release made now
+    ///
+    /// * Errors:
+    ///     * Io - Writing the state file
+    pub fn record_check(&self) -> Result<()> {
+        let mut state = self.load();
+        state.checked_at = Some(unix_now());
+        self.store(state)
+    }
+
+    /// Record an install made now
+    ///
+    /// * Errors:
+    ///     * Io - Writing the state file
+    pub fn record_install(&self) -> Result<()> {
+        let mut state = self.load();
+        state.installed_at = Some(unix_now());
+        self.store(state)
+    }
+
+    /// Check for and install a newer release with `updater` if both are due,
+    /// returning the installed version. Nothing is requested from the release server
+    /// otherwise. A failed check still counts as a check, so a failing server isn't
+    /// retried until `check_interval` has passed.
+    ///
+    /// * Errors:
+    ///     * Any error of `updater` looking up the latest version or updating
+    ///     * SemVer - The current or latest version isn't valid semver
+    ///     * Io - Writing the state file
+    pub fn run(&self, updater: &dyn Updater) -> Result<Option<String>> {
+        if !(self.check_due() && self.install_due()) {
+            return Ok(None);
+        }
+        self.record_check()?;
+        let latest = updater.latest_version()?;
+        if !version::bump_is_greater(&updater.current_version(), &latest)? {
+            return Ok(None);
+        }
+        let status = updater.update()?;
+        if !status.updated() {
+            return Ok(None);
+        }
+        self.record_install()?;
+        Ok(Some(status.version().to_owned()))
+    }
+
+    /// Forget the recorded checks and installs
+    pub fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => Ok(r?),
+        }
+    }
+
+    fn load(&self) -> State {
+        let state = fs::read(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_slice::<serde_json::Value>(&content).ok());
+        match state {
+            Some(state) => State {
+                checked_at: state["checked_at"].as_u64(),
+                installed_at: state["installed_at"].as_u64(),
+            },
+            None => State::default(),
+        }
+    }
+
+    fn store(&self, state: State) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let state = json!({ "checked_at": state.checked_at, "installed_at": state.installed_at });
+        fs::write(&self.path, serde_json::to_vec(&state)?)?;
+        Ok(())
+    }
+}
+
+/// Whether `interval` has passed since `at`, or nothing happened yet. A time in
+/// the future, e.g. after the clock was set back, counts as having passed.
+fn elapsed(at: Option<u64>, now: u64, interval: Duration) -> bool {
+    match at.and_then(|at| now.checked_sub(at)) {
+        Some(age) => age >= interval.as_secs(),
+        None => true,
+    }
+}
+
+/// Whether `hour` is in the `window` from its start hour until its end hour
+fn in_window(window: Option<(u32, u32)>, hour: u32) -> bool {
+    match window {
+        None => true,
+        Some((start, end)) if start == end => true,
+        Some((start, end)) if start < end => start <= hour && hour < end,
+        Some((start, end)) => hour >= start || hour < end,
+    }
+}
+
+/// The local hour of the day at `time`, in seconds since the unix epoch
+#[cfg(unix)]
+fn local_hour(time: u64) -> u32 {
+    let t = time as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+        return utc_hour(time);
+    }
+    tm.tm_hour as u32
+}
+
+/// The local hour of the day now, `time` is only used if it can't be determined
+#[cfg(windows)]
+fn local_hour(time: u64) -> u32 {
+    #[repr(C)]
+    struct SystemTime {
+        year: u16,
+        month: u16,
+        day_of_week: u16,
+        day: u16,
+        hour: u16,
+        minute: u16,
+        second: u16,
+        milliseconds: u16,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetLocalTime(time: *mut SystemTime);
+    }
+
+    let mut now: SystemTime = unsafe { std::mem::zeroed() };
+    unsafe { GetLocalTime(&mut now) };
+    if now.year == 0 {
+        return utc_hour(time);
+    }
+    u32::from(now.hour)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn local_hour(time: u64) -> u32 {
+    utc_hour(time)
+}
+
+fn utc_hour(time: u64) -> u32 {
+    (time / 3600 % 24) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+    use std::cell::Cell;
+    use tempdir::TempDir;
+
+    struct FakeUpdater {
+        latest: &'static str,
+        updates: Cell<usize>,
+    }
+
+    impl Updater for FakeUpdater {
+        fn current_version(&self) -> String {
+            "1.0.0".into()
+        }
+
+        fn latest_version(&self) -> Result<String> {
+            Ok(self.latest.into())
+        }
+
+        fn update(&self) -> Result<Status> {
+            self.updates.set(self.updates.get() + 1);
+            Ok(Status::Updated(self.latest.into()))
+        }
+    }
+
+    #[test]
+    fn windows_and_intervals() {
+        assert!(in_window(None, 13));
+        assert!(in_window(Some((2, 4)), 3));
+        assert!(!in_window(Some((2, 4)), 4));
+        assert!(in_window(Some((22, 2)), 23));
+        assert!(in_window(Some((22, 2)), 1));
+        assert!(!in_window(Some((22, 2)), 12));
+        assert!(in_window(Some((5, 5)), 12));
+
+        let day = Duration::from_secs(24 * 60 * 60);
+        assert!(elapsed(None, 1000, day));
+        assert!(!elapsed(Some(1000), 1000 + 60, day));
+        assert!(elapsed(Some(1000), 1000 + day.as_secs(), day));
+        assert!(elapsed(Some(2000), 1000, day));
+        assert_eq!(1, utc_hour(3600 * 25));
+    }
+
+    #[test]
+    fn runs_when_due() {
+        let tmp = TempDir::new("self_update_schedule").expect("tempdir fail");
+        let mut schedule = UpdateSchedule::at_path(tmp.path().join("nested").join("state.json"));
+        let updater = FakeUpdater {
+            latest: "1.1.0",
+            updates: Cell::new(0),
+        };
+        assert_eq!(Some("1.1.0".into()), schedule.run(&updater).unwrap());
+        assert!(!schedule.check_due());
+        assert_eq!(None, schedule.run(&updater).unwrap());
+        assert_eq!(1, updater.updates.get());
+
+        schedule
+            .check_interval(Duration::from_secs(0))
+            .install_interval(Duration::from_secs(60 * 60));
+        assert!(schedule.check_due());
+        assert!(!schedule.install_due());
+        assert_eq!(None, schedule.run(&updater).unwrap());
+
+        schedule.clear().unwrap();
+        let up_to_date = FakeUpdater {
+            latest: "1.0.0",
+            updates: Cell::new(0),
+        };
+        assert_eq!(None, schedule.run(&up_to_date).unwrap());
+        assert_eq!(0, up_to_date.updates.get());
+        assert!(schedule.install_due());
+    }
+}
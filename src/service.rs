@@ -0,0 +1,227 @@
+/*!
+Restarting the service run from an updated executable
+
+Replacing the executable of a daemon doesn't affect the running process, so the
+service is restarted once the new release is installed. The executable of a running
+Windows service can't be replaced, so Windows services are stopped before replacing it
+and started again afterwards, restoring the previous executable if the service fails
+to start.
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::service::Service;
+
+self_update::backends::github::Update::configure()
+    .restart_service(Service::systemd("app.service"))
+    // ...
+#   .repo_owner("jaemk")
+#   .repo_name("self_update")
+#   .bin_name("self_update_example")
+#   .current_version(self_update::cargo_crate_version!())
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::fmt;
+
+use crate::errors::*;
+use crate::run_command as run;
+
+/// A service running the updated executable
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Service {
+    /// A systemd unit, restarted with `systemctl`. Linux only.
+    Systemd {
+        unit: String,
+        /// Whether it's a unit of the user's service manager (`systemctl --user`)
+        user: bool,
+    },
+    /// A Windows service, stopped and started with `net`. Windows only.
+    Windows { name: String },
+}
+
+impl Service {
+    /// The system-wide systemd `unit`, e.g. `app.service`
+    pub fn systemd(unit: &str) -> Service {
+        Service::Systemd {
+            unit: unit.to_owned(),
+            user: false,
+        }
+    }
+
+    /// The systemd `unit` of the user's service manager
+    pub fn systemd_user(unit: &str) -> Service {
+        Service::Systemd {
+            unit: unit.to_owned(),
+            user: true,
+        }
+    }
+
+    /// The Windows service `name`, as known to the service control manager
+    pub fn windows(name: &str) -> Service {
+        Service::Windows {
+            name: name.to_owned(),
+        }
+    }
+
+    /// * Errors:
+    ///     * Config - The service manager isn't available on this platform
+    pub(crate) fn check_supported(&self) -> Result<()> {
+        match *self {
+            Service::Systemd { .. } if !cfg!(target_os = "linux") => {
+                bail!(
+                    Error::Config,
+                    "systemd units can only be restarted on Linux"
+                )
+            }
+            Service::Windows { .. } if !cfg!(windows) => {
+                bail!(
+                    Error::Config,
+                    "Windows services can only be updated on Windows"
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the service must be stopped for its executable to be replaced
+    pub(crate) fn stops_for_replace(&self) -> bool {
+        matches!(*self, Service::Windows { .. })
+    }
+
+    /// Stop the service if it's running, returning whether it was
+    ///
+    /// * Errors:
+    ///     * Update - The service manager failed to query or stop the service
+    ///     * Io - Running the service manager
+    pub(crate) fn stop(&self) -> Result<bool> {
+        match *self {
+            Service::Systemd { ref unit, user } => {
+                let mut args = systemctl_args(user);
+                args.extend(["stop", unit.as_str()]);
+                run("systemctl", &args)?;
+                Ok(true)
+            }
+            Service::Windows { ref name } => {
+                let query = run("sc", &["query", name])?;
+                if sc_state(&query) == Some("STOPPED") {
+                    return Ok(false);
+                }
+                run("net", &["stop", name])?;
+                Ok(true)
+            }
+        }
+    }
+
+    /// Start the stopped service
+    ///
+    /// * Errors:
+    ///     * Update - The service manager failed to start the service
+    ///     * Io - Running the service manager
+    pub(crate) fn start(&self) -> Result<()> {
+        match *self {
+            Service::Systemd { ref unit, user } => {
+                let mut args = systemctl_args(user);
+                args.extend(["start", unit.as_str()]);
+                run("systemctl", &args).map(drop)
+            }
+            Service::Windows { ref name } => run("net", &["start", name]).map(drop),
+        }
+    }
+
+    /// Restart the service. When the service is the process running the update,
+    /// the restart is only queued as the process is stopped by it.
+    ///
+    /// * Errors:
+    ///     * Update - The service manager failed to restart the service
+    ///     * Io - Running the service manager
+    pub(crate) fn restart(&self, updates_self: bool) -> Result<()> {
+        match *self {
+            Service::Systemd { ref unit, user } => {
+                let args = systemctl_restart_args(unit, user, updates_self);
+                run("systemctl", &args).map(drop)
+            }
+            Service::Windows { .. } => {
+                self.stop()?;
+                self.start()
+            }
+        }
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Service::Systemd { ref unit, .. } => write!(f, "{}", unit),
+            Service::Windows { ref name } => write!(f, "{}", name),
+        }
+    }
+}
+
+fn systemctl_args(user: bool) -> Vec<&'static str> {
+    if user {
+        vec!["--user"]
+    } else {
+        vec![]
+    }
+}
+
+fn systemctl_restart_args(unit: &str, user: bool, no_block: bool) -> Vec<&str> {
+    let mut args = systemctl_args(user);
+    args.push("restart");
+    if no_block {
+        args.push("--no-block");
+    }
+    args.push(unit);
+    args
+}
+
+/// The state in `sc query` output, e.g. `RUNNING` in `STATE : 4  RUNNING`
+fn sc_state(output: &str) -> Option<&str> {
+    output
+        .lines()
+        .find(|line| line.trim_start().starts_with("STATE"))?
+        .split_once(':')?
+        .1
+        .split_whitespace()
+        .nth(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn systemctl_args() {
+        assert_eq!(
+            vec!["restart", "app.service"],
+            systemctl_restart_args("app.service", false, false)
+        );
+        assert_eq!(
+            vec!["--user", "restart", "--no-block", "app.service"],
+            systemctl_restart_args("app.service", true, true)
+        );
+        assert_eq!(
+            cfg!(target_os = "linux"),
+            Service::systemd("app").check_supported().is_ok()
+        );
+        assert_eq!(
+            cfg!(windows),
+            Service::windows("app").check_supported().is_ok()
+        );
+        assert_eq!(
+            "app.service",
+            Service::systemd_user("app.service").to_string()
+        );
+    }
+
+    #[test]
+    fn sc_query_state() {
+        let output = "\r\nSERVICE_NAME: app\r\n        TYPE               : 10  WIN32_OWN_PROCESS\r\n        \
+                      STATE              : 1  STOPPED\r\n        WIN32_EXIT_CODE    : 0  (0x0)\r\n";
+        assert_eq!(Some("STOPPED"), sc_state(output));
+        assert_eq!(None, sc_state("[SC] OpenService FAILED 1060"));
+    }
+}
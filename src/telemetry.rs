@@ -0,0 +1,57 @@
+/*!
+Records of update attempts, for apps reporting update success rates
+
+The crate never sends these anywhere itself, an `on_attempt` hook decides what to do
+with them.
+
+```
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::telemetry::{Outcome, UpdateRecord};
+
+self_update::backends::github::Update::configure()
+    .on_attempt(|record: &UpdateRecord| {
+        if let Outcome::Failed(category) = record.outcome {
+            eprintln!("update from v{} failed: {:?}", record.current_version, category);
+        }
+    })
+    // ...
+#   .repo_owner("jaemk")
+#   .repo_name("self_update")
+#   .bin_name("self_update_example")
+#   .current_version(self_update::cargo_crate_version!())
+    .build()?;
+# Ok(())
+# }
+```
+*/
+use std::time::Duration;
+
+use crate::errors::ErrorCategory;
+
+/// How an update attempt ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// The current version is the latest
+    UpToDate,
+    /// A new release was installed
+    Updated,
+    /// The update failed, with the category of the error
+    Failed(ErrorCategory),
+}
+
+/// An update attempt, passed to the `on_attempt` hook once it's over
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UpdateRecord {
+    /// Version installed before the attempt
+    pub current_version: String,
+    /// Version installed by the attempt, if it updated
+    pub new_version: Option<String>,
+    /// Time the attempt took, including waiting for confirmation
+    pub duration: Duration,
+    pub outcome: Outcome,
+}
+
+/// Hook called with the record of each update attempt
+pub(crate) type AttemptHook = dyn Fn(&UpdateRecord) + Send + Sync;
@@ -0,0 +1,1459 @@
+/*!
+Local HTTP server emulating the GitHub release api, for testing update flows offline
+
+Releases are served from a directory of fixtures, with one sub-directory per release
+tag containing the release's assets:
+
+```text
+fixtures/
+    v1.0.0/
+        app-x86_64-unknown-linux-gnu.tar.gz
+    v1.1.0/
+        app-x86_64-unknown-linux-gnu.tar.gz
+```
+
+The server answers the release listing (paginated with `Link` headers), latest
+release and release-by-tag api endpoints of any repo, and serves assets, also through
+the api's asset endpoint, honoring `Range` requests. Api responses carry an `ETag` and conditional requests for
+unchanged content are answered with `304 Not Modified`.
+
+```no_run
+# fn run() -> Result<(), Box<dyn ::std::error::Error>> {
+use self_update::test_server::ReleaseServer;
+
+let server = ReleaseServer::start("tests/fixtures/releases")?;
+let status = self_update::backends::github::Update::configure()
+    .api_url(&server.api_url())
+    .repo_owner("owner")
+    .repo_name("app")
+    .bin_name("app")
+    .bin_install_path("target/test-install/app")
+    .current_version("1.0.0")
+    .no_confirm(true)
+    .build()?
+    .update()?;
+# Ok(())
+# }
+```
+*/
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::errors::*;
+
+/// Releases per page of a listing unless requested otherwise, like GitHub
+const DEFAULT_PER_PAGE: usize = 30;
+
+/// A running release server, stopped when dropped
+#[derive(Debug)]
+pub struct ReleaseServer {
+    api_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl ReleaseServer {
+    /// Serve the releases in `fixtures` on a free local port
+    ///
+    /// * Errors:
+    ///     * Io - Binding the port
+    pub fn start<P: AsRef<Path>>(fixtures: P) -> Result<ReleaseServer> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let api_url = format!("http://{}", listener.local_addr()?);
+        let server = Server {
+            fixtures: fixtures.as_ref().to_path_buf(),
+            api_url: api_url.clone(),
+        };
+        let requests = Arc::new(Mutex::new(vec![]));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (log, stop) = (requests.clone(), shutdown.clone());
+        thread::spawn(move || {
+            let server = Arc::new(server);
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let (server, log) = (server.clone(), log.clone());
+                if let Ok(stream) = stream {
+                    thread::spawn(move || server.handle(stream, &log));
+                }
+            }
+        });
+        Ok(ReleaseServer {
+            api_url,
+            requests,
+            shutdown,
+        })
+    }
+
+    /// Base url of the emulated api, for the backend's `api_url` setting
+    pub fn api_url(&self) -> String {
+        self.api_url.clone()
+    }
+
+    /// Paths (including the query) of the requests received so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().expect("request log poisoned").clone()
+    }
+}
+
+impl Drop for ReleaseServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up the accepting thread so it notices the shutdown
+        let _ = TcpStream::connect(self.api_url.trim_start_matches("http://"));
+    }
+}
+
+struct Server {
+    fixtures: PathBuf,
+    api_url: String,
+}
+
+/// The parts of a request the server looks at
+struct Request {
+    path: String,
+    query: Vec<(String, String)>,
+    range: Option<(u64, Option<u64>)>,
+    if_none_match: Option<String>,
+    if_range: Option<String>,
+    accept: Option<String>,
+}
+
+struct Reply {
+    status: &'static str,
+    headers: Vec<String>,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn status(status: &'static str) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    fn not_found() -> Self {
+        let mut reply = Self::status("404 Not Found");
+        reply.body = br#"{"message":"Not Found"}"#.to_vec();
+        reply
+    }
+}
+
+impl Server {
+    fn handle(&self, mut stream: TcpStream, log: &Mutex<Vec<String>>) {
+        let request = match read_request(&mut stream) {
+            Ok(request) => request,
+            Err(_) => return,
+        };
+        log.lock()
+            .expect("request log poisoned")
+            .push(if request.query.is_empty() {
+                request.path.clone()
+            } else {
+                let query = request
+                    .query
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>();
+                format!("{}?{}", request.path, query.join("&"))
+            });
+        let reply = self
+            .route(&request)
+            .unwrap_or_else(|_| Reply::status("500 Internal Server Error"));
+        let mut head = format!(
+            "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n",
+            reply.status,
+            reply.body.len()
+        );
+        for header in &reply.headers {
+            head.push_str(header);
+            head.push_str("\r\n");
+        }
+        head.push_str("\r\n");
+        let _ = stream
+            .write_all(head.as_bytes())
+            .and_then(|_| stream.write_all(&reply.body));
+    }
+
+    fn route(&self, request: &Request) -> Result<Reply> {
+        let segments = request
+            .path
+            .trim_matches('/')
+            .split('/')
+            .collect::<Vec<_>>();
+        match segments.as_slice() {
+            ["repos", _, _, "releases"] => self.list(request),
+            ["repos", _, _, "releases", "latest"] => match self.releases()?.first() {
+                Some(tag) => self.api_reply(request, self.release_json(tag)?),
+                None => Ok(Reply::not_found()),
+            },
+            ["repos", _, _, "releases", "tags", tag]
+                if self.releases()?.contains(&tag.to_string()) =>
+            {
+                self.api_reply(request, self.release_json(tag)?)
+            }
+            ["download", tag, name] => self.asset(request, tag, name),
+            // the api serves the content of assets when asked for it
+            ["repos", _, _, "releases", "assets", tag, name]
+                if request.accept.as_deref() == Some("application/octet-stream") =>
+            {
+                self.asset(request, tag, name)
+            }
+            _ => Ok(Reply::not_found()),
+        }
+    }
+
+    /// Release tags, newest first
+    fn releases(&self) -> Result<Vec<String>> {
+        let mut tags = fs::read_dir(&self.fixtures)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect::<Vec<_>>();
+        tags.sort_by_cached_key(|tag| {
+            let version = semver::Version::parse(tag.trim_start_matches('v')).ok();
+            (version, tag.clone())
+        });
+        tags.reverse();
+        Ok(tags)
+    }
+
+    fn release_json(&self, tag: &str) -> Result<serde_json::Value> {
+        let mut names = fs::read_dir(self.fixtures.join(tag))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect::<Vec<_>>();
+        names.sort();
+        let assets = names
+            .iter()
+            .map(|name| {
+                let content = fs::read(self.fixtures.join(tag).join(name))?;
+                Ok(json!({
+                    "name": name,
+                    "size": content.len(),
+                    "digest": format!("sha256:{:x}", Sha256::digest(&content)),
+                    "url": format!("{}/repos/owner/repo/releases/assets/{}/{}", self.api_url, tag, name),
+                    "browser_download_url": format!("{}/download/{}/{}", self.api_url, tag, name),
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(json!({
+            "tag_name": tag,
+            "name": tag,
+            "body": "",
+            "created_at": "2020-01-01T00:00:00Z",
+            "prerelease": false,
+            "assets": assets,
+        }))
+    }
+
+    fn list(&self, request: &Request) -> Result<Reply> {
+        let param = |name: &str, default: usize| {
+            request
+                .query
+                .iter()
+                .find(|(k, _)| k == name)
+                .and_then(|(_, v)| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default)
+        };
+        let per_page = param("per_page", DEFAULT_PER_PAGE);
+        let page = param("page", 1);
+        let tags = self.releases()?;
+        let releases = tags
+            .iter()
+            .skip((page - 1) * per_page)
+            .take(per_page)
+            .map(|tag| self.release_json(tag))
+            .collect::<Result<Vec<_>>>()?;
+        let mut reply = self.api_reply(request, json!(releases))?;
+        if page * per_page < tags.len() {
+            reply.headers.push(format!(
+                "link: <{}{}?per_page={}&page={}>; rel=\"next\"",
+                self.api_url,
+                request.path,
+                per_page,
+                page + 1
+            ));
+        }
+        Ok(reply)
+    }
+
+    /// A json reply, or `304 Not Modified` if the client has it already
+    fn api_reply(&self, request: &Request, body: serde_json::Value) -> Result<Reply> {
+        let body = serde_json::to_vec(&body)?;
+        let etag = format!("\"{:x}\"", Sha256::digest(&body));
+        if request.if_none_match.as_ref() == Some(&etag) {
+            let mut reply = Reply::status("304 Not Modified");
+            reply.headers.push(format!("etag: {}", etag));
+            return Ok(reply);
+        }
+        Ok(Reply {
+            status: "200 OK",
+            headers: vec![
+                "content-type: application/json".into(),
+                format!("etag: {}", etag),
+            ],
+            body,
+        })
+    }
+
+    fn asset(&self, request: &Request, tag: &str, name: &str) -> Result<Reply> {
+        let path = self.fixtures.join(tag).join(name);
+        if name.starts_with('.') || !path.is_file() {
+            return Ok(Reply::not_found());
+        }
+        let content = fs::read(path)?;
+        let len = content.len() as u64;
+        let etag = format!("\"{:x}\"", Sha256::digest(&content));
+        // a range of content that changed since the `If-Range` one is the whole content
+        let range = request
+            .range
+            .filter(|_| request.if_range.as_ref().is_none_or(|e| *e == etag));
+        let (start, end) = match range {
+            Some((start, end)) if start < len => (start, end.map_or(len - 1, |e| e.min(len - 1))),
+            Some(_) => {
+                let mut reply = Reply::status("416 Range Not Satisfiable");
+                reply
+                    .headers
+                    .push(format!("content-range: bytes */{}", len));
+                return Ok(reply);
+            }
+            None => {
+                return Ok(Reply {
+                    status: "200 OK",
+                    headers: vec!["accept-ranges: bytes".into(), format!("etag: {}", etag)],
+                    body: content,
+                })
+            }
+        };
+        Ok(Reply {
+            status: "206 Partial Content",
+            headers: vec![
+                format!("content-range: bytes {}-{}/{}", start, end, len),
+                format!("etag: {}", etag),
+            ],
+            body: content[start as usize..=end as usize].to_vec(),
+        })
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> io::Result<Request> {
+    let mut head = vec![];
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    let head = String::from_utf8_lossy(&head);
+    let mut lines = head.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .unwrap_or("/");
+    // absolute-form targets are sent to proxies
+    let target = match target.find("://") {
+        Some(i) => target[i + 3..]
+            .find('/')
+            .map_or("/", |j| &target[i + 3 + j..]),
+        None => target,
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect();
+    let mut request = Request {
+        path: path.to_owned(),
+        query,
+        range: None,
+        if_none_match: None,
+        if_range: None,
+        accept: None,
+    };
+    for line in lines {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim().to_lowercase(), value.trim()),
+            None => continue,
+        };
+        match name.as_str() {
+            "range" => {
+                request.range = value
+                    .strip_prefix("bytes=")
+                    .and_then(|r| r.split_once('-'))
+                    .and_then(|(s, e)| Some((s.parse().ok()?, e.parse().ok())));
+            }
+            "if-none-match" => request.if_none_match = Some(value.to_owned()),
+            "if-range" => request.if_range = Some(value.to_owned()),
+            "accept" => request.accept = Some(value.to_owned()),
+            _ => (),
+        }
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::github;
+    use crate::blocks::BlockIndex;
+    use crate::checksum::Algorithm;
+    use crate::progress::Phase;
+    use crate::{Download, NonInteractive};
+    use std::time::Duration;
+    use tempdir::TempDir;
+
+    fn fixtures(dir: &Path, versions: &[&str]) {
+        for version in versions {
+            let release = dir.join(format!("v{}", version));
+            fs::create_dir_all(&release).unwrap();
+            fs::write(release.join("app-test-target"), version).unwrap();
+        }
+    }
+
+    /// Updater of the app in `fixtures` installed at `install_path` as `version`,
+    /// without output or prompts
+    fn updater(
+        server: &ReleaseServer,
+        install_path: &Path,
+        version: &str,
+    ) -> github::UpdateBuilder {
+        let mut builder = github::Update::configure();
+        builder
+            .api_url(&server.api_url())
+            .repo_owner("owner")
+            .repo_name("app")
+            .target("test-target")
+            .bin_name("app")
+            .bin_install_path(install_path)
+            .current_version(version)
+            .show_output(false)
+            .no_confirm(true);
+        builder
+    }
+
+    #[test]
+    fn paginated_listing() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let versions = (0..150).map(|i| format!("1.{}.0", i)).collect::<Vec<_>>();
+        fixtures(
+            tmp.path(),
+            &versions.iter().map(String::as_str).collect::<Vec<_>>(),
+        );
+        let server = ReleaseServer::start(tmp.path()).unwrap();
+        let releases = github::ReleaseList::configure()
+            .api_url(&server.api_url())
+            .repo_owner("owner")
+            .repo_name("app")
+            .build()
+            .unwrap()
+            .fetch()
+            .unwrap();
+        assert_eq!(150, releases.len());
+        assert_eq!("1.149.0", releases[0].version());
+        assert_eq!("1.0.0", releases[149].version());
+        assert_eq!(2, server.requests().len());
+
+        let mut iter = github::ReleaseList::configure()
+            .api_url(&server.api_url())
+            .repo_owner("owner")
+            .repo_name("app")
+            .build()
+            .unwrap()
+            .iter();
+        assert_eq!("1.149.0", iter.next().unwrap().unwrap().version());
+        assert_eq!(3, server.requests().len());
+    }
+
+    #[test]
+    fn update_from_fixtures() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0", "1.2.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let phases = Arc::new(Mutex::new(vec![]));
+        let percents = Arc::new(Mutex::new(vec![]));
+        let update = |current: &str, version: Option<&str>| {
+            let reported = phases.clone();
+            let overall = percents.clone();
+            let mut builder = updater(&server, &install_path, current);
+            builder
+                .show_download_progress(false)
+                .on_progress(move |progress| {
+                    reported.lock().unwrap().push(progress.phase);
+                    overall.lock().unwrap().push(progress.overall_percent());
+                });
+            if let Some(version) = version {
+                builder.target_version_tag(version);
+            }
+            builder.build().unwrap().update().unwrap()
+        };
+
+        let status = update("1.0.0", None);
+        assert!(status.updated());
+        assert_eq!("1.2.0", status.version());
+        assert_eq!("1.2.0", fs::read_to_string(&install_path).unwrap());
+        phases.lock().unwrap().dedup();
+        assert_eq!(
+            vec![
+                Phase::Checking,
+                Phase::Downloading,
+                Phase::Installing,
+                Phase::Done
+            ],
+            *phases.lock().unwrap()
+        );
+        let percents = percents.lock().unwrap().clone();
+        assert!(percents.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(Some(&100.0), percents.last());
+
+        assert!(update("1.2.0", None).uptodate());
+
+        let status = update("1.2.0", Some("v1.1.0"));
+        assert_eq!("1.1.0", status.version());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+    }
+
+    #[test]
+    fn semver_selection() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        fixtures(tmp.path(), &["1.0.0", "1.1.0", "2.0.0", "3.0.0-rc.1"]);
+        fs::create_dir(tmp.path().join("nightly")).unwrap();
+        let server = ReleaseServer::start(tmp.path()).unwrap();
+        let latest = |builder: &mut github::UpdateBuilder| {
+            builder
+                .api_url(&server.api_url())
+                .repo_owner("owner")
+                .repo_name("app")
+                .bin_name("app")
+                .current_version("1.0.0")
+                .build()
+                .unwrap()
+                .get_latest_release()
+                .unwrap()
+                .tag
+        };
+
+        assert_eq!("v3.0.0-rc.1", latest(&mut github::Update::configure()));
+        assert_eq!(
+            "v2.0.0",
+            latest(github::Update::configure().latest_by_semver(true))
+        );
+        assert_eq!(
+            "v1.1.0",
+            latest(
+                github::Update::configure()
+                    .release_filter(|release| release.version().starts_with("1."))
+            )
+        );
+    }
+
+    #[test]
+    fn asset_ranking() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.1.0"]);
+        let release_dir = fixture_dir.join("v1.1.0");
+        fs::write(release_dir.join("a-test-target.sha256"), "checksum").unwrap();
+        fs::write(release_dir.join("app-test-target-alt"), "alt").unwrap();
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let configure = || updater(&server, &install_path, "1.0.0");
+        let update = |builder: &mut github::UpdateBuilder| {
+            builder.build().unwrap().update().unwrap();
+            fs::read_to_string(&install_path).unwrap()
+        };
+
+        assert_eq!("1.1.0", update(&mut configure()));
+        assert_eq!(
+            "alt",
+            update(configure().asset_ranker(|name| Some(name.ends_with("-alt") as i64)))
+        );
+
+        fs::write(release_dir.join("app-v1.1.0-test-target"), "templated").unwrap();
+        assert_eq!(
+            "templated",
+            update(configure().asset_name_template("{bin}-{tag}-{target}"))
+        );
+        let unknown = github::Update::configure()
+            .repo_owner("owner")
+            .repo_name("app")
+            .bin_name("app")
+            .current_version("1.0.0")
+            .asset_name_template("{bin}-{arch}")
+            .build();
+        assert!(matches!(unknown, Err(Error::Config(_))));
+
+        fs::write(release_dir.join("app-linux64-1.1.0"), "named").unwrap();
+        assert_eq!(
+            "named",
+            update(
+                configure()
+                    .asset_name_template("{bin}-{tag}-{target}")
+                    .asset_name_for("other-target", "app-test-target-alt")
+                    .asset_name_for("test-target", "app-linux64-{version}")
+            )
+        );
+        assert_eq!(
+            "1.1.0",
+            update(configure().asset_name_for("other-target", "app-linux64-{version}"))
+        );
+        let unknown = github::Update::configure()
+            .repo_owner("owner")
+            .repo_name("app")
+            .bin_name("app")
+            .current_version("1.0.0")
+            .asset_name_for("test-target", "app-{os}")
+            .build();
+        assert!(matches!(unknown, Err(Error::Config(_))));
+
+        let targets = vec!["linux-musl".to_owned(), "linux-gnu".to_owned()];
+        let rank = |name| github::default_asset_rank(name, &targets);
+        assert_eq!(None, rank("app-linux-gnu.tar.gz.sha256"));
+        assert_eq!(None, rank("app-darwin.tar.gz"));
+        assert!(rank("app-linux-musl") > rank("app-linux-gnu.tar.gz"));
+        if !cfg!(windows) {
+            assert!(rank("app-linux-gnu.tar.gz") > rank("app-linux-gnu.zip"));
+            assert!(rank("app-linux-gnu.zip") > rank("app-linux-gnu"));
+        }
+    }
+
+    #[test]
+    fn archive_format() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.1.0"]);
+        let release_dir = fixture_dir.join("v1.1.0");
+        fs::write(release_dir.join("app-test-target.zip"), "zip").unwrap();
+        fs::write(release_dir.join("app-test-target.tar.gz"), "tar").unwrap();
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let configure = || updater(&server, &install_path, "1.0.0");
+        let update = |builder: &mut github::UpdateBuilder| {
+            builder
+                .extractor(ExeOnly)
+                .build()
+                .unwrap()
+                .update()
+                .unwrap();
+            fs::read_to_string(&install_path).unwrap()
+        };
+
+        let native = if cfg!(windows) { "zip" } else { "tar" };
+        assert_eq!(native, update(&mut configure()));
+        for (format, expected) in &[
+            (github::ArchiveFormat::Zip, "zip"),
+            (github::ArchiveFormat::Tar, "tar"),
+        ] {
+            assert_eq!(*expected, update(configure().archive_format(*format)));
+            assert_eq!(
+                *expected,
+                update(
+                    configure()
+                        .archive_format(*format)
+                        .asset_name_template("{bin}-{target}.{ext}")
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn temp_dir_and_cleanup() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_dir = tmp.path().join("bin");
+        let temp_dir = tmp.path().join("temp");
+        fs::create_dir_all(install_dir.join("app_download.stale")).unwrap();
+        fs::create_dir_all(temp_dir.join("app_download.crashed").join("sub")).unwrap();
+        fs::create_dir_all(temp_dir.join("other_download.kept")).unwrap();
+        let update = updater(&server, &install_dir.join("app"), "1.0.0")
+            .temp_dir(&temp_dir)
+            .build()
+            .unwrap();
+        assert_eq!(2, update.cleanup_temp_dirs().unwrap());
+        assert_eq!(0, update.cleanup_temp_dirs().unwrap());
+        assert!(temp_dir.join("other_download.kept").exists());
+
+        assert!(update.update().unwrap().updated());
+        assert_eq!(
+            "1.1.0",
+            fs::read_to_string(install_dir.join("app")).unwrap()
+        );
+        let names = |dir: &Path| {
+            fs::read_dir(dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                .filter(|name| name.starts_with("app_download"))
+                .count()
+        };
+        assert_eq!(0, names(&install_dir));
+        assert_eq!(0, names(&temp_dir));
+    }
+
+    #[test]
+    fn custom_downloader() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let status = updater(&server, &install_path, "1.0.0")
+            .downloader(|url: &str, dest: &mut dyn Write| {
+                assert!(url.ends_with("/download/v1.1.0/app-test-target"));
+                dest.write_all(b"1.1.0")?;
+                Ok(())
+            })
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!("1.1.0", status.version());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| !request.contains("/download/")));
+    }
+
+    #[test]
+    fn asset_digest() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let result = updater(&server, &install_path, "1.0.0")
+            .downloader(|_: &str, dest: &mut dyn Write| {
+                dest.write_all(b"tampered")?;
+                Ok(())
+            })
+            .build()
+            .unwrap()
+            .update();
+        match result {
+            Err(Error::ChecksumMismatch { expected, .. }) => {
+                assert_eq!(format!("{:x}", Sha256::digest(b"1.1.0")), expected)
+            }
+            r => panic!("expected ChecksumMismatch, got {:?}", r),
+        }
+        assert!(!install_path.exists());
+    }
+
+    /// Extracts the executable by copying the asset, fails for any other file
+    struct ExeOnly;
+
+    impl crate::extractor::Extractor for ExeOnly {
+        fn handles(&self, _: &Path) -> bool {
+            true
+        }
+
+        fn extract_file(
+            &self,
+            archive: &Path,
+            into_dir: &Path,
+            file_to_extract: &Path,
+        ) -> Result<PathBuf> {
+            if file_to_extract != Path::new("app") {
+                return Err(Error::ArchiveEntryNotFound(file_to_extract.to_path_buf()));
+            }
+            let out = into_dir.join(file_to_extract);
+            fs::copy(archive, &out)?;
+            Ok(out)
+        }
+    }
+
+    #[test]
+    fn failures_leave_no_artifacts() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        for stage in &["download", "verify", "extract", "replace"] {
+            let install_dir = tmp.path().join(stage);
+            let install_path = install_dir.join("app");
+            fs::create_dir_all(&install_dir).unwrap();
+            if *stage == "replace" {
+                // a directory can't be replaced by the executable
+                fs::create_dir_all(install_path.join("in-the-way")).unwrap();
+            } else {
+                fs::write(&install_path, "1.0.0").unwrap();
+            }
+            let mut builder = updater(&server, &install_path, "1.0.0");
+            match *stage {
+                "download" => {
+                    builder.downloader(|url: &str, dest: &mut dyn Write| {
+                        dest.write_all(b"1.1")?;
+                        Err(Error::HttpStatus {
+                            url: url.to_owned(),
+                            status: 500,
+                        })
+                    });
+                }
+                "verify" => {
+                    builder.downloader(|_: &str, dest: &mut dyn Write| {
+                        dest.write_all(b"tampered")?;
+                        Ok(())
+                    });
+                }
+                "extract" => {
+                    builder
+                        .extractor(ExeOnly)
+                        .companion_file("missing", &install_dir);
+                }
+                _ => (),
+            }
+            let result = builder.build().unwrap().update();
+            match (*stage, &result) {
+                ("download", Err(Error::HttpStatus { .. }))
+                | ("verify", Err(Error::ChecksumMismatch { .. }))
+                | ("extract", Err(Error::ArchiveEntryNotFound(_)))
+                | ("replace", Err(_)) => (),
+                _ => panic!("unexpected {} result {:?}", stage, result),
+            }
+            let entries = fs::read_dir(&install_dir)
+                .unwrap()
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                // the lock file is kept between updates
+                .filter(|name| name != ".app.lock")
+                .collect::<Vec<_>>();
+            assert_eq!(vec!["app".to_owned()], entries, "{}: {:?}", stage, result);
+            if *stage == "replace" {
+                assert!(install_path.join("in-the-way").is_dir());
+            } else {
+                assert_eq!("1.0.0", fs::read_to_string(&install_path).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn keep_old_exe() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let old_exe = tmp.path().join("bin").join("app.old");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        fs::write(&install_path, "1.0.0").unwrap();
+        let update = |current: &str| {
+            updater(&server, &install_path, current)
+                .keep_old_exe(true)
+                .build()
+                .unwrap()
+                .update_extended()
+                .unwrap()
+        };
+        let status = update("1.0.0");
+        let installed = status.installed().unwrap();
+        assert_eq!(Some(&old_exe), installed.old_exe.as_ref());
+        assert!(!installed.restart_required);
+        assert!(installed.pids.is_empty());
+        assert_eq!("1.0.0", fs::read_to_string(&old_exe).unwrap());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+
+        assert!(update("1.1.0").uptodate());
+        assert!(!old_exe.exists());
+    }
+
+    #[test]
+    fn local_changes() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        let update = |local_changes: github::LocalChanges| {
+            updater(&server, &install_path, "1.0.0")
+                .local_changes(local_changes)
+                .build()
+                .unwrap()
+                .update_extended()
+        };
+
+        // confirming a modified executable isn't possible without a terminal
+        fs::write(&install_path, "1.0.0 patched").unwrap();
+        match update(github::LocalChanges::Confirm) {
+            Err(Error::NonInteractive) => (),
+            r => panic!("expected NonInteractive, got {:?}", r.map(|s| s.updated())),
+        }
+        assert_eq!("1.0.0 patched", fs::read_to_string(&install_path).unwrap());
+        assert!(update(github::LocalChanges::Warn).unwrap().updated());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+
+        fs::write(&install_path, "1.0.0").unwrap();
+        assert!(update(github::LocalChanges::Confirm).unwrap().updated());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+    }
+
+    #[test]
+    fn spawned_update() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        fs::write(&install_path, "1.0.0").unwrap();
+        let hook_calls = Arc::new(Mutex::new(0));
+        let calls = hook_calls.clone();
+        let update = updater(&server, &install_path, "1.0.0")
+            .on_progress(move |_| *calls.lock().unwrap() += 1)
+            .build()
+            .unwrap()
+            .spawn();
+        let phases = update
+            .progress()
+            .iter()
+            .map(|progress| progress.phase)
+            .collect::<Vec<_>>();
+        assert!(update.join().unwrap().updated());
+        assert_eq!(Some(&Phase::Checking), phases.first());
+        assert_eq!(Some(&Phase::Done), phases.last());
+        assert_eq!(phases.len(), *hook_calls.lock().unwrap());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+    }
+
+    #[test]
+    fn download_cache() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let cache = tmp.path().join("cache");
+        let cached = cache
+            .join("1.1.0")
+            .join("test-target")
+            .join("app-test-target");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        let update = || {
+            fs::write(&install_path, "1.0.0").unwrap();
+            updater(&server, &install_path, "1.0.0")
+                .download_cache(&cache)
+                .build()
+                .unwrap()
+                .update()
+                .unwrap();
+            assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+        };
+        let downloads = || {
+            server
+                .requests()
+                .iter()
+                .filter(|path| path.starts_with("/download/"))
+                .count()
+        };
+
+        update();
+        assert_eq!(1, downloads());
+        assert_eq!("1.1.0", fs::read_to_string(&cached).unwrap());
+        update();
+        assert_eq!(1, downloads());
+
+        // a corrupted archive is downloaded again
+        fs::write(&cached, "1.1.1").unwrap();
+        update();
+        assert_eq!(2, downloads());
+        assert_eq!("1.1.0", fs::read_to_string(&cached).unwrap());
+    }
+
+    #[test]
+    fn resumed_download() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        let content = (0..10_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        fs::create_dir_all(fixture_dir.join("v1.1.0")).unwrap();
+        fs::write(fixture_dir.join("v1.1.0").join("app-test-target"), &content).unwrap();
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let url = format!("{}/download/v1.1.0/app-test-target", server.api_url());
+        let etag = format!("\"{:x}\"", Sha256::digest(&content));
+        let partial = tmp.path().join("partial").join("app-test-target");
+        let state = tmp.path().join("partial").join("app-test-target.json");
+        let dest = tmp.path().join("app");
+        let interrupted = |prefix: &[u8], etag: &str| {
+            fs::write(&partial, prefix).unwrap();
+            let state_json = json!({"url": url, "etag": etag});
+            fs::write(&state, serde_json::to_vec(&state_json).unwrap()).unwrap();
+        };
+
+        Download::from_url(&url)
+            .resume_from(&partial)
+            .download_to_file(&dest)
+            .unwrap();
+        assert_eq!(content, fs::read(&dest).unwrap());
+        assert!(!partial.exists() && !state.exists());
+
+        // only the rest is downloaded, after the bytes already in the partial file
+        interrupted(&[0xff; 4000], &etag);
+        Download::from_url(&url)
+            .resume_from(&partial)
+            .download_to_file(&dest)
+            .unwrap();
+        assert_eq!(&[0xff; 4000][..], &fs::read(&dest).unwrap()[..4000]);
+        assert_eq!(&content[4000..], &fs::read(&dest).unwrap()[4000..]);
+
+        // the checksum covers the bytes downloaded before
+        let sum = format!("{:x}", Sha256::digest(&content));
+        interrupted(&content[..4000], &etag);
+        Download::from_url(&url)
+            .resume_from(&partial)
+            .checksum(Algorithm::Sha256, &sum)
+            .download_to_file(&dest)
+            .unwrap();
+        assert_eq!(content, fs::read(&dest).unwrap());
+        interrupted(&[0xff; 4000], &etag);
+        let result = Download::from_url(&url)
+            .resume_from(&partial)
+            .checksum(Algorithm::Sha256, &sum)
+            .download_to_file(&dest);
+        assert!(matches!(result, Err(Error::ChecksumMismatch { .. })));
+        assert!(!partial.exists() && !state.exists() && !dest.exists());
+
+        // a changed asset is downloaded again
+        interrupted(&[0xff; 4000], "\"changed\"");
+        Download::from_url(&url)
+            .resume_from(&partial)
+            .download_to_file(&dest)
+            .unwrap();
+        assert_eq!(content, fs::read(&dest).unwrap());
+
+        // a partial file left complete, or too large, is downloaded again
+        for len in &[content.len(), content.len() + 100] {
+            let mut prefix = vec![0xff; *len];
+            prefix[..4000].copy_from_slice(&content[..4000]);
+            interrupted(&prefix, &etag);
+            Download::from_url(&url)
+                .resume_from(&partial)
+                .checksum(Algorithm::Sha256, &sum)
+                .download_to_file(&dest)
+                .unwrap();
+            assert_eq!(content, fs::read(&dest).unwrap());
+            assert!(!partial.exists() && !state.exists());
+        }
+
+        // through the updater
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        fs::write(&install_path, "1.0.0").unwrap();
+        let cache = tmp.path().join("cache");
+        fs::create_dir_all(cache.join("partial")).unwrap();
+        fs::write(
+            cache.join("partial").join("app-test-target"),
+            &content[..4000],
+        )
+        .unwrap();
+        let state_json = json!({"url": url, "etag": etag});
+        fs::write(
+            cache.join("partial").join("app-test-target.json"),
+            serde_json::to_vec(&state_json).unwrap(),
+        )
+        .unwrap();
+        updater(&server, &install_path, "1.0.0")
+            .download_cache(&cache)
+            .resume_downloads(true)
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!(content, fs::read(&install_path).unwrap());
+        assert!(!cache.join("partial").join("app-test-target").exists());
+    }
+
+    #[test]
+    fn total_timeout() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        fs::write(&install_path, "1.0.0").unwrap();
+        let result = updater(&server, &install_path, "1.0.0")
+            .total_timeout(Duration::from_millis(500))
+            .downloader(|_: &str, dest: &mut dyn Write| {
+                std::thread::sleep(Duration::from_millis(600));
+                dest.write_all(b"1.1.0")?;
+                Ok(())
+            })
+            .build()
+            .unwrap()
+            .update();
+        match result {
+            Err(Error::TimedOut(timeout)) => assert_eq!(Duration::from_millis(500), timeout),
+            r => panic!("expected TimedOut, got {:?}", r),
+        }
+        assert_eq!("1.0.0", fs::read_to_string(&install_path).unwrap());
+    }
+
+    #[test]
+    fn expired_download_url() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let attempts = std::sync::atomic::AtomicUsize::new(0);
+        updater(&server, &install_path, "1.0.0")
+            .downloader(move |url: &str, dest: &mut dyn Write| {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return Err(Error::HttpStatus {
+                        url: url.to_owned(),
+                        status: 403,
+                    });
+                }
+                dest.write_all(b"1.1.0")?;
+                Ok(())
+            })
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.ends_with("/releases/tags/v1.1.0")));
+    }
+
+    #[test]
+    fn authenticated_asset_download() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        updater(&server, &install_path, "1.0.0")
+            .auth_token("secret")
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+        let requests = server.requests();
+        assert!(requests
+            .iter()
+            .any(|request| request.ends_with("/releases/assets/v1.1.0/app-test-target")));
+        assert!(requests
+            .iter()
+            .all(|request| !request.contains("/download/")));
+    }
+
+    #[test]
+    fn attempt_records() {
+        use crate::errors::ErrorCategory;
+        use crate::telemetry::{Outcome, UpdateRecord};
+
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let update = |version: &str, tag: Option<&str>| {
+            let records = records.clone();
+            let mut builder = updater(&server, &install_path, version);
+            builder.on_attempt(move |record: &UpdateRecord| {
+                records.lock().unwrap().push(record.clone())
+            });
+            if let Some(tag) = tag {
+                builder.target_version_tag(tag);
+            }
+            builder.build().unwrap().update()
+        };
+        update("1.0.0", None).unwrap();
+        update("1.1.0", None).unwrap();
+        assert!(update("1.1.0", Some("v9.9.9")).is_err());
+
+        let records = records.lock().unwrap();
+        let summary = records
+            .iter()
+            .map(|r| {
+                (
+                    r.current_version.as_str(),
+                    r.new_version.as_deref(),
+                    r.outcome,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("1.0.0", Some("1.1.0"), Outcome::Updated),
+                ("1.1.0", None, Outcome::UpToDate),
+                ("1.1.0", None, Outcome::Failed(ErrorCategory::NotFound)),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn select_release() {
+        if crate::is_interactive() {
+            return;
+        }
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0", "1.2.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        let update = |non_interactive: NonInteractive| {
+            updater(&server, &install_path, "1.2.0")
+                .release_filter(|release| release.version() != "1.2.0")
+                .select_release(5)
+                .non_interactive(non_interactive)
+                .build()
+                .unwrap()
+                .update()
+        };
+        match update(NonInteractive::Abort) {
+            Err(Error::NonInteractive) => (),
+            r => panic!("expected NonInteractive, got {:?}", r),
+        }
+        // without a terminal, the most recent release the filter accepts
+        let status = update(NonInteractive::Proceed).unwrap();
+        assert_eq!("1.1.0", status.version());
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+    }
+
+    #[test]
+    fn checksum_verification() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0", "1.1.0"]);
+        let release_dir = fixture_dir.join("v1.1.0");
+        let sha256 = Algorithm::Sha256
+            .file_checksum(release_dir.join("app-test-target"))
+            .unwrap();
+        fs::write(
+            release_dir.join("SHA256SUMS"),
+            format!("{}  app-test-target\n", sha256),
+        )
+        .unwrap();
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        let update = || {
+            let mut builder = updater(&server, &install_path, "1.0.0");
+            builder.verify_checksum(true);
+            builder
+        };
+
+        fs::write(&install_path, "1.0.0").unwrap();
+        update().build().unwrap().update().unwrap();
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+
+        // the stronger algorithm is preferred
+        fs::write(release_dir.join("app-test-target.sha512"), "0".repeat(128)).unwrap();
+        fs::write(&install_path, "1.0.0").unwrap();
+        match update().build().unwrap().update() {
+            Err(Error::ChecksumMismatch { .. }) => (),
+            r => panic!("expected ChecksumMismatch, got {:?}", r),
+        }
+        assert_eq!("1.0.0", fs::read_to_string(&install_path).unwrap());
+        update()
+            .checksum_algorithm(Algorithm::Sha256)
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+
+        let blake3 = update().checksum_algorithm(Algorithm::Blake3).build();
+        if cfg!(feature = "checksum-blake3") {
+            match blake3.unwrap().update() {
+                Err(Error::Release(_)) => (),
+                r => panic!("expected no BLAKE3 checksum, got {:?}", r),
+            }
+        } else {
+            assert!(blake3.is_err());
+        }
+    }
+
+    #[test]
+    fn delta_update() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        let old = b"#!/bin/sh\necho 'version 1.0.0'\n".repeat(20);
+        let new = b"#!/bin/sh\necho 'version 1.1.0'\n".repeat(20);
+        let diff = |new: &[u8]| {
+            let mut patch = vec![];
+            bsdiff::diff(&old, new, &mut patch).expect("diff fail");
+            patch
+        };
+        let release_dir = fixture_dir.join("v1.1.0");
+        fs::create_dir_all(&release_dir).unwrap();
+        fs::write(release_dir.join("app-test-target"), &new).unwrap();
+        fs::write(
+            release_dir.join("app-test-target.from-1.0.0.bsdiff"),
+            diff(&new),
+        )
+        .unwrap();
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        let update = || {
+            fs::write(&install_path, &old).unwrap();
+            updater(&server, &install_path, "1.0.0")
+                .delta_updates(true)
+                .build()
+                .unwrap()
+                .update()
+                .unwrap();
+            assert_eq!(new, fs::read(&install_path).unwrap());
+        };
+        let downloaded = |name: &str| {
+            server
+                .requests()
+                .iter()
+                .filter(|path| path.ends_with(&format!("/{}", name)))
+                .count()
+        };
+
+        // the patched executable matches the digest of the asset
+        update();
+        assert_eq!(1, downloaded("app-test-target.from-1.0.0.bsdiff"));
+        assert_eq!(0, downloaded("app-test-target"));
+
+        // a patch producing another executable falls back to the full download
+        fs::write(
+            release_dir.join("app-test-target.from-1.0.0.bsdiff"),
+            diff(b"tampered"),
+        )
+        .unwrap();
+        update();
+        assert_eq!(2, downloaded("app-test-target.from-1.0.0.bsdiff"));
+        assert_eq!(1, downloaded("app-test-target"));
+
+        // the patch of an archive is verified against its own digest
+        fs::remove_file(release_dir.join("app-test-target")).unwrap();
+        fs::remove_file(release_dir.join("app-test-target.from-1.0.0.bsdiff")).unwrap();
+        fs::write(release_dir.join("app-test-target.tar.gz"), &new).unwrap();
+        fs::write(
+            release_dir.join("app-test-target.tar.gz.from-1.0.0.bsdiff"),
+            diff(&new),
+        )
+        .unwrap();
+        fs::write(&install_path, &old).unwrap();
+        updater(&server, &install_path, "1.0.0")
+            .delta_updates(true)
+            .extractor(ExeOnly)
+            .build()
+            .unwrap()
+            .update()
+            .unwrap();
+        assert_eq!(new, fs::read(&install_path).unwrap());
+        assert_eq!(1, downloaded("app-test-target.tar.gz.from-1.0.0.bsdiff"));
+        assert_eq!(0, downloaded("app-test-target.tar.gz"));
+    }
+
+    #[test]
+    fn differential_update() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        let old = (0..4096).map(|i| (i * 7 % 251) as u8).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new[1000..1010].copy_from_slice(b"1.1.0 1.1.");
+        let release_dir = fixture_dir.join("v1.1.0");
+        fs::create_dir_all(&release_dir).unwrap();
+        fs::write(release_dir.join("app-test-target"), &new).unwrap();
+        let write_index = |indexed: &[u8]| {
+            let index = BlockIndex::from_bytes(indexed, 64).unwrap();
+            fs::write(
+                release_dir.join("app-test-target.blocks.json"),
+                index.to_json(),
+            )
+            .unwrap();
+        };
+        write_index(&new);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        let update = || {
+            fs::write(&install_path, &old).unwrap();
+            updater(&server, &install_path, "1.0.0")
+                .differential_download(true)
+                .build()
+                .unwrap()
+                .update()
+                .unwrap();
+            assert_eq!(new, fs::read(&install_path).unwrap());
+        };
+        let downloaded = |name: &str| {
+            server
+                .requests()
+                .iter()
+                .filter(|path| path.ends_with(&format!("/{}", name)))
+                .count()
+        };
+
+        // only the changed block is downloaded, then checked against the digest
+        update();
+        assert_eq!(1, downloaded("app-test-target.blocks.json"));
+        assert_eq!(1, downloaded("app-test-target"));
+
+        // an index of another executable that rebuilds consistently with it is caught
+        // by the digest of the asset, and falls back to the full download
+        write_index(&old);
+        update();
+        assert_eq!(2, downloaded("app-test-target.blocks.json"));
+        assert_eq!(2, downloaded("app-test-target"));
+    }
+
+    #[test]
+    fn install_from_archive() {
+        let tmp = TempDir::new("self_update_test_server").expect("tempdir fail");
+        let fixture_dir = tmp.path().join("fixtures");
+        fixtures(&fixture_dir, &["1.0.0"]);
+        let server = ReleaseServer::start(&fixture_dir).unwrap();
+        let install_path = tmp.path().join("bin").join("app");
+        fs::create_dir_all(install_path.parent().unwrap()).unwrap();
+        let archive = tmp.path().join("app-test-target");
+        fs::write(&archive, "1.1.0").unwrap();
+        let sum = format!("{:x}", Sha256::digest(b"1.1.0"));
+        let install = |verify: bool, checksum: Option<(Algorithm, &str)>| {
+            fs::write(&install_path, "1.0.0").unwrap();
+            updater(&server, &install_path, "1.0.0")
+                .verify_checksum(verify)
+                .build()
+                .unwrap()
+                .install_from_archive(&archive, checksum)
+        };
+
+        install(false, None).unwrap();
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+        install(true, Some((Algorithm::Sha256, &sum))).unwrap();
+        assert_eq!("1.1.0", fs::read_to_string(&install_path).unwrap());
+
+        match install(true, None) {
+            Err(Error::Config(_)) => (),
+            r => panic!("expected Config, got {:?}", r),
+        }
+        assert_eq!("1.0.0", fs::read_to_string(&install_path).unwrap());
+        let wrong = "0".repeat(64);
+        match install(false, Some((Algorithm::Sha256, &wrong))) {
+            Err(Error::ChecksumMismatch { .. }) => (),
+            r => panic!("expected ChecksumMismatch, got {:?}", r),
+        }
+        assert_eq!("1.0.0", fs::read_to_string(&install_path).unwrap());
+        assert!(server.requests().is_empty());
+    }
+}